@@ -83,9 +83,33 @@ fn dilate_4k_image_crate(c: &mut Criterion) {
         });
 }
 
+fn dilate_4k_large_l1_rle(c: &mut Criterion) {
+        let rle = RLE::from(&clone_to_image(&load_image("benches/slice000.png")));
+        let dilate = RLE::l1_structuring(20);
+        let rle_clone = rle.clone();
+        c.bench_function("4k_large_l1_rle", move |b| {
+            b.iter(|| rle_clone.dilate(&dilate))
+        });
+        c.bench_function("4k_large_l1_rle_decomposed", move |b| {
+            b.iter(|| rle.dilate_l1(20))
+        });
+}
+
+fn dilate_4k_large_linf_rle(c: &mut Criterion) {
+        let rle = RLE::from(&clone_to_image(&load_image("benches/slice000.png")));
+        let dilate = RLE::linf_structuring(20);
+        let rle_clone = rle.clone();
+        c.bench_function("4k_large_linf_rle", move |b| {
+            b.iter(|| rle_clone.dilate(&dilate))
+        });
+        c.bench_function("4k_large_linf_rle_decomposed", move |b| {
+            b.iter(|| rle.dilate_linf(20))
+        });
+}
+
 criterion_group! {
     dilate, dilate_small_rle,dilate_4k_rle, dilate_small_image_crate,
-    dilate_4k_image_crate
+    dilate_4k_image_crate, dilate_4k_large_l1_rle, dilate_4k_large_linf_rle
 }
 
 criterion_main!(dilate);
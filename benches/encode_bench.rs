@@ -0,0 +1,38 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rle_morph::RLE;
+
+/// 4K mask with few filled rectangles, most of rows are empty.
+fn sparse_4k() -> Vec<u8> {
+    let (w, h) = (3840, 2160);
+    let mut data = vec![0; w * h];
+    for &(x0, y0, size) in &[(100, 200, 300), (2000, 900, 150), (3500, 1800, 40)] {
+        for y in y0..y0 + size {
+            for x in x0..x0 + size {
+                data[y * w + x] = 255;
+            }
+        }
+    }
+    data
+}
+
+/// Mask with short runs in every row.
+fn dense(w: usize, h: usize) -> Vec<u8> {
+    (0..w * h).map(|i| ((i * 7919) % 13 < 6) as u8).collect()
+}
+
+fn encode_sparse(c: &mut Criterion) {
+    let data = sparse_4k();
+    c.bench_function("encode_sparse_4k", move |b| {
+        b.iter(|| RLE::from_raw_data(3840, 2160, black_box(&data)))
+    });
+}
+
+fn encode_dense(c: &mut Criterion) {
+    let data = dense(1024, 1024);
+    c.bench_function("encode_dense_1k", move |b| {
+        b.iter(|| RLE::from_raw_data(1024, 1024, black_box(&data)))
+    });
+}
+
+criterion_group!(benches, encode_sparse, encode_dense);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::path::Path;
+use rle_morph::{RLE, Image, StructuringElement};
+use image::GrayImage;
+
+fn load_image<P: AsRef<Path>>(path: P) -> GrayImage {
+    image::open(path).unwrap().into_luma8()
+}
+
+fn clone_to_image(img: &GrayImage) -> Image {
+    let clone = img.clone();
+    let (width, height) = img.dimensions();
+    Image::new(width as _, height as _, clone.into_raw())
+}
+
+fn dilate_4k_lines(c: &mut Criterion) {
+        let rle = RLE::from(&clone_to_image(&load_image("benches/slice000.png")));
+        let rle_clone = rle.clone();
+        c.bench_function("4k_hline_rle", move |b| {
+            b.iter(|| rle_clone.dilate_h(41))
+        });
+        let rle_clone = rle.clone();
+        c.bench_function("4k_vline_rle", move |b| {
+            b.iter(|| rle_clone.dilate_v(41))
+        });
+        let rle_clone = rle.clone();
+        c.bench_function("4k_erode_vline_rle", move |b| {
+            b.iter(|| rle_clone.erode_v(41))
+        });
+}
+
+fn dilate_4k_box(c: &mut Criterion) {
+        let rle = RLE::from(&clone_to_image(&load_image("benches/slice000.png")));
+        // cross shape is not line, so all primary runs are sorted
+        let mut cross = RLE::new(41, 41);
+        rle_morph::draw::filled_rect(&mut cross, 0, 20, 41, 1);
+        rle_morph::draw::filled_rect(&mut cross, 20, 0, 1, 41);
+        let cross = StructuringElement::centered(cross);
+        let rle_clone = rle.clone();
+        c.bench_function("4k_cross_rle", move |b| {
+            b.iter(|| rle_clone.dilate(&cross))
+        });
+        let rle_clone = rle.clone();
+        c.bench_function("4k_box_rle", move |b| {
+            b.iter(|| rle_clone.dilate(&RLE::linf_structuring(20)))
+        });
+        c.bench_function("4k_box_separable_rle", move |b| {
+            b.iter(|| rle.dilate_h(41).dilate_v(41))
+        });
+}
+
+criterion_group! {
+    line, dilate_4k_lines, dilate_4k_box
+}
+
+criterion_main!(line);
@@ -0,0 +1,46 @@
+/* C API of rle_morph, available when crate is built with `ffi` feature. */
+#ifndef RLE_MORPH_H
+#define RLE_MORPH_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque run length encoded binary image. */
+typedef struct RLE RLE;
+
+/* Encode 8-bit image with rows `stride` bytes apart, pixels greater than 0 are 1.
+ * Returns NULL if data is NULL or stride < width. */
+RLE *rle_encode(const uint8_t *data, size_t width, size_t height, size_t stride);
+
+/* Release image returned by any function of this library, NULL is ignored. */
+void rle_free(RLE *rle);
+
+size_t rle_width(const RLE *rle);
+size_t rle_height(const RLE *rle);
+/* Number of pixels set to 1. */
+size_t rle_area(const RLE *rle);
+
+/* Square and diamond structuring elements of size 2k + 1. */
+RLE *rle_linf_structuring(size_t k);
+RLE *rle_l1_structuring(size_t k);
+
+/* Morphology and boolean operations return new image or NULL on invalid arguments
+ * (NULL handles, coordinate overflow, different dimensions of operands). */
+RLE *rle_dilate(const RLE *rle, const RLE *se);
+RLE *rle_erode(const RLE *rle, const RLE *se);
+RLE *rle_and(const RLE *a, const RLE *b);
+RLE *rle_or(const RLE *a, const RLE *b);
+
+/* Decode image into buffer of width * height bytes, pixels set to 1 get pixel_val.
+ * Returns 0 on success and -1 on invalid arguments. */
+int rle_to_buffer(const RLE *rle, uint8_t *out, size_t len, uint8_t pixel_val);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RLE_MORPH_H */
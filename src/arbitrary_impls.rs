@@ -0,0 +1,135 @@
+//! `Arbitrary` implementations used for fuzzing and property tests.
+//! Enabled with `arbitrary` feature.
+use super::{Run, StructuringElement, RLE};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Maximum width and height of generated images.
+const MAX_DIM: usize = 64;
+/// Maximum k of generated structuring elements (dimension is 2 * k + 1).
+const MAX_STRUCTURING_K: usize = 3;
+
+impl<'a> Arbitrary<'a> for Run {
+    /// Generate valid run (x_start <= x_end) with non-negative coordinates.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let x_start = u.arbitrary::<u16>()? as i32;
+        let len = u.arbitrary::<u16>()? as i32;
+        let y = u.arbitrary::<u16>()? as i32;
+        Ok(Run { x_start, x_end: x_start + len, y })
+    }
+}
+
+impl<'a> Arbitrary<'a> for RLE {
+    /// Generate RLE with canonical runs (sorted, inside image, not overlapping or touching).
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let width = u.int_in_range(0..=MAX_DIM)?;
+        let height = u.int_in_range(0..=MAX_DIM)?;
+        arbitrary_rle(u, width, height)
+    }
+}
+
+impl RLE {
+    /// Generate arbitrary non empty structuring element with odd dimensions
+    /// and canonical runs.
+    pub fn arbitrary_structuring(u: &mut Unstructured<'_>) -> Result<Self> {
+        let dim = 2 * u.int_in_range(0..=MAX_STRUCTURING_K)? + 1;
+        let mut s = arbitrary_rle(u, dim, dim)?;
+        if s.runs.is_empty() {
+            // structuring element without pixels would erase everything, use center
+            let center = (dim / 2) as i32;
+            s.runs.push(Run { x_start: center, x_end: center, y: center });
+        }
+        Ok(s)
+    }
+}
+
+impl<'a> Arbitrary<'a> for StructuringElement {
+    /// Generate non empty structuring element of any (also even) width and height up
+    /// to 2 * MAX_STRUCTURING_K + 1 with anchor inside of its dimensions.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let width = u.int_in_range(1..=2 * MAX_STRUCTURING_K + 1)?;
+        let height = u.int_in_range(1..=2 * MAX_STRUCTURING_K + 1)?;
+        let ax = u.int_in_range(0..=width - 1)? as i32;
+        let ay = u.int_in_range(0..=height - 1)? as i32;
+        let mut shape = arbitrary_rle(u, width, height)?;
+        if shape.runs.is_empty() {
+            // element without pixels would erase everything, use anchor
+            shape.runs.push(Run { x_start: ax, x_end: ax, y: ay });
+        }
+        Ok(StructuringElement::new(shape, ax, ay))
+    }
+}
+
+/// Generate canonical RLE with given dimensions.
+fn arbitrary_rle(u: &mut Unstructured<'_>, width: usize, height: usize) -> Result<RLE> {
+    let mut runs = Vec::new();
+    for y in 0..height as i32 {
+        let mut x = 0;
+        // when data is exhausted bool is false so generation stops
+        while x < width && u.arbitrary::<bool>()? {
+            let x_start = x + u.int_in_range(0..=width - x - 1)?;
+            let len = u.int_in_range(1..=width - x_start)?;
+            runs.push(Run { x_start: x_start as _, x_end: (x_start + len - 1) as _, y });
+            // leave at least one 0 pixel between runs
+            x = x_start + len + 1;
+        }
+    }
+    Ok(RLE { width, height, runs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::XorShift;
+    use crate::Kernel;
+
+    fn bytes(seed: u32, len: usize) -> Vec<u8> {
+        XorShift::new(seed).take(len).map(|state| state as u8).collect()
+    }
+
+    #[test]
+    fn arbitrary_rle_is_canonical_test() {
+        for seed in 1..200 {
+            let data = bytes(seed, 1024);
+            let mut u = Unstructured::new(&data);
+            let rle = RLE::arbitrary(&mut u).unwrap();
+            assert!(rle.is_canonical());
+            assert_eq!(RLE::from(&rle.to_image(1)).runs(), rle.runs());
+        }
+    }
+
+    #[test]
+    fn arbitrary_structuring_test() {
+        for seed in 1..200 {
+            let data = bytes(seed, 256);
+            let mut u = Unstructured::new(&data);
+            let s = RLE::arbitrary_structuring(&mut u).unwrap();
+            assert!(s.is_canonical());
+            assert!(!s.runs().is_empty());
+            assert_eq!(s.width() % 2, 1);
+            assert_eq!(s.width(), s.height());
+        }
+    }
+
+    #[test]
+    fn arbitrary_structuring_element_test() {
+        for seed in 1..200 {
+            let data = bytes(seed, 256);
+            let mut u = Unstructured::new(&data);
+            let s = StructuringElement::arbitrary(&mut u).unwrap();
+            let (ax, ay) = s.anchor();
+            assert!(s.shape().is_canonical() && !s.shape().is_empty());
+            assert!((0..s.width() as i32).contains(&ax) && (0..s.height() as i32).contains(&ay));
+        }
+    }
+
+    #[test]
+    fn arbitrary_run_test() {
+        let data = bytes(7, 64);
+        let mut u = Unstructured::new(&data);
+        for _ in 0..10 {
+            let run = Run::arbitrary(&mut u).unwrap();
+            assert!(run.x_start <= run.x_end);
+            assert!(run.x_start >= 0 && run.y >= 0);
+        }
+    }
+}
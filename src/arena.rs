@@ -0,0 +1,94 @@
+//! Morphology with temporary runs allocated in `bumpalo::Bump` arena, enabled with
+//! `bumpalo` feature. Only runs of result come from global allocator, so when arena
+//! is reset after every frame hot loops reuse same memory for all temporaries.
+use super::rle::{clip_runs, push_shifted_runs};
+use super::{coord, union_iter, FlipBitsIter, Kernel, RleError, Run, RLE};
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+
+impl RLE {
+    /// Same as `dilate` but primary runs are allocated in bump.
+    /// If some coordinate of result does not fit in i32 this method will panic.
+    pub fn dilate_in<K: Kernel + ?Sized>(&self, s: &K, bump: &Bump) -> Self {
+        match dilate_runs_in(&self.runs, s, bump) {
+            Ok(runs) => Self { width: self.width, height: self.height, runs: runs.iter().copied().collect() },
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Same as `erode` but complement of self and primary runs of its dilation are
+    /// allocated in bump.
+    /// If some coordinate does not fit in i32 this method will panic.
+    pub fn erode_in<K: Kernel + ?Sized>(&self, s: &K, bump: &Bump) -> Self {
+        let (width, height) = match (coord::dimension(self.width), coord::dimension(self.height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            (Err(e), _) | (_, Err(e)) => panic!("{}", e),
+        };
+        let mut canonical = BumpVec::from_iter_in(self.runs.iter().copied(), bump);
+        let len = clip_runs(&mut canonical, width, height);
+        canonical.truncate(len);
+        let complement = BumpVec::from_iter_in(FlipBitsIter::from_runs(&canonical, self.width, self.height), bump);
+        let mut dilated = match dilate_runs_in(&complement, s, bump) {
+            Ok(runs) => runs,
+            Err(e) => panic!("{}", e),
+        };
+        let len = clip_runs(&mut dilated, width, height);
+        dilated.truncate(len);
+        Self {
+            width: self.width,
+            height: self.height,
+            runs: FlipBitsIter::from_runs(&dilated, self.width, self.height).collect(),
+        }
+    }
+}
+
+/// Merged primary runs of dilation, runs shifted by every run of s (see
+/// `shifted_runs`) are merged pairwise as in `dilate` but all buffers are in bump.
+fn dilate_runs_in<'bump, K: Kernel + ?Sized>(runs: &[Run], s: &K, bump: &'bump Bump) -> Result<BumpVec<'bump, Run>, RleError> {
+    let anchor = s.anchor();
+    let mut stack: Vec<(BumpVec<Run>, usize)> = Vec::new();
+    for &b in s.kernel_runs() {
+        let mut merged: BumpVec<Run> = BumpVec::with_capacity_in(runs.len(), bump);
+        push_shifted_runs(runs, b, anchor, &mut merged)?;
+        let mut count = 1;
+        while stack.last().map(|&(_, top_count)| top_count) == Some(count) {
+            let (top, top_count) = stack.pop().unwrap();
+            merged = BumpVec::from_iter_in(union_iter(top.iter().copied(), merged.iter().copied()), bump);
+            count += top_count;
+        }
+        stack.push((merged, count));
+    }
+    let mut res = BumpVec::new_in(bump);
+    while let Some((top, _)) = stack.pop() {
+        res = BumpVec::from_iter_in(union_iter(top.iter().copied(), res.iter().copied()), bump);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Image, StructuringElement};
+
+    #[test]
+    fn arena_test() {
+        let rle = RLE::from(&Image::new(8, 6, vec![
+            0, 0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 1, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0, 0,
+            0, 1, 1, 0, 1, 1, 1, 0,
+            1, 1, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0, 0, 1,
+        ]));
+        let se = StructuringElement::new(RLE::from_raw_data(2, 2, &[1, 1, 0, 1]), 0, 1);
+        let mut bump = Bump::new();
+        for _ in 0..3 {
+            assert_eq!(rle.dilate_in(&RLE::l1_structuring(1), &bump).runs(), rle.dilate(&RLE::l1_structuring(1)).runs());
+            assert_eq!(rle.dilate_in(&se, &bump).runs(), rle.dilate(&se).runs());
+            assert_eq!(rle.erode_in(&RLE::linf_structuring(1), &bump).runs(), rle.erode(&RLE::linf_structuring(1)).runs());
+            let dilated = rle.dilate(&se);
+            assert_eq!(dilated.erode_in(&se, &bump), dilated.erode(&se));
+            bump.reset();
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use super::RLE;
+use std::fmt;
+
+/// Largest number of columns and rows printed by `Display`, larger masks are downsampled.
+const DISPLAY_MAX_SIZE: (usize, usize) = (80, 40);
+
+impl RLE {
+    /// Render mask as text with one line per row, pixels set to 1 are fg and other
+    /// pixels are bg.
+    pub fn render_ascii(&self, fg: char, bg: char) -> String {
+        self.render_ascii_scaled(fg, bg, self.width, self.height)
+    }
+
+    /// Render mask as text with at most max_width columns and max_height lines.
+    /// Mask is downsampled by same integer factor in both directions and character
+    /// is fg if any pixel of its block is set.
+    pub fn render_ascii_scaled(&self, fg: char, bg: char, max_width: usize, max_height: usize) -> String {
+        let factor = std::cmp::max(
+            self.width.div_ceil(max_width.max(1)),
+            self.height.div_ceil(max_height.max(1)),
+        ).max(1);
+        let (width, height) = (self.width.div_ceil(factor), self.height.div_ceil(factor));
+        let indexed = self.indexed();
+        let mut res = String::with_capacity((width + 1) * height);
+        let mut line = vec![false; width];
+        for row in 0..height {
+            line.iter_mut().for_each(|cell| *cell = false);
+            let y_start = (row * factor) as i32;
+            for run in indexed.rows(y_start, y_start + factor as i32 - 1) {
+                for cell in &mut line[run.x_start as usize / factor..=run.x_end as usize / factor] {
+                    *cell = true;
+                }
+            }
+            res.extend(line.iter().map(|&set| if set { fg } else { bg }));
+            res.push('\n');
+        }
+        res
+    }
+}
+
+/// Mask drawn with '#' for 1 and '.' for 0, one line per row. Masks larger than
+/// 80x40 are downsampled, see `render_ascii_scaled`.
+impl fmt::Display for RLE {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_ascii_scaled('#', '.', DISPLAY_MAX_SIZE.0, DISPLAY_MAX_SIZE.1))
+    }
+}
+
+/// Alternate form (`{:#?}`) lists runs grouped by row as `y: x_start..=x_end ...`.
+impl fmt::Debug for RLE {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return f.debug_struct("RLE")
+                .field("width", &self.width)
+                .field("height", &self.height)
+                .field("runs", &self.runs)
+                .finish();
+        }
+        writeln!(f, "RLE {}x{} {{", self.width, self.height)?;
+        for (y, runs) in self.rows() {
+            write!(f, "    {}:", y)?;
+            for run in runs {
+                write!(f, " {}..={}", run.x_start, run.x_end)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Image, Run};
+
+    #[test]
+    fn render_test() {
+        let rle = RLE::from(&Image::new(6, 3, vec![
+            0, 1, 1, 0, 0, 1,
+            0, 0, 0, 0, 0, 0,
+            1, 1, 0, 1, 1, 0,
+        ]));
+        assert_eq!(rle.render_ascii('x', ' '), " xx  x\n      \nxx xx \n");
+        assert_eq!(rle.to_string(), ".##..#\n......\n##.##.\n");
+        // blocks of 2x2 pixels
+        assert_eq!(rle.render_ascii_scaled('#', '.', 3, 3), "###\n###\n");
+        assert_eq!(RLE::new(0, 0).to_string(), "");
+        let large = RLE::ones(800, 100);
+        assert_eq!(large.to_string(), format!("{}\n", "#".repeat(80)).repeat(10));
+    }
+
+    #[test]
+    fn debug_test() {
+        let rle = RLE::from_runs(6, 3, vec![Run::new(1, 2, 0), Run::new(5, 5, 0), Run::new(-2, 1, 2)]);
+        assert_eq!(format!("{:#?}", rle), "RLE 6x3 {\n    0: 1..=2 5..=5\n    2: -2..=1\n}");
+        assert!(format!("{:?}", rle).starts_with("RLE { width: 6, height: 3, runs: [Run {"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Command line tool for morphological operations on PNG images.
+//! Pixels with value greater than 0 are treated as 1, output images use 255 for 1.
+use image::{DynamicImage, ImageOutputFormat};
+use rle_morph::{Connectivity, RLE};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+const USAGE: &str = r#"Usage: rle-morph COMMAND [OPTIONS] INPUT [INPUT2]
+
+Commands:
+    dilate      dilate INPUT with structuring element
+    erode       erode INPUT with structuring element
+    open        morphological opening of INPUT with structuring element
+    close       morphological closing of INPUT with structuring element
+    invert      flip all pixels of INPUT
+    components  print number of connected components of INPUT and their areas
+    report      print summary of INPUT (area, components, bounding box, holes, ...)
+    iou         print intersection over union of INPUT and INPUT2
+
+Options:
+    --se SPEC           structuring element, default square:1
+                        disk:R     pixels with euclidean distance to center at most R
+                        diamond:R  pixels with manhattan distance to center at most R
+                        square:R   pixels with chebyshev distance to center at most R
+                        rect:WxH   rectangle with width W and height H
+    --connectivity N    4 or 8, connectivity of components, default 8
+    -o, --output PATH   output image, default is PNG written to standard output
+
+INPUT can be '-' to read image from standard input."#;
+
+fn parse_usize(s: &str) -> Result<usize, String> {
+    s.parse().map_err(|_| format!("invalid number '{}'", s))
+}
+
+fn parse_se(spec: &str) -> Result<RLE, String> {
+    let mut parts = spec.splitn(2, ':');
+    let kind = parts.next().unwrap_or("");
+    let arg = parts.next().ok_or_else(|| format!("invalid structuring element '{}'", spec))?;
+    match kind {
+        "disk" => Ok(RLE::disk_structuring(parse_usize(arg)?)),
+        "diamond" => Ok(RLE::l1_structuring(parse_usize(arg)?)),
+        "square" => Ok(RLE::linf_structuring(parse_usize(arg)?)),
+        "rect" => {
+            let mut dims = arg.splitn(2, 'x');
+            let w = parse_usize(dims.next().unwrap_or(""))?;
+            let h = parse_usize(dims.next().ok_or_else(|| format!("invalid rectangle '{}'", arg))?)?;
+            if w == 0 || h == 0 {
+                return Err(format!("invalid rectangle '{}'", arg));
+            }
+            Ok(RLE::rect_structuring(w, h))
+        }
+        _ => Err(format!("unknown structuring element '{}'", kind)),
+    }
+}
+
+fn load(path: &str) -> Result<RLE, String> {
+    let img = if path == "-" {
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data).map_err(|e| e.to_string())?;
+        image::load_from_memory(&data)
+    } else {
+        image::open(path)
+    };
+    let img = img.map_err(|e| format!("{}: {}", path, e))?;
+    RLE::try_from(img).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn save(rle: &RLE, path: Option<&str>) -> Result<(), String> {
+    let gray = rle.to_gray_image(255, 0);
+    match path {
+        Some(path) => gray.save(path).map_err(|e| format!("{}: {}", path, e)),
+        None => {
+            let stdout = std::io::stdout();
+            let mut out = stdout.lock();
+            DynamicImage::ImageLuma8(gray)
+                .write_to(&mut out, ImageOutputFormat::Png)
+                .map_err(|e| e.to_string())?;
+            out.flush().map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut args = args.into_iter();
+    let command = args.next().ok_or_else(|| USAGE.to_string())?;
+    let mut se = RLE::linf_structuring(1);
+    let mut connectivity = Connectivity::Eight;
+    let mut output = None;
+    let mut inputs = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--se" => se = parse_se(&args.next().ok_or("--se needs a value")?)?,
+            "--connectivity" => {
+                connectivity = match args.next().as_deref() {
+                    Some("4") => Connectivity::Four,
+                    Some("8") => Connectivity::Eight,
+                    _ => return Err("--connectivity must be 4 or 8".to_string()),
+                }
+            }
+            "-o" | "--output" => output = Some(args.next().ok_or("--output needs a value")?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => inputs.push(arg),
+        }
+    }
+    let expected_inputs = if command == "iou" { 2 } else { 1 };
+    if inputs.len() != expected_inputs {
+        return Err(USAGE.to_string());
+    }
+    let rle = load(&inputs[0])?;
+    let output = output.as_deref();
+    match command.as_ref() {
+        "dilate" => save(&rle.dilate(&se), output),
+        "erode" => save(&rle.erode(&se), output),
+        "open" => save(&rle.open(&se), output),
+        "close" => save(&rle.close(&se), output),
+        "invert" => save(&!&rle, output),
+        "components" => {
+            let components = rle.components(connectivity);
+            println!("{}", components.len());
+            for component in &components {
+                println!("{}", component.area());
+            }
+            Ok(())
+        }
+        "report" => {
+            println!("{}", rle.report());
+            Ok(())
+        }
+        "iou" => {
+            let other = load(&inputs[1])?;
+            if rle.width() != other.width() || rle.height() != other.height() {
+                return Err("images must have same dimensions".to_string());
+            }
+            let stats = rle.change_stats(&other);
+            println!("{}", stats.iou);
+            Ok(())
+        }
+        _ => Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    }
+}
+
+fn main() {
+    if let Err(e) = run(std::env::args().skip(1).collect()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,161 @@
+//! Conversion between `RLE` and packed bitmaps with 1 bit per pixel.
+//! Bits are packed most significant bit first (as in PBM files) and every row starts
+//! at byte boundary, so row takes ceil(width / 8) bytes.
+use super::{Run, RleError, RLE};
+
+/// Number of bytes of one row of bitmap.
+fn row_bytes(width: usize) -> usize {
+    width.div_ceil(8)
+}
+
+/// Set bits start..=end of packed row.
+fn set_bits(row: &mut [u8], start: usize, end: usize) {
+    let (first, last) = (start / 8, end / 8);
+    // bits from start to end of byte (and from start of byte to end)
+    let head = 0xffu8 >> (start % 8);
+    let tail = 0xffu8 << (7 - end % 8);
+    if first == last {
+        row[first] |= head & tail;
+        return;
+    }
+    row[first] |= head;
+    for byte in &mut row[first + 1..last] {
+        *byte = 0xff;
+    }
+    row[last] |= tail;
+}
+
+/// Encode one packed row of width pixels, 64 pixels are read at once and runs are
+/// found by counting leading zeros (or ones) of whole words.
+fn encode_packed_row(row: &[u8], width: usize, y: i32, runs: &mut Vec<Run>) {
+    let mut run_start: Option<usize> = None;
+    for (i, chunk) in row.chunks(8).enumerate() {
+        let base = i * 64;
+        if base >= width {
+            break;
+        }
+        let mut bytes = [0u8; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let word = u64::from_be_bytes(bytes);
+        // bits of word which are pixels of row, padding bits are ignored
+        let bits = std::cmp::min(64, width - base);
+        let mut pos = 0;
+        while pos < bits {
+            let rest = word << pos;
+            match run_start {
+                Some(start) => {
+                    pos = std::cmp::min(bits, pos + (!rest).leading_zeros() as usize);
+                    if pos < bits {
+                        runs.push(Run::new(start as i32, (base + pos) as i32 - 1, y));
+                        run_start = None;
+                    }
+                }
+                None => {
+                    pos = std::cmp::min(bits, pos + rest.leading_zeros() as usize);
+                    if pos < bits {
+                        run_start = Some(base + pos);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(start) = run_start {
+        runs.push(Run::new(start as i32, width as i32 - 1, y));
+    }
+}
+
+impl RLE {
+    /// Create RLE from packed bitmap with 1 bit per pixel, see module documentation
+    /// for layout. Padding bits at end of rows are ignored.
+    /// If data is shorter than h * ceil(w / 8) bytes then this will panic.
+    pub fn from_bitmap(w: usize, h: usize, packed: &[u8]) -> Self {
+        match Self::try_from_bitmap(w, h, packed) {
+            Ok(rle) => rle,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Create RLE from packed bitmap with 1 bit per pixel, see `from_bitmap`.
+    /// Returns error if data is shorter than h * ceil(w / 8) bytes.
+    pub fn try_from_bitmap(w: usize, h: usize, packed: &[u8]) -> Result<Self, RleError> {
+        let stride = row_bytes(w);
+        if packed.len() < stride * h {
+            return Err(RleError::DataLength { expected: stride * h, actual: packed.len() });
+        }
+        let mut runs = Vec::new();
+        for y in 0..h {
+            encode_packed_row(&packed[y * stride..(y + 1) * stride], w, y as i32, &mut runs);
+        }
+        Ok(Self { width: w, height: h, runs })
+    }
+
+    /// Encode RLE to packed bitmap with 1 bit per pixel, see module documentation
+    /// for layout. Padding bits at end of rows are 0 and parts of runs outside of image
+    /// are ignored.
+    pub fn to_bitmap(&self) -> Vec<u8> {
+        let stride = row_bytes(self.width);
+        let mut out = vec![0; stride * self.height];
+        for run in self.canonical_runs().iter() {
+            let row = &mut out[run.y as usize * stride..(run.y as usize + 1) * stride];
+            set_bits(row, run.x_start as usize, run.x_end as usize);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_util, Run, RleError, RLE};
+
+    #[test]
+    fn to_bitmap_test() {
+        let rle = RLE::from_runs(11, 3, vec![
+            Run::new(0, 0, 0),
+            Run::new(2, 9, 0),
+            Run::new(3, 5, 1),
+            Run::new(-4, 20, 2),
+        ]);
+        assert_eq!(rle.to_bitmap(), vec![
+            0b1011_1111, 0b1100_0000,
+            0b0001_1100, 0b0000_0000,
+            0b1111_1111, 0b1110_0000,
+        ]);
+        assert!(RLE::new(0, 3).to_bitmap().is_empty());
+        assert_eq!(RLE::ones(8, 1).to_bitmap(), vec![0xff]);
+    }
+
+    #[test]
+    fn from_bitmap_test() {
+        // padding bits are set and must be ignored
+        let packed = [
+            0b1011_1111, 0b1101_1111,
+            0b0001_1100, 0b0000_0000,
+            0b1111_1111, 0b1111_1111,
+        ];
+        let rle = RLE::from_bitmap(11, 3, &packed);
+        assert_eq!(rle.runs(), &[
+            Run::new(0, 0, 0),
+            Run::new(2, 9, 0),
+            Run::new(3, 5, 1),
+            Run::new(0, 10, 2),
+        ]);
+        assert_eq!(
+            RLE::try_from_bitmap(11, 3, &packed[..5]),
+            Err(RleError::DataLength { expected: 6, actual: 5 })
+        );
+    }
+
+    #[test]
+    fn bitmap_round_trip_test() {
+        // rows longer than one word with runs crossing word boundaries
+        let width = 150;
+        let mut rle = RLE::from(&test_util::noise(width, 7, 0x9e37_79b9, |state| state % 5 < 3));
+        rle.fill_rect(0, 3, width, 1);
+        rle.clear_rect(0, 4, width, 1);
+        rle.fill_rect(60, 5, 70, 1);
+        let packed = rle.to_bitmap();
+        assert_eq!(packed.len(), 19 * 7);
+        let decoded = RLE::from_bitmap(width, 7, &packed);
+        assert_eq!(decoded.runs(), rle.runs());
+    }
+}
@@ -0,0 +1,278 @@
+//! Morphology with explicit handling of pixels outside of image.
+use super::{Kernel, Run, RLE};
+
+/// Values of pixels outside of image used by `RLE::dilate_with_mode` and
+/// `RLE::erode_with_mode`. Results are always clipped to image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderMode {
+    /// Pixels outside of image are 0, so erosion removes pixels near border
+    /// (OpenCV `BORDER_CONSTANT` with value 0).
+    Clip,
+    /// Pixels outside of image do not change result, they are 0 for dilation and 1
+    /// for erosion (same as `dilate` and `erode`, OpenCV default).
+    Ignore,
+    /// Image is repeated, pixel (x, y) outside is (x mod width, y mod height)
+    /// (OpenCV `BORDER_WRAP`).
+    Wrap,
+    /// Image is mirrored at border including border pixels, `fedcba|abcdef|fedcba`
+    /// (OpenCV `BORDER_REFLECT`).
+    Reflect,
+}
+
+impl BorderMode {
+    /// Coordinate inside of image with n pixels which is used for coordinate c
+    /// (only for `Wrap` and `Reflect`).
+    fn map(self, c: i64, n: i64) -> i64 {
+        let (tile, r) = (c.div_euclid(n), c.rem_euclid(n));
+        if self == BorderMode::Reflect && tile & 1 == 1 {
+            n - 1 - r
+        } else {
+            r
+        }
+    }
+}
+
+/// Largest distance of set pixel of s from its anchor in x and y direction.
+fn kernel_radius<K: Kernel + ?Sized>(s: &K) -> (usize, usize) {
+    let (ax, ay) = s.anchor();
+    s.kernel_runs().iter().fold((0, 0), |(rx, ry), run| {
+        let dx = ((run.x_start - ax).abs()).max((run.x_end - ax).abs()) as usize;
+        (rx.max(dx), ry.max((run.y - ay).unsigned_abs() as usize))
+    })
+}
+
+/// Number of columns (rows) by which dilation with s can grow image to the left,
+/// right, top and bottom. Pixel p of image sets pixels p - (sx - ax) of dilation.
+fn dilation_extent<K: Kernel + ?Sized>(s: &K) -> (usize, usize, usize, usize) {
+    let (ax, ay) = s.anchor();
+    let runs = s.kernel_runs();
+    let (left, right, top, bottom) = runs.iter().fold((0, 0, 0, 0), |(l, r, t, b), run| {
+        (l.max(run.x_end - ax), r.max(ax - run.x_start), t.max(run.y - ay), b.max(ay - run.y))
+    });
+    (left as usize, right as usize, top as usize, bottom as usize)
+}
+
+/// Pad image by rx columns on both sides and ry rows on both sides, new pixels are
+/// filled from image as given by `Wrap` or `Reflect` mode.
+fn pad_periodic(rle: &RLE, rx: usize, ry: usize, mode: BorderMode) -> RLE {
+    let (w, h) = (rle.width() as i64, rle.height() as i64);
+    let (rx, ry) = (rx as i64, ry as i64);
+    let canonical = RLE::from_runs(rle.width(), rle.height(), rle.canonical_runs().into_owned());
+    let mut runs = Vec::new();
+    for y in -ry..h + ry {
+        let row = canonical.row_runs(mode.map(y, h) as i32);
+        // tiles of row which intersect padded row [-rx, w + rx)
+        for tile in (-rx).div_euclid(w)..=(w + rx - 1).div_euclid(w) {
+            let mirrored = mode == BorderMode::Reflect && tile & 1 == 1;
+            for run in row {
+                let (x_start, x_end) = if mirrored {
+                    (tile * w + w - 1 - run.x_end as i64, tile * w + w - 1 - run.x_start as i64)
+                } else {
+                    (tile * w + run.x_start as i64, tile * w + run.x_end as i64)
+                };
+                let (x_start, x_end) = (x_start.max(-rx), x_end.min(w + rx - 1));
+                if x_start <= x_end {
+                    runs.push(Run::new((x_start + rx) as i32, (x_end + rx) as i32, (y + ry) as i32));
+                }
+            }
+        }
+    }
+    RLE::from_runs((w + 2 * rx) as usize, (h + 2 * ry) as usize, runs)
+}
+
+impl RLE {
+    /// Dilate with structuring element s where pixels outside of image are given by
+    /// mode (see `BorderMode`), result is clipped to image.
+    pub fn dilate_with_mode<K: Kernel + ?Sized>(&self, s: &K, mode: BorderMode) -> Self {
+        self.apply_with_mode(s, mode, true)
+    }
+
+    /// Erode with structuring element s where pixels outside of image are given by
+    /// mode (see `BorderMode`), result is clipped to image.
+    pub fn erode_with_mode<K: Kernel + ?Sized>(&self, s: &K, mode: BorderMode) -> Self {
+        self.apply_with_mode(s, mode, false)
+    }
+
+    /// Erode with structuring element s where pixels outside of image are 1 if
+    /// border_is_foreground (same as `erode`, objects touching border are not eroded
+    /// from that side) or 0 otherwise (`BorderMode::Clip`).
+    pub fn erode_with_border<K: Kernel + ?Sized>(&self, s: &K, border_is_foreground: bool) -> Self {
+        if border_is_foreground {
+            self.erode(s)
+        } else {
+            self.erode_with_mode(s, BorderMode::Clip)
+        }
+    }
+
+    /// Dilate with structuring element s into larger image so that no pixel is lost at
+    /// border. Image is padded by as many columns and rows as dilation can add on every
+    /// side and offset (left, top) of original image in result is returned with it,
+    /// pixel (x, y) of self is pixel (x + left, y + top) of result.
+    pub fn dilate_expand<K: Kernel + ?Sized>(&self, s: &K) -> (Self, (usize, usize)) {
+        let (left, right, top, bottom) = dilation_extent(s);
+        // padding has room for all pixels of dilation so nothing is outside of result
+        let res = self.pad(left, right, top, bottom, false).dilate(s);
+        (res, (left, top))
+    }
+
+    fn apply_with_mode<K: Kernel + ?Sized>(&self, s: &K, mode: BorderMode, dilate: bool) -> Self {
+        if self.width == 0 || self.height == 0 {
+            return Self::new(self.width, self.height);
+        }
+        let op = |rle: &RLE| if dilate { rle.dilate(s) } else { rle.erode(s) };
+        let (rx, ry) = kernel_radius(s);
+        let padded = match mode {
+            // for dilation padding with 0s is same as ignoring outside pixels
+            BorderMode::Clip if !dilate => self.pad(rx, rx, ry, ry, false),
+            BorderMode::Clip | BorderMode::Ignore => {
+                let mut res = op(self);
+                res.clip_to_bounds();
+                return res;
+            }
+            BorderMode::Wrap | BorderMode::Reflect => pad_periodic(self, rx, ry, mode),
+        };
+        let mut res = op(&padded);
+        res.translate_mut(-(rx as i32), -(ry as i32), false);
+        res.width = self.width;
+        res.height = self.height;
+        res.clip_to_bounds();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Image, Morphology, StructuringElement};
+
+    /// Dense reference where outside pixels are read through mode.
+    fn naive(img: &Image, s: &Image, (ax, ay): (i64, i64), mode: BorderMode, dilate: bool) -> Image {
+        let (w, h) = (img.w() as i64, img.h() as i64);
+        let mut out = Image::empty(img.w(), img.h());
+        for y in 0..h {
+            for x in 0..w {
+                let mut values = Vec::new();
+                for sy in 0..s.h() as i64 {
+                    for sx in 0..s.w() as i64 {
+                        if s[sy as usize][sx as usize] == 0 {
+                            continue;
+                        }
+                        let (px, py) = (x + sx - ax, y + sy - ay);
+                        let inside = px >= 0 && py >= 0 && px < w && py < h;
+                        let value = match mode {
+                            _ if inside => img[py as usize][px as usize] > 0,
+                            BorderMode::Clip => false,
+                            BorderMode::Ignore => !dilate,
+                            _ => img[mode.map(py, h) as usize][mode.map(px, w) as usize] > 0,
+                        };
+                        values.push(value);
+                    }
+                }
+                let set = if dilate { values.iter().any(|&v| v) } else { values.iter().all(|&v| v) };
+                out[y as usize][x as usize] = set as u8;
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn map_test() {
+        let wrapped: Vec<i64> = (-4..7).map(|c| BorderMode::Wrap.map(c, 3)).collect();
+        assert_eq!(wrapped, vec![2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0]);
+        let reflected: Vec<i64> = (-4..7).map(|c| BorderMode::Reflect.map(c, 3)).collect();
+        assert_eq!(reflected, vec![2, 2, 1, 0, 0, 1, 2, 2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn border_modes_test() {
+        let img = Image::new(6, 5, vec![
+            1, 1, 0, 0, 0, 1,
+            1, 1, 1, 0, 0, 0,
+            0, 0, 0, 0, 1, 0,
+            0, 1, 1, 0, 0, 1,
+            1, 1, 1, 0, 1, 1,
+        ]);
+        let rle = RLE::from(&img);
+        let elements = vec![
+            StructuringElement::centered(RLE::linf_structuring(1)),
+            StructuringElement::centered(RLE::l1_structuring(2)),
+            StructuringElement::new(RLE::rect_structuring(2, 3), 0, 0),
+            // anchor outside of shape and kernel larger than image
+            StructuringElement::new(RLE::rect_structuring(3, 1), -2, 1),
+            StructuringElement::centered(RLE::rect_structuring(9, 1)),
+        ];
+        for se in &elements {
+            let s = se.shape().to_image(1);
+            let anchor = (se.anchor().0 as i64, se.anchor().1 as i64);
+            for &mode in &[BorderMode::Clip, BorderMode::Ignore, BorderMode::Wrap, BorderMode::Reflect] {
+                let dilated = rle.dilate_with_mode(se, mode);
+                assert!(dilated.is_canonical());
+                assert_eq!(dilated.to_image(1), naive(&img, &s, anchor, mode, true), "dilate {:?} {:?}", mode, se);
+                let eroded = rle.erode_with_mode(se, mode);
+                assert!(eroded.is_canonical());
+                assert_eq!(eroded.to_image(1), naive(&img, &s, anchor, mode, false), "erode {:?} {:?}", mode, se);
+            }
+        }
+        let s = RLE::linf_structuring(1);
+        assert_eq!(rle.erode_with_mode(&s, BorderMode::Ignore), Morphology::erode(&rle, &s));
+        assert_eq!(RLE::ones(4, 4).erode_with_mode(&s, BorderMode::Wrap), RLE::ones(4, 4));
+        assert_eq!(RLE::new(0, 3).dilate_with_mode(&s, BorderMode::Reflect), RLE::new(0, 3));
+    }
+
+    #[test]
+    fn dilate_expand_test() {
+        let rle = RLE::from(&Image::new(3, 2, vec![
+            1, 0, 0,
+            0, 0, 1,
+        ]));
+        let (expanded, offset) = rle.dilate_expand(&RLE::linf_structuring(1));
+        assert_eq!(offset, (1, 1));
+        assert_eq!(expanded.to_image(1), Image::new(5, 4, vec![
+            1, 1, 1, 0, 0,
+            1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1,
+            0, 0, 1, 1, 1,
+        ]));
+        // anchor at top left, dilation grows only to left and up
+        let se = StructuringElement::new(RLE::rect_structuring(3, 2), 0, 0);
+        let (expanded, offset) = rle.dilate_expand(&se);
+        assert_eq!(offset, (2, 1));
+        assert!(expanded.is_canonical());
+        assert_eq!(expanded.to_image(1), Image::new(5, 3, vec![
+            1, 1, 1, 0, 0,
+            1, 1, 1, 1, 1,
+            0, 0, 1, 1, 1,
+        ]));
+        // inside of image result is same as dilation
+        let (w, h) = (rle.width(), rle.height());
+        let mut cropped = expanded.translate(-2, -1, false);
+        cropped.width = w;
+        cropped.height = h;
+        assert_eq!(cropped, rle.dilate(&se));
+    }
+
+    #[test]
+    fn erode_with_border_test() {
+        let rle = RLE::from(&Image::new(5, 4, vec![
+            1, 1, 1, 0, 0,
+            1, 1, 1, 0, 0,
+            1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        let s = RLE::linf_structuring(1);
+        assert_eq!(rle.erode_with_border(&s, true).to_image(1), Image::new(5, 4, vec![
+            1, 1, 0, 0, 0,
+            1, 1, 0, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(rle.erode_with_border(&s, true), rle.erode(&s));
+        assert_eq!(rle.erode_with_border(&s, false).to_image(1), Image::new(5, 4, vec![
+            0, 0, 0, 0, 0,
+            0, 1, 0, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(RLE::ones(3, 3).erode_with_border(&s, true), RLE::ones(3, 3));
+    }
+}
@@ -0,0 +1,99 @@
+use super::RLE;
+
+/// Which side of the mask boundary is included in boundary band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandSide {
+    /// Only pixels of mask (pixels set to 1).
+    Inner,
+    /// Only pixels of background (pixels set to 0).
+    Outer,
+    /// Pixels on both sides of boundary.
+    Both,
+}
+
+impl RLE {
+    /// Get all pixels within (euclidean) distance d of mask boundary on both sides,
+    /// same as `boundary_band_side(d, BandSide::Both)`.
+    pub fn boundary_band(&self, d: usize) -> Self {
+        self.boundary_band_side(d, BandSide::Both)
+    }
+
+    /// Get pixels within (euclidean) distance d of mask boundary on selected side.
+    /// Mask pixel belongs to inner band if there is background pixel at distance at
+    /// most d, pixels outside of image are background so mask pixels next to image
+    /// border are also in band. Background pixel belongs to outer band if there is mask
+    /// pixel at distance at most d. If d is 0 band is empty.
+    pub fn boundary_band_side(&self, d: usize, side: BandSide) -> Self {
+        if d == 0 {
+            return Self::new(self.width, self.height);
+        }
+        let disk = Self::disk_structuring(d);
+        let inner = || self - &self.erode_zero_border(&disk);
+        let outer = || &self.dilate(&disk) - self;
+        match side {
+            BandSide::Inner => inner(),
+            BandSide::Outer => outer(),
+            BandSide::Both => inner() | outer(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn boundary_band_test() {
+        let mask = RLE::from(&Image::new(7, 7, vec![
+            0, 0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(
+            mask.boundary_band_side(1, BandSide::Inner).to_image(1),
+            Image::new(7, 7, vec![
+                0, 0, 0, 0, 0, 0, 0,
+                0, 1, 1, 1, 1, 1, 0,
+                0, 1, 0, 0, 0, 1, 0,
+                0, 1, 0, 0, 0, 1, 0,
+                0, 1, 0, 0, 0, 1, 0,
+                0, 1, 1, 1, 1, 1, 0,
+                0, 0, 0, 0, 0, 0, 0,
+            ])
+        );
+        assert_eq!(
+            mask.boundary_band_side(1, BandSide::Outer).to_image(1),
+            Image::new(7, 7, vec![
+                0, 1, 1, 1, 1, 1, 0,
+                1, 0, 0, 0, 0, 0, 1,
+                1, 0, 0, 0, 0, 0, 1,
+                1, 0, 0, 0, 0, 0, 1,
+                1, 0, 0, 0, 0, 0, 1,
+                1, 0, 0, 0, 0, 0, 1,
+                0, 1, 1, 1, 1, 1, 0,
+            ])
+        );
+        assert_eq!(
+            mask.boundary_band(2).to_image(1),
+            Image::new(7, 7, vec![
+                1, 1, 1, 1, 1, 1, 1,
+                1, 1, 1, 1, 1, 1, 1,
+                1, 1, 1, 1, 1, 1, 1,
+                1, 1, 1, 0, 1, 1, 1,
+                1, 1, 1, 1, 1, 1, 1,
+                1, 1, 1, 1, 1, 1, 1,
+                1, 1, 1, 1, 1, 1, 1,
+            ])
+        );
+        assert!(mask.boundary_band(0).runs().is_empty());
+        // mask touching image border has boundary there too
+        let full = RLE::ones(3, 3);
+        assert_eq!(full.boundary_band_side(1, BandSide::Inner), &full - &RLE::from_runs(3, 3, vec![crate::Run::new(1, 1, 1)]));
+        assert!(full.boundary_band_side(1, BandSide::Outer).runs().is_empty());
+    }
+}
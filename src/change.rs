@@ -0,0 +1,93 @@
+use super::connected_components::{components, Connectivity};
+use super::RLE;
+
+/// Change of one connected component of union of two masks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentChange {
+    /// pixels of component (8-connected) of union of both masks
+    pub component: RLE,
+    /// pixels of component which are only in newer mask
+    pub gained: RLE,
+    /// pixels of component which are only in older mask
+    pub lost: RLE,
+}
+
+/// Report about changes between two masks (for example two frames).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeStats {
+    /// number of pixels which are only in newer mask
+    pub appeared_area: usize,
+    /// number of pixels which are only in older mask
+    pub disappeared_area: usize,
+    /// intersection over union of masks, 1 if both masks are empty
+    pub iou: f64,
+    /// changes of every connected component of union of masks
+    pub components: Vec<ComponentChange>,
+}
+
+impl RLE {
+    /// Compare self (older mask) with other (newer mask).
+    /// If dimensions of self and other are not same this method will panic.
+    pub fn change_stats(&self, other: &RLE) -> ChangeStats {
+        assert!(self.width == other.width && self.height == other.height);
+        let appeared = other - self;
+        let disappeared = self - other;
+        let union = self | other;
+        let union_area = union.area();
+        let intersection_area = (self & other).area();
+        let iou = if union_area == 0 {
+            1.0
+        } else {
+            intersection_area as f64 / union_area as f64
+        };
+        let components = components(&union, Connectivity::Eight).into_iter()
+            .map(|component| ComponentChange {
+                gained: &component & &appeared,
+                lost: &component & &disappeared,
+                component,
+            })
+            .collect();
+        ChangeStats {
+            appeared_area: appeared.area(),
+            disappeared_area: disappeared.area(),
+            iou,
+            components,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn change_stats_test() {
+        let old = RLE::from(&Image::new(6, 2, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 0, 0, 1, 0,
+        ]));
+        let new = RLE::from(&Image::new(6, 2, vec![
+            0, 1, 1, 0, 1, 0,
+            0, 0, 0, 0, 1, 0,
+        ]));
+        let stats = old.change_stats(&new);
+        assert_eq!(stats.appeared_area, 1);
+        assert_eq!(stats.disappeared_area, 1);
+        assert!((stats.iou - 3.0 / 5.0).abs() < 1e-9);
+        assert_eq!(stats.components.len(), 2);
+        assert_eq!(stats.components[0].gained.to_image(1), Image::new(6, 2, vec![
+            0, 0, 1, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(stats.components[0].lost.to_image(1), Image::new(6, 2, vec![
+            1, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]));
+        assert!(stats.components[1].gained.runs().is_empty());
+        assert!(stats.components[1].lost.runs().is_empty());
+
+        let empty = RLE::new(3, 3);
+        assert_eq!(empty.change_stats(&empty).iou, 1.0);
+    }
+}
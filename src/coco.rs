@@ -0,0 +1,321 @@
+//! Conversion to and from COCO run length encoding (as used by pycocotools).
+//!
+//! COCO stores mask in column-major order as counts of alternating runs of 0s and 1s,
+//! starting with 0s (first count is 0 if first pixel is set). Counts are either stored
+//! as integers (uncompressed RLE) or compressed to string where every count is stored
+//! as difference to count two positions before in LEB128-like encoding with 5 bits
+//! per character.
+use super::{Run, RleError, RLE};
+
+impl RLE {
+    /// Encode to uncompressed COCO counts (column-major, starting with count of 0s).
+    /// Counts which do not fit in u32 (only in images with more than u32::MAX pixels)
+    /// are split with count 0 of other value between parts.
+    pub fn to_coco_counts(&self) -> Vec<u32> {
+        let h = self.height() as u64;
+        let mut counts: Vec<u64> = Vec::new();
+        // index (in column-major order) of first pixel after last written count
+        let mut position = 0;
+        // runs of transposed image go over columns of this one
+        for run in self.transpose().runs() {
+            let start = run.y as u64 * h + run.x_start as u64;
+            let end = run.y as u64 * h + run.x_end as u64 + 1;
+            if start == position && !counts.is_empty() {
+                // run continues from previous column
+                *counts.last_mut().unwrap() += end - start;
+            } else {
+                counts.push(start - position);
+                counts.push(end - start);
+            }
+            position = end;
+        }
+        let total = self.width() as u64 * h;
+        if position < total || counts.is_empty() {
+            counts.push(total - position);
+        }
+        let mut res = Vec::with_capacity(counts.len());
+        for mut count in counts {
+            while count > u32::MAX as u64 {
+                res.push(u32::MAX);
+                res.push(0);
+                count -= u32::MAX as u64;
+            }
+            res.push(count as u32);
+        }
+        res
+    }
+
+    /// Decode uncompressed COCO counts.
+    /// Returns error if counts do not sum to width * height, if width * height
+    /// overflows expected length in error is usize::MAX.
+    pub fn from_coco_counts(width: usize, height: usize, counts: &[u32]) -> Result<Self, RleError> {
+        let total: usize = counts.iter().map(|&count| count as usize).sum();
+        match width.checked_mul(height) {
+            Some(len) if len == total => {}
+            len => return Err(RleError::DataLength { expected: len.unwrap_or(usize::MAX), actual: total }),
+        }
+        Ok(from_counts(width, height, counts.iter().map(|&count| count as usize)))
+    }
+
+    /// Encode to compressed COCO string (`counts` field of pycocotools RLE).
+    pub fn to_coco_string(&self) -> String {
+        let counts = self.to_coco_counts();
+        let mut res = String::new();
+        for i in 0..counts.len() {
+            let mut x = counts[i] as i64;
+            if i > 2 {
+                x -= counts[i - 2] as i64;
+            }
+            loop {
+                let mut c = (x & 0x1f) as u8;
+                x >>= 5;
+                let more = if c & 0x10 != 0 { x != -1 } else { x != 0 };
+                if more {
+                    c |= 0x20;
+                }
+                res.push((c + 48) as char);
+                if !more {
+                    break;
+                }
+            }
+        }
+        res
+    }
+
+    /// Decode compressed COCO string.
+    /// Returns error if string contains invalid characters, decodes to negative counts
+    /// or if counts do not sum to width * height.
+    pub fn from_coco_string(width: usize, height: usize, s: &str) -> Result<Self, RleError> {
+        let mut counts: Vec<u32> = Vec::new();
+        let mut bytes = s.bytes();
+        while let Some(mut byte) = bytes.next() {
+            let mut x = 0i64;
+            let mut k = 0;
+            loop {
+                if !(48..48 + 64).contains(&byte) || k > 12 {
+                    return Err(RleError::InvalidEncoding);
+                }
+                let c = (byte - 48) as i64;
+                x |= (c & 0x1f) << (5 * k);
+                k += 1;
+                if c & 0x20 == 0 {
+                    if c & 0x10 != 0 {
+                        x |= -1 << (5 * k);
+                    }
+                    break;
+                }
+                byte = bytes.next().ok_or(RleError::InvalidEncoding)?;
+            }
+            if counts.len() > 2 {
+                x += counts[counts.len() - 2] as i64;
+            }
+            if x < 0 || x > u32::MAX as i64 {
+                return Err(RleError::InvalidEncoding);
+            }
+            counts.push(x as u32);
+        }
+        Self::from_coco_counts(width, height, &counts)
+    }
+
+    /// Rasterize COCO polygon segmentation, list of polygons where every polygon is
+    /// flattened list of coordinates [x0, y0, x1, y1, ...]. Polygons are rasterized
+    /// same as in pycocotools (`frPyObjects` followed by `merge`), so result is union
+    /// of all polygons and polygon inside other polygon does not make hole.
+    /// Returns error if some polygon has odd number of coordinates.
+    pub fn from_coco_polygons<P: AsRef<[f64]>>(width: usize, height: usize, polygons: &[P]) -> Result<Self, RleError> {
+        let mut rle = Self::new(width, height);
+        for polygon in polygons {
+            let xy = polygon.as_ref();
+            if xy.len() & 1 == 1 {
+                return Err(RleError::InvalidEncoding);
+            }
+            rle |= &from_counts(width, height, polygon_counts(xy, width, height));
+        }
+        Ok(rle)
+    }
+}
+
+/// Port of `rleFrPoly` from pycocotools: polygon is upsampled 5 times, points along
+/// its boundary are found and positions (in column-major order) where columns cross
+/// boundary are turned into counts.
+fn polygon_counts(xy: &[f64], width: usize, height: usize) -> Vec<usize> {
+    let scale = 5.0;
+    // casts truncate towards zero as in C
+    let mut x: Vec<i64> = xy.iter().step_by(2).map(|&x| (scale * x + 0.5) as i64).collect();
+    let mut y: Vec<i64> = xy.iter().skip(1).step_by(2).map(|&y| (scale * y + 0.5) as i64).collect();
+    if x.is_empty() {
+        return vec![width * height];
+    }
+    x.push(x[0]);
+    y.push(y[0]);
+    // points densely along boundary
+    let (mut u, mut v) = (Vec::new(), Vec::new());
+    for j in 0..x.len() - 1 {
+        let (mut xs, mut xe, mut ys, mut ye) = (x[j], x[j + 1], y[j], y[j + 1]);
+        let (dx, dy) = ((xe - xs).abs(), (ys - ye).abs());
+        let flip = (dx >= dy && xs > xe) || (dx < dy && ys > ye);
+        if flip {
+            std::mem::swap(&mut xs, &mut xe);
+            std::mem::swap(&mut ys, &mut ye);
+        }
+        if dx >= dy {
+            let s = if dx == 0 { 0.0 } else { (ye - ys) as f64 / dx as f64 };
+            for d in 0..=dx {
+                let t = if flip { dx - d } else { d };
+                u.push(t + xs);
+                v.push((ys as f64 + s * t as f64 + 0.5) as i64);
+            }
+        } else {
+            let s = (xe - xs) as f64 / dy as f64;
+            for d in 0..=dy {
+                let t = if flip { dy - d } else { d };
+                v.push(t + ys);
+                u.push((xs as f64 + s * t as f64 + 0.5) as i64);
+            }
+        }
+    }
+    // points where boundary crosses centers of columns, downsampled
+    let mut positions = Vec::new();
+    for j in 1..u.len() {
+        if u[j] == u[j - 1] {
+            continue;
+        }
+        let xd = (if u[j] < u[j - 1] { u[j] } else { u[j] - 1 } as f64 + 0.5) / scale - 0.5;
+        if xd.floor() != xd || xd < 0.0 || xd > width as f64 - 1.0 {
+            continue;
+        }
+        let yd = (v[j].min(v[j - 1]) as f64 + 0.5) / scale - 0.5;
+        let yd = yd.max(0.0).min(height as f64).ceil();
+        positions.push(xd as usize * height + yd as usize);
+    }
+    positions.push(width * height);
+    positions.sort_unstable();
+    // every crossing flips value, crossings at same position cancel out
+    let mut counts = Vec::with_capacity(positions.len());
+    let mut previous = 0;
+    let mut merge = false;
+    for position in positions {
+        let count = position - previous;
+        previous = position;
+        if merge {
+            *counts.last_mut().unwrap() += count;
+            merge = false;
+        } else if count == 0 && !counts.is_empty() {
+            merge = true;
+        } else {
+            counts.push(count);
+        }
+    }
+    counts
+}
+
+/// Decode counts which sum to width * height.
+fn from_counts<I: IntoIterator<Item = usize>>(width: usize, height: usize, counts: I) -> RLE {
+    // runs of transposed image, row of transposed image is column of this one
+    let mut transposed = Vec::new();
+    let mut position = 0;
+    for (i, count) in counts.into_iter().enumerate() {
+        if i & 1 == 1 {
+            let mut start = position;
+            while start < position + count {
+                let (x, y_start) = (start / height, start % height);
+                let y_end = (y_start + position + count - start).min(height) - 1;
+                transposed.push(Run::new(y_start as _, y_end as _, x as _));
+                start += y_end - y_start + 1;
+            }
+        }
+        position += count;
+    }
+    RLE::from_runs(height, width, transposed).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    fn sample() -> RLE {
+        RLE::from(&Image::new(5, 4, vec![
+            1, 0, 0, 1, 1,
+            1, 0, 1, 1, 0,
+            0, 0, 1, 0, 1,
+            1, 0, 1, 1, 1,
+        ]))
+    }
+
+    #[test]
+    fn coco_counts_test() {
+        let rle = sample();
+        // columns: 1101, 0000, 0111, 1101, 1011
+        assert_eq!(rle.to_coco_counts(), vec![0, 2, 1, 1, 5, 5, 1, 2, 1, 2]);
+        assert_eq!(RLE::from_coco_counts(5, 4, &rle.to_coco_counts()), Ok(rle));
+        assert_eq!(RLE::new(3, 2).to_coco_counts(), vec![6]);
+        assert_eq!(RLE::ones(3, 2).to_coco_counts(), vec![0, 6]);
+        assert_eq!(RLE::new(0, 0).to_coco_counts(), vec![0]);
+        assert_eq!(RLE::from_coco_counts(3, 2, &[0, 6]), Ok(RLE::ones(3, 2)));
+        assert_eq!(
+            RLE::from_coco_counts(3, 2, &[1, 2]),
+            Err(RleError::DataLength { expected: 6, actual: 3 })
+        );
+        assert_eq!(
+            RLE::from_coco_counts(usize::MAX, 2, &[1, 2]),
+            Err(RleError::DataLength { expected: usize::MAX, actual: 3 })
+        );
+        // more than u32::MAX pixels
+        let mut large = RLE::new(70_000, 70_000);
+        large.insert_run(Run::new(69_999, 69_999, 69_999));
+        let total = 70_000u64 * 70_000;
+        let zeros = total - 1 - u32::MAX as u64;
+        assert_eq!(large.to_coco_counts(), vec![u32::MAX, 0, zeros as u32, 1]);
+        assert_eq!(RLE::from_coco_counts(70_000, 70_000, &large.to_coco_counts()), Ok(large));
+    }
+
+    #[test]
+    fn coco_string_test() {
+        assert_eq!(RLE::ones(2, 2).to_coco_string(), "04");
+        // difference to count two positions before is negative
+        let rle = RLE::from_coco_counts(4, 3, &[1, 5, 1, 2, 3]).unwrap();
+        assert_eq!(rle.to_coco_string(), "151M2");
+        assert_eq!(RLE::from_coco_string(4, 3, "151M2"), Ok(rle));
+        let mut large = RLE::new(40, 30);
+        crate::draw::filled_rect(&mut large, 3, 4, 30, 20);
+        let encoded = large.to_coco_string();
+        assert_eq!(RLE::from_coco_string(40, 30, &encoded), Ok(large.clone()));
+        assert_eq!(RLE::from_coco_string(40, 30, "0 "), Err(RleError::InvalidEncoding));
+        // unfinished multi character count
+        assert_eq!(RLE::from_coco_string(40, 30, "0`"), Err(RleError::InvalidEncoding));
+        let rle = sample();
+        assert_eq!(RLE::from_coco_string(5, 4, &rle.to_coco_string()), Ok(rle));
+    }
+
+    #[test]
+    fn coco_polygons_test() {
+        // expected counts are computed with rleFrPoly of pycocotools
+        let square = [1.0, 1.0, 4.0, 1.0, 4.0, 4.0, 1.0, 4.0];
+        let rle = RLE::from_coco_polygons(6, 6, &[square]).unwrap();
+        assert_eq!(rle.to_coco_counts(), vec![7, 3, 3, 3, 3, 3, 14]);
+        let triangle = [0.5, 0.5, 5.5, 2.0, 2.0, 5.5];
+        let rle = RLE::from_coco_polygons(7, 6, &[&triangle[..]]).unwrap();
+        assert_eq!(rle.to_coco_counts(), vec![7, 3, 3, 4, 2, 3, 4, 1, 15]);
+        // clipped at border
+        let outside = vec![-2.0, -2.0, 3.0, -2.0, 3.0, 3.0, -2.0, 3.0];
+        let rle = RLE::from_coco_polygons(5, 5, &[outside]).unwrap();
+        assert_eq!(rle.to_coco_counts(), vec![0, 3, 2, 3, 2, 3, 12]);
+
+        // parts are merged with union
+        let inner = vec![2.0, 2.0, 3.0, 2.0, 3.0, 3.0, 2.0, 3.0];
+        let parts = vec![square.to_vec(), inner, vec![4.0, 0.0, 6.0, 0.0, 6.0, 2.0, 4.0, 2.0]];
+        let rle = RLE::from_coco_polygons(6, 6, &parts).unwrap();
+        assert_eq!(rle.to_image(1), Image::new(6, 6, vec![
+            0, 0, 0, 0, 1, 1,
+            0, 1, 1, 1, 1, 1,
+            0, 1, 1, 1, 0, 0,
+            0, 1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]));
+        let empty: [&[f64]; 0] = [];
+        assert_eq!(RLE::from_coco_polygons(3, 2, &empty), Ok(RLE::new(3, 2)));
+        assert_eq!(RLE::from_coco_polygons(3, 2, &[[1.0, 2.0, 3.0]]), Err(RleError::InvalidEncoding));
+    }
+}
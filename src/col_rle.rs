@@ -0,0 +1,117 @@
+//! Binary image encoded as runs along columns, for operations which work on columns.
+use super::document::rlsa_horizontal;
+use super::RLE;
+
+/// Binary image stored as vertical runs, column after column. Column oriented
+/// operations (vertical structuring elements, vertical RLSA, column profiles) work
+/// directly on vertical runs like `RLE` works on horizontal ones, so image is
+/// converted once instead of transposing it for every operation.
+/// Convert from and to `RLE` with `From`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColRle {
+    /// transposed image, run (x_start, x_end, y) is column y from row x_start to x_end,
+    /// runs are always canonical
+    transposed: RLE,
+}
+
+impl ColRle {
+    /// Create empty image with given dimensions.
+    pub fn new(width: usize, height: usize) -> Self {
+        ColRle { transposed: RLE::new(height, width) }
+    }
+
+    pub fn width(&self) -> usize {
+        self.transposed.height()
+    }
+
+    pub fn height(&self) -> usize {
+        self.transposed.width()
+    }
+
+    /// Number of pixels set to 1.
+    pub fn area(&self) -> usize {
+        self.transposed.area()
+    }
+
+    /// Runs (y_start, y_end) of column x from top to bottom, y_end is inclusive.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let runs = self.transposed.runs();
+        let x = x as i32;
+        let start = runs.partition_point(|run| run.y < x);
+        runs[start..].iter().take_while(move |run| run.y == x).map(|run| (run.x_start, run.x_end))
+    }
+
+    /// Number of pixels set to 1 in every column.
+    pub fn vertical_profile(&self) -> Vec<usize> {
+        let mut profile = vec![0; self.width()];
+        for run in self.transposed.runs() {
+            profile[run.y as usize] += (run.x_end - run.x_start + 1) as usize;
+        }
+        profile
+    }
+
+    /// Fill gaps of at most gap pixels between two runs in same column, vertical
+    /// version of `document::rlsa_horizontal`.
+    pub fn rlsa(&self, gap: usize) -> Self {
+        ColRle { transposed: rlsa_horizontal(&self.transposed, gap) }
+    }
+
+    /// Dilate with vertical line of len pixels (see `RLE::vline_structuring`).
+    pub fn dilate_vertical(&self, len: usize) -> Self {
+        let mut transposed = self.transposed.dilate(&RLE::hline_structuring(len));
+        transposed.clip_to_bounds();
+        ColRle { transposed }
+    }
+
+    /// Erode with vertical line of len pixels (see `RLE::vline_structuring`),
+    /// pixels outside of image are treated as 1.
+    pub fn erode_vertical(&self, len: usize) -> Self {
+        let mut transposed = self.transposed.erode(&RLE::hline_structuring(len));
+        transposed.clip_to_bounds();
+        ColRle { transposed }
+    }
+}
+
+/// Convert with one sweep over rows, see `RLE::transpose`.
+impl From<&RLE> for ColRle {
+    fn from(rle: &RLE) -> Self {
+        ColRle { transposed: rle.transpose() }
+    }
+}
+
+impl From<&ColRle> for RLE {
+    fn from(col: &ColRle) -> Self {
+        col.transposed.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{document, Image};
+
+    #[test]
+    fn col_rle_test() {
+        let rle = RLE::from(&Image::new(4, 5, vec![
+            1, 0, 1, 0,
+            1, 0, 0, 0,
+            0, 0, 1, 1,
+            1, 0, 0, 1,
+            1, 0, 1, 0,
+        ]));
+        let col = ColRle::from(&rle);
+        assert_eq!((col.width(), col.height(), col.area()), (4, 5, rle.area()));
+        assert_eq!(col.column(0).collect::<Vec<_>>(), vec![(0, 1), (3, 4)]);
+        assert_eq!(col.column(1).count(), 0);
+        assert_eq!(col.column(2).collect::<Vec<_>>(), vec![(0, 0), (2, 2), (4, 4)]);
+        assert_eq!(col.vertical_profile(), vec![4, 0, 3, 2]);
+        assert_eq!(RLE::from(&col), rle);
+        assert_eq!(RLE::from(&col.rlsa(1)), document::rlsa_vertical(&rle, 1));
+        let vline = RLE::vline_structuring(3);
+        let dilated = col.dilate_vertical(3);
+        assert_eq!(RLE::from(&dilated), rle.dilate(&vline));
+        assert_eq!(dilated.vertical_profile(), vec![5, 0, 5, 4]);
+        assert_eq!(RLE::from(&col.erode_vertical(3)), rle.erode(&vline));
+        assert_eq!(RLE::from(&ColRle::new(3, 2)), RLE::new(3, 2));
+    }
+}
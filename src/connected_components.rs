@@ -0,0 +1,614 @@
+//! Connected components labeling done directly on runs.
+//!
+//! Runs of neighboring rows which overlap (or touch diagonally for 8-connectivity) are
+//! joined with union-find, so image is never decoded. Components are numbered in order
+//! of their first run (top to bottom, left to right).
+use super::rle::row_chunks;
+use super::{parallel, trace, Context, LabelRle, LabelRun, Run, RleError, RLE};
+
+/// Which neighboring pixels are connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Pixels are connected only with left, right, top and bottom neighbor.
+    Four,
+    /// Pixels are connected with all 8 neighbors (including diagonal).
+    Eight,
+}
+
+/// Statistics of one connected component, see `RLE::component_stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStats {
+    /// number of pixels
+    pub area: usize,
+    /// bounding box (x_min, y_min, x_max, y_max) of pixels
+    pub bbox: (i32, i32, i32, i32),
+    /// centroid (x, y) of pixels
+    pub centroid: (f64, f64),
+    /// pixels of component, same dimensions as labeled image
+    pub mask: RLE,
+}
+
+/// Union-find (disjoint set) over run indices.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self { parent: (0..n).collect() }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            // path halving
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Join sets of a and b, returns false if they were already same set.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        // smaller index is root so labels follow order of runs
+        let (root, child) = if a < b { (a, b) } else { (b, a) };
+        self.parent[child] = root;
+        true
+    }
+}
+
+/// Check if runs from two consecutive rows are connected.
+#[inline]
+fn connected(a: Run, b: Run, connectivity: Connectivity) -> bool {
+    let d = match connectivity {
+        Connectivity::Four => 0,
+        Connectivity::Eight => 1,
+    };
+    a.x_start <= b.x_end + d && b.x_start <= a.x_end + d
+}
+
+/// Label runs by connected components.
+/// Runs must be canonical (sorted, no overlapping or touching runs in same row).
+/// Returns label of every run and number of components, labels are numbered from 0 in
+/// order of first run of each component.
+/// Progress is reported to ctx and error is returned if ctx is cancelled.
+pub(crate) fn label_runs(
+    runs: &[Run],
+    connectivity: Connectivity,
+    ctx: &mut Context,
+) -> Result<(Vec<usize>, usize), RleError> {
+    let (mut uf, _) = union_runs(runs, connectivity, ctx)?;
+    let mut labels = vec![usize::MAX; runs.len()];
+    let mut count = 0;
+    for i in 0..runs.len() {
+        let root = uf.find(i);
+        if labels[root] == usize::MAX {
+            labels[root] = count;
+            count += 1;
+        }
+        labels[i] = labels[root];
+    }
+    ctx.step(runs.len(), runs.len())?;
+    Ok((labels, count))
+}
+
+/// Number of runs joined together when labeling in parallel, see `union_runs`.
+const LABEL_CHUNK_RUNS: usize = 1 << 14;
+
+/// Join connected runs (see `label_runs`) in union-find, also returns number of
+/// components. With `rayon` feature chunks of rows are joined in parallel and then
+/// last row of every chunk is joined with first row of next chunk, union-find
+/// partition and therefore labels are same as when runs are joined row by row.
+fn union_runs(runs: &[Run], connectivity: Connectivity, ctx: &mut Context) -> Result<(UnionFind, usize), RleError> {
+    let chunks = row_chunks(runs, LABEL_CHUNK_RUNS);
+    if !cfg!(feature = "rayon") || chunks.len() <= 1 {
+        let mut uf = UnionFind::new(runs.len());
+        let joined = union_rows(runs, connectivity, &mut uf, ctx)?;
+        return Ok((uf, runs.len() - joined));
+    }
+    ctx.step(0, runs.len())?;
+    let parts = parallel::map(&chunks, |chunk| {
+        let mut uf = UnionFind::new(chunk.len());
+        let joined = union_rows(chunk, connectivity, &mut uf, &mut Context::new()).unwrap();
+        (uf.parent, joined)
+    });
+    // indices of runs in chunk are shifted by start of chunk
+    let mut uf = UnionFind { parent: Vec::with_capacity(runs.len()) };
+    let mut joined = 0;
+    for (parent, chunk_joined) in parts {
+        let offset = uf.parent.len();
+        uf.parent.extend(parent.into_iter().map(|p| p + offset));
+        joined += chunk_joined;
+    }
+    let mut cur_start = 0;
+    for pair in chunks.windows(2) {
+        cur_start += pair[0].len();
+        ctx.step(cur_start, runs.len())?;
+        let last_y = pair[0][pair[0].len() - 1].y;
+        let prev_start = cur_start - pair[0].iter().rev().take_while(|run| run.y == last_y).count();
+        let cur_end = cur_start + pair[1].iter().take_while(|run| run.y == pair[1][0].y).count();
+        joined += join_rows(runs, prev_start, cur_start, cur_end, connectivity, &mut uf);
+    }
+    Ok((uf, runs.len() - joined))
+}
+
+/// Join connected runs of consecutive rows row by row, returns number of successful
+/// unions.
+fn union_rows(runs: &[Run], connectivity: Connectivity, uf: &mut UnionFind, ctx: &mut Context) -> Result<usize, RleError> {
+    let mut joined = 0;
+    // start of previous row and start of current row
+    let mut prev_start = 0;
+    let mut cur_start = 0;
+    while cur_start < runs.len() {
+        ctx.step(cur_start, runs.len())?;
+        let y = runs[cur_start].y;
+        let cur_end = cur_start + runs[cur_start..].iter().take_while(|run| run.y == y).count();
+        if prev_start < cur_start {
+            joined += join_rows(runs, prev_start, cur_start, cur_end, connectivity, uf);
+        }
+        prev_start = cur_start;
+        cur_start = cur_end;
+    }
+    Ok(joined)
+}
+
+/// Join connected runs of row runs[prev_start..cur_start] and row
+/// runs[cur_start..cur_end] if rows are consecutive, returns number of successful unions.
+fn join_rows(
+    runs: &[Run],
+    prev_start: usize,
+    cur_start: usize,
+    cur_end: usize,
+    connectivity: Connectivity,
+    uf: &mut UnionFind,
+) -> usize {
+    let mut joined = 0;
+    if runs[prev_start].y + 1 != runs[cur_start].y {
+        return joined;
+    }
+    // two pointer walk over previous and current row
+    let (mut i, mut j) = (prev_start, cur_start);
+    while i < cur_start && j < cur_end {
+        if connected(runs[i], runs[j], connectivity) && uf.union(i, j) {
+            joined += 1;
+        }
+        if runs[i].x_end < runs[j].x_end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    joined
+}
+
+/// Split rle in connected components, one RLE per component ordered by first run.
+pub fn components(rle: &RLE, connectivity: Connectivity) -> Vec<RLE> {
+    components_with_progress(rle, connectivity, &mut Context::new()).unwrap()
+}
+
+fn components_with_progress(rle: &RLE, connectivity: Connectivity, ctx: &mut Context) -> Result<Vec<RLE>, RleError> {
+    let runs = rle.canonical_runs();
+    let (labels, count) = label_runs(&runs, connectivity, ctx)?;
+    let mut res = vec![Vec::new(); count];
+    for (&run, &label) in runs.iter().zip(&labels) {
+        res[label].push(run);
+    }
+    Ok(res.into_iter().map(|runs| RLE::from_runs(rle.width(), rle.height(), runs)).collect())
+}
+
+/// Number of pixels of every component, labels and count are from `label_runs`.
+fn component_areas(runs: &[Run], labels: &[usize], count: usize) -> Vec<usize> {
+    let mut areas = vec![0; count];
+    for (run, &label) in runs.iter().zip(labels) {
+        areas[label] += (run.x_end - run.x_start + 1) as usize;
+    }
+    areas
+}
+
+/// Keep only runs of components selected by keep, which gets canonical runs, their
+/// labels and number of components and returns for every component if it is kept.
+fn retain_components<F>(rle: &RLE, connectivity: Connectivity, keep: F) -> RLE
+where
+    F: FnOnce(&[Run], &[usize], usize) -> Vec<bool>,
+{
+    let runs = rle.canonical_runs();
+    let (labels, count) = label_runs(&runs, connectivity, &mut Context::new()).unwrap();
+    let kept = keep(&runs, &labels, count);
+    let runs = runs.iter().zip(&labels)
+        .filter(|&(_, &label)| kept[label])
+        .map(|(&run, _)| run)
+        .collect();
+    // subset of canonical runs is canonical
+    RLE { width: rle.width(), height: rle.height(), runs }
+}
+
+/// Statistics of connected components of rle ordered by first run, all are
+/// accumulated in one pass over labeled runs.
+pub fn component_stats(rle: &RLE, connectivity: Connectivity) -> Vec<ComponentStats> {
+    let runs = rle.canonical_runs();
+    let (labels, count) = label_runs(&runs, connectivity, &mut Context::new()).unwrap();
+    // stats of every component with sums of x and y instead of centroid
+    let mut acc: Vec<(ComponentStats, i128, i128)> = Vec::with_capacity(count);
+    for (&run, &label) in runs.iter().zip(&labels) {
+        if label == acc.len() {
+            // first run of component, runs are ordered so it has smallest y
+            let stats = ComponentStats {
+                area: 0,
+                bbox: (run.x_start, run.y, run.x_end, run.y),
+                centroid: (0.0, 0.0),
+                mask: RLE::new(rle.width(), rle.height()),
+            };
+            acc.push((stats, 0, 0));
+        }
+        let (stats, sum_x, sum_y) = &mut acc[label];
+        let n = (run.x_end - run.x_start + 1) as i128;
+        stats.area += n as usize;
+        *sum_x += n * (run.x_start as i128 + run.x_end as i128) / 2;
+        *sum_y += n * run.y as i128;
+        stats.bbox.0 = stats.bbox.0.min(run.x_start);
+        stats.bbox.2 = stats.bbox.2.max(run.x_end);
+        stats.bbox.3 = run.y;
+        // subset of canonical runs is canonical
+        stats.mask.runs.push(run);
+    }
+    acc.into_iter()
+        .map(|(mut stats, sum_x, sum_y)| {
+            stats.centroid = (sum_x as f64 / stats.area as f64, sum_y as f64 / stats.area as f64);
+            stats
+        })
+        .collect()
+}
+
+/// Label connected components of rle, component i (ordered by first run) gets label
+/// i + 1 and background stays 0.
+pub fn label(rle: &RLE, connectivity: Connectivity) -> LabelRle {
+    let runs = rle.canonical_runs();
+    let (labels, _) = label_runs(&runs, connectivity, &mut Context::new()).unwrap();
+    let runs = runs.iter().zip(&labels)
+        .map(|(&run, &label)| LabelRun { run, label: label as u32 + 1 })
+        .collect();
+    LabelRle { width: rle.width(), height: rle.height(), runs }
+}
+
+/// Label connected components of rle and decode labels to dense label image stored
+/// row by row, see `label`.
+pub fn label_map(rle: &RLE, connectivity: Connectivity) -> Vec<u32> {
+    label(rle, connectivity).to_label_map()
+}
+
+impl RLE {
+    /// Split image in connected components, one RLE per component ordered by first run.
+    pub fn components(&self, connectivity: Connectivity) -> Vec<RLE> {
+        trace::instrument("components", self.runs().len(), || components(self, connectivity))
+    }
+
+    /// Label connected components, see `connected_components::label`.
+    pub fn label_components(&self, connectivity: Connectivity) -> LabelRle {
+        trace::instrument("label_components", self.runs().len(), || label(self, connectivity))
+    }
+
+    /// Number of connected components, counted while joining runs in union-find
+    /// without assigning labels or building masks.
+    pub fn count_objects(&self, connectivity: Connectivity) -> usize {
+        let runs = self.canonical_runs();
+        union_runs(&runs, connectivity, &mut Context::new()).unwrap().1
+    }
+
+    /// Statistics (area, bounding box, centroid and mask) of every connected component,
+    /// see `connected_components::component_stats`.
+    pub fn component_stats(&self, connectivity: Connectivity) -> Vec<ComponentStats> {
+        component_stats(self, connectivity)
+    }
+
+    /// Iterate over connected components ordered by first run as pairs of bounding box
+    /// (x_min, y_min, x_max, y_max) and mask of component cropped to bounding box, so
+    /// no mask has dimensions of whole image. Image is labeled once, masks are created
+    /// when iterator advances.
+    pub fn components_cropped(&self, connectivity: Connectivity) -> impl Iterator<Item = ((i32, i32, i32, i32), RLE)> {
+        let runs = self.canonical_runs();
+        let (labels, count) = label_runs(&runs, connectivity, &mut Context::new()).unwrap();
+        let mut grouped = vec![Vec::new(); count];
+        for (&run, &label) in runs.iter().zip(&labels) {
+            grouped[label].push(run);
+        }
+        grouped.into_iter().map(|runs| {
+            // runs are sorted so first and last run have smallest and largest y
+            let (y_min, y_max) = (runs[0].y, runs[runs.len() - 1].y);
+            let x_min = runs.iter().map(|run| run.x_start).min().unwrap();
+            let x_max = runs.iter().map(|run| run.x_end).max().unwrap();
+            let runs = runs.into_iter()
+                .map(|run| Run::new(run.x_start - x_min, run.x_end - x_min, run.y - y_min))
+                .collect();
+            let (width, height) = ((x_max - x_min + 1) as usize, (y_max - y_min + 1) as usize);
+            // shifted subset of canonical runs is canonical
+            ((x_min, y_min, x_max, y_max), RLE { width, height, runs })
+        })
+    }
+
+    /// Remove connected components which have less than min_area pixels (area opening).
+    pub fn remove_small_objects(&self, min_area: usize, connectivity: Connectivity) -> RLE {
+        trace::instrument("remove_small_objects", self.runs().len(), || {
+            retain_components(self, connectivity, |runs, labels, count| {
+                component_areas(runs, labels, count).into_iter().map(|area| area >= min_area).collect()
+            })
+        })
+    }
+
+    /// Get largest connected component, if more components have same area first one
+    /// (by first run) is returned. Returns None if image has no pixels set to 1.
+    pub fn largest_component(&self, connectivity: Connectivity) -> Option<RLE> {
+        trace::instrument("largest_component", self.runs().len(), || {
+            if self.is_empty() {
+                return None;
+            }
+            Some(retain_components(self, connectivity, |runs, labels, count| {
+                let areas = component_areas(runs, labels, count);
+                // first component with largest area
+                let largest = (0..areas.len()).rev().max_by_key(|&label| areas[label]);
+                (0..areas.len()).map(|label| Some(label) == largest).collect()
+            }))
+        })
+    }
+
+    /// Remove all connected components which touch edge of image.
+    pub fn clear_border(&self, connectivity: Connectivity) -> RLE {
+        trace::instrument("clear_border", self.runs().len(), || {
+            let (w, h) = (self.width() as i32, self.height() as i32);
+            retain_components(self, connectivity, |runs, labels, count| {
+                let mut kept = vec![true; count];
+                for (run, &label) in runs.iter().zip(labels) {
+                    if run.x_start == 0 || run.x_end == w - 1 || run.y == 0 || run.y == h - 1 {
+                        kept[label] = false;
+                    }
+                }
+                kept
+            })
+        })
+    }
+
+    /// Same as `components` but reports progress to ctx and returns
+    /// `RleError::Cancelled` if ctx is cancelled.
+    pub fn components_with_progress(&self, connectivity: Connectivity, ctx: &mut Context) -> Result<Vec<RLE>, RleError> {
+        trace::instrument("components", self.runs().len(), || components_with_progress(self, connectivity, ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn label_runs_test() {
+        let rle = RLE::from(&Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 1, 0, 1, 0,
+            1, 0, 0, 0, 1, 1,
+            1, 0, 1, 1, 0, 0,
+        ]));
+        let (labels, count) = label_runs(rle.runs(), Connectivity::Four, &mut Context::new()).unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(labels, vec![0, 1, 2, 1, 3, 1, 3, 4]);
+        let (labels, count) = label_runs(rle.runs(), Connectivity::Eight, &mut Context::new()).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(labels, vec![0, 1, 0, 1, 2, 1, 2, 1]);
+    }
+
+    #[test]
+    fn components_test() {
+        let rle = RLE::from(&Image::new(4, 2, vec![
+            1, 0, 0, 1,
+            1, 0, 1, 1,
+        ]));
+        let components = components(&rle, Connectivity::Four);
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].to_image(1), Image::new(4, 2, vec![
+            1, 0, 0, 0,
+            1, 0, 0, 0,
+        ]));
+        assert_eq!(&components[0] | &components[1], rle);
+    }
+
+    #[test]
+    fn label_test() {
+        let rle = RLE::from(&Image::new(5, 3, vec![
+            1, 1, 0, 0, 1,
+            0, 0, 1, 0, 1,
+            1, 0, 1, 1, 0,
+        ]));
+        assert_eq!(label_map(&rle, Connectivity::Four), vec![
+            1, 1, 0, 0, 2,
+            0, 0, 3, 0, 2,
+            4, 0, 3, 3, 0,
+        ]);
+        assert_eq!(label_map(&rle, Connectivity::Eight), vec![
+            1, 1, 0, 0, 1,
+            0, 0, 1, 0, 1,
+            2, 0, 1, 1, 0,
+        ]);
+        let labels = rle.label_components(Connectivity::Eight);
+        assert_eq!(labels.labels(), vec![1, 2]);
+        assert_eq!(labels.foreground(), rle);
+        for (i, component) in components(&rle, Connectivity::Eight).iter().enumerate() {
+            assert_eq!(&labels.extract(i as u32 + 1), component);
+        }
+    }
+
+    #[test]
+    fn components_with_progress_test() {
+        let rle = RLE::from(&Image::new(4, 3, vec![
+            1, 0, 0, 1,
+            1, 0, 1, 0,
+            0, 0, 1, 1,
+        ]));
+        let mut reported = Vec::new();
+        let res = rle.components_with_progress(
+            Connectivity::Four,
+            &mut Context::new().with_progress(|p| reported.push(p)),
+        );
+        assert_eq!(res, Ok(rle.components(Connectivity::Four)));
+        assert_eq!(reported.last(), Some(&1.0));
+
+        let token = crate::CancellationToken::new();
+        token.cancel();
+        let res = rle.components_with_progress(Connectivity::Four, &mut Context::new().with_cancellation(token));
+        assert_eq!(res, Err(RleError::Cancelled));
+    }
+
+    #[test]
+    fn remove_small_objects_test() {
+        let rle = RLE::from(&Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 1, 0, 1, 0,
+            1, 0, 0, 0, 1, 1,
+            1, 0, 1, 1, 0, 0,
+        ]));
+        assert_eq!(rle.remove_small_objects(0, Connectivity::Four), rle);
+        assert_eq!(rle.remove_small_objects(2, Connectivity::Four).to_image(1), Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 0, 0, 1, 0,
+            1, 0, 0, 0, 1, 1,
+            1, 0, 1, 1, 0, 0,
+        ]));
+        assert_eq!(rle.remove_small_objects(3, Connectivity::Four).to_image(1), Image::new(6, 4, vec![
+            0, 0, 0, 0, 1, 0,
+            0, 0, 0, 0, 1, 0,
+            0, 0, 0, 0, 1, 1,
+            0, 0, 0, 0, 0, 0,
+        ]));
+        // with 8-connectivity components have 3, 6 and 2 pixels
+        assert_eq!(rle.remove_small_objects(3, Connectivity::Eight).to_image(1), Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 1, 0, 1, 0,
+            0, 0, 0, 0, 1, 1,
+            0, 0, 1, 1, 0, 0,
+        ]));
+        assert_eq!(rle.remove_small_objects(6, Connectivity::Eight).to_image(1), Image::new(6, 4, vec![
+            0, 0, 0, 0, 1, 0,
+            0, 0, 0, 0, 1, 0,
+            0, 0, 0, 0, 1, 1,
+            0, 0, 1, 1, 0, 0,
+        ]));
+        assert!(rle.remove_small_objects(7, Connectivity::Eight).is_empty());
+    }
+
+    #[test]
+    fn largest_component_test() {
+        let rle = RLE::from(&Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 1, 0, 1, 0,
+            1, 0, 0, 0, 1, 1,
+            1, 0, 1, 1, 0, 0,
+        ]));
+        assert_eq!(rle.largest_component(Connectivity::Four), Some(rle.remove_small_objects(3, Connectivity::Four)));
+        assert_eq!(rle.largest_component(Connectivity::Eight), Some(rle.remove_small_objects(4, Connectivity::Eight)));
+        // ties go to first component
+        let two = RLE::from(&Image::new(5, 1, vec![1, 1, 0, 1, 1]));
+        assert_eq!(two.largest_component(Connectivity::Four), Some(RLE::from(&Image::new(5, 1, vec![1, 1, 0, 0, 0]))));
+        assert_eq!(RLE::new(3, 3).largest_component(Connectivity::Eight), None);
+    }
+
+    #[test]
+    fn clear_border_test() {
+        let rle = RLE::from(&Image::new(7, 6, vec![
+            1, 0, 0, 0, 0, 0, 0,
+            0, 1, 0, 0, 1, 1, 0,
+            0, 0, 0, 0, 1, 0, 0,
+            0, 1, 1, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 1, 1,
+            0, 0, 0, 1, 0, 0, 0,
+        ]));
+        assert_eq!(rle.clear_border(Connectivity::Four).to_image(1), Image::new(7, 6, vec![
+            0, 0, 0, 0, 0, 0, 0,
+            0, 1, 0, 0, 1, 1, 0,
+            0, 0, 0, 0, 1, 0, 0,
+            0, 1, 1, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        // pixel (1, 1) is diagonal neighbor of (0, 0)
+        assert_eq!(rle.clear_border(Connectivity::Eight).to_image(1), Image::new(7, 6, vec![
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 1, 0,
+            0, 0, 0, 0, 1, 0, 0,
+            0, 1, 1, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert!(RLE::ones(3, 3).clear_border(Connectivity::Four).is_empty());
+    }
+
+    #[test]
+    fn component_stats_test() {
+        let rle = RLE::from(&Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 1, 0, 1, 0,
+            1, 0, 0, 0, 1, 1,
+            1, 0, 1, 1, 0, 0,
+        ]));
+        let stats = rle.component_stats(Connectivity::Eight);
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].area, 3);
+        assert_eq!(stats[0].bbox, (0, 0, 2, 1));
+        assert_eq!(stats[0].centroid, (1.0, 1.0 / 3.0));
+        assert_eq!(stats[1].area, 6);
+        assert_eq!(stats[1].bbox, (2, 0, 5, 3));
+        assert_eq!(stats[1].centroid, (22.0 / 6.0, 11.0 / 6.0));
+        assert_eq!(stats[2].bbox, (0, 2, 0, 3));
+        for (stat, component) in stats.iter().zip(rle.components(Connectivity::Eight)) {
+            assert_eq!(stat.mask, component);
+            assert_eq!(stat.area, component.area());
+            assert_eq!(Some(stat.centroid), component.centroid());
+        }
+        assert!(RLE::new(3, 3).component_stats(Connectivity::Four).is_empty());
+    }
+
+    #[test]
+    fn count_objects_test() {
+        let rle = RLE::from(&Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 1, 0, 1, 0,
+            1, 0, 0, 0, 1, 1,
+            1, 0, 1, 1, 0, 0,
+        ]));
+        assert_eq!(rle.count_objects(Connectivity::Four), 5);
+        assert_eq!(rle.count_objects(Connectivity::Eight), 3);
+        // U shape whose arms are joined only in last row
+        let u = RLE::from(&Image::new(5, 3, vec![
+            1, 0, 1, 0, 1,
+            1, 0, 1, 0, 1,
+            1, 1, 1, 1, 1,
+        ]));
+        assert_eq!(u.count_objects(Connectivity::Four), 1);
+        assert_eq!(RLE::new(3, 3).count_objects(Connectivity::Eight), 0);
+    }
+
+    #[test]
+    fn components_cropped_test() {
+        let rle = RLE::from(&Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 0,
+            0, 0, 1, 0, 1, 0,
+            1, 0, 0, 0, 1, 1,
+            1, 0, 1, 1, 0, 0,
+        ]));
+        let crops: Vec<_> = rle.components_cropped(Connectivity::Eight).collect();
+        assert_eq!(crops.len(), 3);
+        assert_eq!(crops[0], ((0, 0, 2, 1), RLE::from(&Image::new(3, 2, vec![
+            1, 1, 0,
+            0, 0, 1,
+        ]))));
+        assert_eq!(crops[2], ((0, 2, 0, 3), RLE::ones(1, 2)));
+        let stats = rle.component_stats(Connectivity::Eight);
+        for ((bbox, crop), stat) in crops.iter().zip(&stats) {
+            assert_eq!(*bbox, stat.bbox);
+            let (x, y, _, _) = *bbox;
+            let mut full = RLE::new(6, 4);
+            full.paste(crop, x, y, crate::PasteMode::Or);
+            assert_eq!(full, stat.mask);
+        }
+        assert_eq!(RLE::new(4, 4).components_cropped(Connectivity::Four).count(), 0);
+    }
+}
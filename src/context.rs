@@ -0,0 +1,116 @@
+//! Progress reporting and cancellation of long running operations.
+use super::RleError;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Flag shared between threads which is used to cancel long running operation.
+/// Clones share same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of all operations using this token (or its clones).
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Context of long running operation (`*_with_progress` methods) with optional
+/// progress callback and cancellation token.
+/// Progress is reported as fraction in 0..=1, at most once per percent.
+/// When token is cancelled operation stops and returns `RleError::Cancelled`.
+#[derive(Default)]
+pub struct Context<'a> {
+    token: Option<CancellationToken>,
+    progress: Option<Box<dyn FnMut(f64) + 'a>>,
+    /// last reported progress in percents
+    reported: Option<u32>,
+}
+
+impl<'a> Context<'a> {
+    /// Create context without progress callback which can not be cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel operation when token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Call f with fraction of work done.
+    pub fn with_progress<F: FnMut(f64) + 'a>(mut self, f: F) -> Self {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
+    /// Report that done of total units of work are finished and check cancellation.
+    pub(crate) fn step(&mut self, done: usize, total: usize) -> Result<(), RleError> {
+        if let Some(token) = &self.token {
+            if token.is_cancelled() {
+                return Err(RleError::Cancelled);
+            }
+        }
+        if let Some(progress) = &mut self.progress {
+            let fraction = if total == 0 { 1.0 } else { done as f64 / total as f64 };
+            let percent = (fraction * 100.0) as u32;
+            let report = match self.reported {
+                Some(reported) => percent > reported,
+                None => true,
+            };
+            if report {
+                self.reported = Some(percent);
+                progress(fraction);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Context<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("token", &self.token)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_step_test() {
+        let mut reported = Vec::new();
+        {
+            let mut ctx = Context::new().with_progress(|p| reported.push(p));
+            for i in 0..=400 {
+                ctx.step(i, 400).unwrap();
+            }
+        }
+        assert_eq!(reported.len(), 101);
+        assert_eq!(reported[0], 0.0);
+        assert_eq!(reported[100], 1.0);
+
+        let token = CancellationToken::new();
+        let mut ctx = Context::new().with_cancellation(token.clone());
+        assert_eq!(ctx.step(0, 1), Ok(()));
+        token.cancel();
+        assert_eq!(ctx.step(1, 1), Err(RleError::Cancelled));
+    }
+}
@@ -0,0 +1,240 @@
+//! Contours traced along pixel edges with hierarchy of outer boundaries and holes.
+//!
+//! Every connected component has one outer contour and every hole (component of
+//! background, with opposite connectivity, which does not touch image border) has one
+//! hole contour. Points are corners of pixels, pixel (x, y) has corners (x, y) and
+//! (x + 1, y + 1), so polygons enclose exactly the pixels of mask.
+use super::connected_components::label_runs;
+use super::{Connectivity, Context, Run, RLE};
+
+/// Whether contour is outer boundary of component or boundary of hole in component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContourKind {
+    /// Outer boundary, points are in clockwise order (with y axis pointing down).
+    Outer,
+    /// Boundary of hole, points are in counter-clockwise order.
+    Hole,
+}
+
+/// One contour of `RLE::contours` with links to enclosing and enclosed contours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contour {
+    /// corners (x, y) of polygon, only points where direction changes
+    pub points: Vec<(i32, i32)>,
+    /// outer boundary or hole
+    pub kind: ContourKind,
+    /// index of enclosing contour, hole for outer contours and outer contour for holes
+    pub parent: Option<usize>,
+    /// indices of directly enclosed contours
+    pub children: Vec<usize>,
+}
+
+/// Directions east, south, west, north as (dx, dy), turning right adds 1.
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+impl RLE {
+    /// Get contours of all components and their holes as tree, like `RETR_TREE` in
+    /// OpenCV. Outer contours are first (ordered as components), holes follow ordered
+    /// by their top left pixel. Parent of hole is outer contour of component around it
+    /// and parent of outer contour is hole in which component lies (None for
+    /// components which are not inside of other component), so outer contours with
+    /// their children are polygons with holes (two level hierarchy of `RETR_CCOMP`).
+    /// Holes are background components with opposite connectivity.
+    pub fn contours(&self, connectivity: Connectivity) -> Vec<Contour> {
+        let hole_connectivity = match connectivity {
+            Connectivity::Four => Connectivity::Eight,
+            Connectivity::Eight => Connectivity::Four,
+        };
+        let fg = Labeled::new(self, connectivity);
+        let bg = Labeled::new(&!self, hole_connectivity);
+        let (width, height) = (self.width as i32, self.height as i32);
+        // background components touching border are outside of all components
+        let mut hole_index = vec![None; bg.crops.len()];
+        let mut n_holes = 0;
+        for (label, &((x_min, y_min, x_max, y_max), _)) in bg.crops.iter().enumerate() {
+            if x_min > 0 && y_min > 0 && x_max < width - 1 && y_max < height - 1 {
+                hole_index[label] = Some(fg.crops.len() + n_holes);
+                n_holes += 1;
+            }
+        }
+        let mut contours: Vec<Contour> = fg.crops.iter()
+            .map(|&((x, y, _, _), ref crop)| {
+                // left neighbor of top left pixel is in background around component
+                let parent = bg.label_at(x + crop.runs[0].x_start - 1, y).and_then(|label| hole_index[label]);
+                Contour { points: trace(crop, connectivity, x, y), kind: ContourKind::Outer, parent, children: Vec::new() }
+            })
+            .collect();
+        for (label, &((x, y, _, _), ref crop)) in bg.crops.iter().enumerate() {
+            if hole_index[label].is_some() {
+                // top neighbor of top left pixel is in component around hole
+                let parent = fg.label_at(x + crop.runs[0].x_start, y - 1);
+                let mut points = trace(crop, hole_connectivity, x, y);
+                // hole is traced clockwise as region, reverse keeping first point
+                points[1..].reverse();
+                contours.push(Contour { points, kind: ContourKind::Hole, parent, children: Vec::new() });
+            }
+        }
+        for i in 0..contours.len() {
+            if let Some(parent) = contours[i].parent {
+                contours[parent].children.push(i);
+            }
+        }
+        contours
+    }
+}
+
+/// Labeled runs and components cropped to their bounding boxes.
+struct Labeled {
+    runs: Vec<Run>,
+    labels: Vec<usize>,
+    crops: Vec<((i32, i32, i32, i32), RLE)>,
+}
+
+impl Labeled {
+    fn new(rle: &RLE, connectivity: Connectivity) -> Self {
+        let runs = rle.canonical_runs().into_owned();
+        let (labels, _) = label_runs(&runs, connectivity, &mut Context::new()).unwrap();
+        let crops = rle.components_cropped(connectivity).collect();
+        Labeled { runs, labels, crops }
+    }
+
+    /// Label of component containing pixel (x, y).
+    fn label_at(&self, x: i32, y: i32) -> Option<usize> {
+        let i = self.runs.partition_point(|run| (run.y, run.x_start) <= (y, x));
+        if i > 0 && self.runs[i - 1].y == y && self.runs[i - 1].x_end >= x {
+            Some(self.labels[i - 1])
+        } else {
+            None
+        }
+    }
+}
+
+/// Trace boundary of connected region keeping region on the right side, starting at
+/// top left corner of its top left pixel. Region is cropped and (x, y) is position of
+/// its (0, 0) pixel in image.
+/// When only diagonal pixels are in region at some corner, boundary goes between them
+/// for 8-connectivity and around them for 4-connectivity.
+fn trace(region: &RLE, connectivity: Connectivity, x: i32, y: i32) -> Vec<(i32, i32)> {
+    let indexed = region.indexed();
+    let start = (region.runs[0].x_start, 0);
+    let mut points = vec![(x + start.0, y)];
+    let (mut cx, mut cy, mut d) = (start.0, start.1, 0);
+    loop {
+        cx += DIRECTIONS[d].0;
+        cy += DIRECTIONS[d].1;
+        if (cx, cy) == start {
+            return points;
+        }
+        // pixels ahead of corner on the left and on the right side
+        let (ldx, ldy) = DIRECTIONS[(d + 3) & 3];
+        let (fdx, fdy) = DIRECTIONS[d];
+        let pixel = |dx: i32, dy: i32| indexed.contains(cx + (dx + fdx - 1) / 2, cy + (dy + fdy - 1) / 2);
+        let left = pixel(ldx, ldy);
+        let right = pixel(-ldx, -ldy);
+        let next = match (left, right, connectivity) {
+            (true, true, _) | (true, false, Connectivity::Eight) => (d + 3) & 3,
+            (false, true, _) => d,
+            _ => (d + 1) & 3,
+        };
+        if next != d {
+            points.push((x + cx, y + cy));
+        }
+        d = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    /// Twice the signed area of polygon, positive for clockwise order with y down.
+    fn signed_area2(points: &[(i32, i32)]) -> i64 {
+        (0..points.len())
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64
+            })
+            .sum()
+    }
+
+    #[test]
+    fn contours_test() {
+        let rle = RLE::from(&Image::new(9, 7, vec![
+            1, 1, 1, 1, 1, 1, 0, 0, 0,
+            1, 0, 0, 0, 0, 1, 0, 1, 0,
+            1, 0, 1, 1, 0, 1, 0, 0, 0,
+            1, 0, 1, 0, 0, 1, 0, 0, 0,
+            1, 0, 0, 0, 0, 1, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+        let contours = rle.contours(Connectivity::Four);
+        assert_eq!(contours.len(), 4);
+        assert_eq!(contours[0], Contour {
+            points: vec![(0, 0), (6, 0), (6, 6), (0, 6)],
+            kind: ContourKind::Outer,
+            parent: None,
+            children: vec![3],
+        });
+        assert_eq!(contours[1].points, vec![(7, 1), (8, 1), (8, 2), (7, 2)]);
+        assert_eq!(contours[2].points, vec![(2, 2), (4, 2), (4, 3), (3, 3), (3, 4), (2, 4)]);
+        assert_eq!(contours[2].parent, Some(3));
+        assert_eq!(contours[3], Contour {
+            points: vec![(1, 1), (1, 5), (5, 5), (5, 1)],
+            kind: ContourKind::Hole,
+            parent: Some(0),
+            children: vec![2],
+        });
+        assert_eq!(signed_area2(&contours[3].points), -32);
+    }
+
+    #[test]
+    fn diagonal_test() {
+        let rle = RLE::from(&Image::new(4, 4, vec![
+            0, 1, 0, 0,
+            1, 0, 1, 0,
+            0, 1, 0, 0,
+            0, 0, 0, 0,
+        ]));
+        // 4 separate pixels, background in the middle is connected with outside
+        let contours = rle.contours(Connectivity::Four);
+        assert_eq!(contours.len(), 4);
+        assert!(contours.iter().all(|c| c.kind == ContourKind::Outer && c.points.len() == 4));
+        // one component with hole in the middle
+        let contours = rle.contours(Connectivity::Eight);
+        assert_eq!(contours.len(), 2);
+        assert_eq!(contours[0].points, vec![
+            (1, 0), (2, 0), (2, 1), (3, 1), (3, 2), (2, 2), (2, 3), (1, 3), (1, 2), (0, 2), (0, 1), (1, 1),
+        ]);
+        assert_eq!(contours[1].points, vec![(1, 1), (1, 2), (2, 2), (2, 1)]);
+        assert_eq!(contours[1].parent, Some(0));
+    }
+
+    #[test]
+    fn area_test() {
+        let rle = RLE::from(&Image::new(10, 8, vec![
+            1, 1, 1, 1, 1, 1, 1, 1, 0, 1,
+            1, 0, 0, 0, 0, 0, 0, 1, 1, 0,
+            1, 0, 1, 1, 1, 1, 0, 1, 0, 1,
+            1, 0, 1, 0, 0, 1, 0, 1, 0, 0,
+            1, 0, 1, 0, 1, 1, 0, 1, 1, 0,
+            1, 0, 1, 1, 0, 1, 0, 0, 1, 0,
+            1, 0, 0, 0, 1, 0, 1, 1, 0, 1,
+            1, 1, 1, 1, 1, 1, 1, 0, 1, 1,
+        ]));
+        for &connectivity in &[Connectivity::Four, Connectivity::Eight] {
+            let contours = rle.contours(connectivity);
+            // outer polygons are positive and holes negative, together they cover mask
+            let total: i64 = contours.iter().map(|c| signed_area2(&c.points)).sum();
+            assert_eq!(total, 2 * rle.area() as i64);
+            for (i, contour) in contours.iter().enumerate() {
+                assert_eq!(signed_area2(&contour.points) > 0, contour.kind == ContourKind::Outer);
+                for &child in &contour.children {
+                    assert_eq!(contours[child].parent, Some(i));
+                    assert_ne!(contours[child].kind, contour.kind);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Coordinate arithmetic. With `strict` feature `add` and `from_usize` are
+//! checked and return `RleError::CoordinateOverflow` instead of overflowing, without
+//! it they compile to plain integer operations. `narrow` and `dimension` are always
+//! checked.
+use super::RleError;
+
+#[inline(always)]
+pub(crate) fn add(a: i32, b: i32) -> Result<i32, RleError> {
+    #[cfg(feature = "strict")]
+    {
+        a.checked_add(b).ok_or(RleError::CoordinateOverflow)
+    }
+    #[cfg(not(feature = "strict"))]
+    {
+        Ok(a + b)
+    }
+}
+
+/// Narrow coordinate computed in i64 to i32. Checked with and without `strict`
+/// feature, used for offsets of structuring elements which can leave i32 range even
+/// for runs inside of image.
+#[inline(always)]
+pub(crate) fn narrow(a: i64) -> Result<i32, RleError> {
+    use std::convert::TryFrom;
+    i32::try_from(a).map_err(|_| RleError::CoordinateOverflow)
+}
+
+/// Convert dimension (width, height) to coordinate, checked with and without `strict`
+/// feature (see `narrow`).
+#[inline(always)]
+pub(crate) fn dimension(a: usize) -> Result<i32, RleError> {
+    use std::convert::TryFrom;
+    i32::try_from(a).map_err(|_| RleError::CoordinateOverflow)
+}
+
+/// Convert dimension (width, height) to coordinate.
+#[inline(always)]
+pub(crate) fn from_usize(a: usize) -> Result<i32, RleError> {
+    #[cfg(feature = "strict")]
+    {
+        use std::convert::TryFrom;
+        i32::try_from(a).map_err(|_| RleError::CoordinateOverflow)
+    }
+    #[cfg(not(feature = "strict"))]
+    {
+        Ok(a as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrow_test() {
+        assert_eq!(narrow(i32::MIN as i64), Ok(i32::MIN));
+        assert_eq!(narrow(i32::MAX as i64 + 1), Err(RleError::CoordinateOverflow));
+        assert_eq!(narrow(i32::MIN as i64 - 1), Err(RleError::CoordinateOverflow));
+        assert_eq!(dimension(i32::MAX as usize), Ok(i32::MAX));
+        assert_eq!(dimension(i32::MAX as usize + 1), Err(RleError::CoordinateOverflow));
+    }
+
+    #[cfg(feature = "strict")]
+    #[test]
+    fn checked_test() {
+        assert_eq!(add(i32::MAX - 1, 1), Ok(i32::MAX));
+        assert_eq!(add(i32::MAX, 1), Err(RleError::CoordinateOverflow));
+        assert_eq!(add(i32::MIN, -1), Err(RleError::CoordinateOverflow));
+        assert_eq!(from_usize(i32::MAX as usize), Ok(i32::MAX));
+        assert_eq!(from_usize(i32::MAX as usize + 1), Err(RleError::CoordinateOverflow));
+    }
+}
@@ -0,0 +1,184 @@
+//! Dilation and erosion with large structuring elements decomposed into repeated
+//! dilations with small ones. Number of primary runs of dilation is product of number
+//! of runs of image and of structuring element, so for large L1 or Linf kernels it is
+//! much cheaper to dilate several times with small kernel (or once per axis with lines).
+//! Dilation with single row or single column is done directly on runs, see `dilate_line`.
+use super::rle::shifted_runs;
+use super::{coord, union_iter, Kernel, RleError, Run, RLE};
+
+impl RLE {
+    /// Dilate n times with s, same as dilating once with n-fold Minkowski sum of s.
+    /// Intermediate results are not clipped, so result is exact also near image border.
+    pub fn dilate_iterative<K: Kernel + ?Sized>(&self, s: &K, n: usize) -> Self {
+        let mut res = self.clone();
+        for _ in 0..n {
+            res = res.dilate(s);
+        }
+        res
+    }
+
+    /// Erode n times with s, same as eroding once with n-fold Minkowski sum of s.
+    pub fn erode_iterative<K: Kernel + ?Sized>(&self, s: &K, n: usize) -> Self {
+        // complement is taken only once so that pixels outside of image stay 1
+        !&(!self).dilate_iterative(s, n)
+    }
+
+    /// Same as `dilate(&RLE::l1_structuring(k))` but done as k dilations with
+    /// `l1_structuring(1)` which has only 3 runs.
+    pub fn dilate_l1(&self, k: usize) -> Self {
+        self.dilate_iterative(&RLE::l1_structuring(1), k)
+    }
+
+    /// Same as `erode(&RLE::l1_structuring(k))`, see `dilate_l1`.
+    pub fn erode_l1(&self, k: usize) -> Self {
+        self.erode_iterative(&RLE::l1_structuring(1), k)
+    }
+
+    /// Same as `dilate(&RLE::linf_structuring(k))` but done as dilation with
+    /// horizontal and then with vertical line (separable kernel).
+    pub fn dilate_linf(&self, k: usize) -> Self {
+        self.dilate(&RLE::hline_structuring(2 * k + 1)).dilate(&RLE::vline_structuring(2 * k + 1))
+    }
+
+    /// Same as `erode(&RLE::linf_structuring(k))`, see `dilate_linf`.
+    pub fn erode_linf(&self, k: usize) -> Self {
+        !&(!self).dilate_linf(k)
+    }
+
+    /// Same as `dilate(&RLE::hline_structuring(len))`, runs are only widened.
+    pub fn dilate_h(&self, len: usize) -> Self {
+        self.dilate(&RLE::hline_structuring(len))
+    }
+
+    /// Same as `erode(&RLE::hline_structuring(len))`.
+    pub fn erode_h(&self, len: usize) -> Self {
+        self.erode(&RLE::hline_structuring(len))
+    }
+
+    /// Same as `dilate(&RLE::vline_structuring(len))`, done with log(len) unions
+    /// of shifted runs.
+    pub fn dilate_v(&self, len: usize) -> Self {
+        self.dilate(&RLE::vline_structuring(len))
+    }
+
+    /// Same as `erode(&RLE::vline_structuring(len))`.
+    pub fn erode_v(&self, len: usize) -> Self {
+        self.erode(&RLE::vline_structuring(len))
+    }
+}
+
+/// Check if structuring element is single run or column of equal runs in consecutive
+/// rows (for example vertical line or rectangle).
+pub(crate) fn is_line(s_runs: &[Run]) -> bool {
+    match s_runs.first() {
+        Some(first) => s_runs.iter().zip(first.y..).all(|(run, y)| {
+            run.x_start == first.x_start && run.x_end == first.x_end && run.y == y
+        }),
+        None => false,
+    }
+}
+
+/// Dilate sorted runs with structuring element which is line (see `is_line`) without
+/// sorting primary runs: runs are widened by first run of s (see `shifted_runs`) and
+/// stacked with log(rows of s) unions of shifted copies.
+/// Returns None if s is not line.
+pub(crate) fn dilate_line(runs: &[Run], s_runs: &[Run], anchor: (i32, i32)) -> Option<Result<Vec<Run>, RleError>> {
+    if !is_line(s_runs) {
+        return None;
+    }
+    Some(shifted_runs(runs, s_runs[0], anchor).and_then(|mut res| {
+        // res is union of shifts by 0..len rows up
+        let mut len = 1;
+        while len < s_runs.len() {
+            let step = std::cmp::min(len, s_runs.len() - len);
+            let shifted = res.iter()
+                .map(|&run| Ok(Run { y: coord::narrow(run.y as i64 - step as i64)?, ..run }))
+                .collect::<Result<Vec<Run>, RleError>>()?;
+            res = union_iter(res.iter().copied(), shifted).collect();
+            len += step;
+        }
+        Ok(res)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CollectRle, Image, StructuringElement};
+
+    fn sample() -> RLE {
+        RLE::from(&Image::new(12, 9, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0,
+            0, 1, 1, 0, 1, 1, 1, 1, 1, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        ]))
+    }
+
+    #[test]
+    fn decomposed_test() {
+        let rle = sample();
+        for k in 0..4 {
+            assert_eq!(rle.dilate_l1(k), rle.dilate(&RLE::l1_structuring(k)), "{}", k);
+            assert_eq!(rle.erode_l1(k), rle.erode(&RLE::l1_structuring(k)), "{}", k);
+            assert_eq!(rle.dilate_linf(k), rle.dilate(&RLE::linf_structuring(k)), "{}", k);
+            assert_eq!(rle.erode_linf(k), rle.erode(&RLE::linf_structuring(k)), "{}", k);
+        }
+    }
+
+    #[test]
+    fn iterative_test() {
+        let rle = sample();
+        let s = RLE::from(&Image::new(3, 2, vec![
+            1, 0, 1,
+            0, 1, 1,
+        ]));
+        assert_eq!(rle.dilate_iterative(&s, 0), rle);
+        assert_eq!(rle.dilate_iterative(&s, 2), rle.dilate(&s).dilate(&s));
+        // eroding twice clips in between, which is same here because s is small
+        assert_eq!(rle.erode_iterative(&s, 2), !&(!&rle).dilate(&s).dilate(&s));
+    }
+
+    /// Dilation with all primary runs sorted and merged.
+    fn generic_dilate(rle: &RLE, s: &StructuringElement) -> RLE {
+        let (ax, ay) = s.anchor();
+        let runs = rle.runs().iter()
+            .flat_map(|a| s.kernel_runs().iter().map(move |b| Run::new(a.x_start + ax - b.x_end, a.x_end + ax - b.x_start, a.y + ay - b.y)))
+            .collect();
+        RLE::from_runs(rle.width(), rle.height(), runs)
+    }
+
+    #[test]
+    fn line_test() {
+        let rle = sample();
+        assert!(is_line(RLE::rect_structuring(3, 4).runs()));
+        assert!(!is_line(RLE::l1_structuring(1).runs()));
+        assert!(!is_line(&[]));
+        let kernels = vec![
+            StructuringElement::centered(RLE::hline_structuring(4)),
+            StructuringElement::new(RLE::hline_structuring(3), 0, 0),
+            StructuringElement::centered(RLE::vline_structuring(1)),
+            StructuringElement::centered(RLE::vline_structuring(7)),
+            StructuringElement::new(RLE::vline_structuring(6), 0, 5),
+            StructuringElement::new(RLE::rect_structuring(2, 5), 1, 1),
+            StructuringElement::from_points(&[(3, 2), (3, 3), (3, 4)]),
+        ];
+        for s in &kernels {
+            assert!(is_line(s.kernel_runs()));
+            let expected = generic_dilate(&rle, s);
+            assert_eq!(rle.dilate(s).runs(), expected.runs());
+            assert_eq!(rle.erode(s), !&generic_dilate(&!&rle, s));
+        }
+        for len in 0..6 {
+            assert_eq!(rle.dilate_h(len).runs(), generic_dilate(&rle, &RLE::hline_structuring(len).into()).runs());
+            assert_eq!(rle.dilate_v(len).runs(), generic_dilate(&rle, &RLE::vline_structuring(len).into()).runs());
+            assert_eq!(rle.erode_h(len), !&rle.flip_bits_iter().collect_into_rle(12, 9).dilate_h(len));
+            assert_eq!(rle.erode_v(len), !&rle.flip_bits_iter().collect_into_rle(12, 9).dilate_v(len));
+        }
+    }
+}
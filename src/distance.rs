@@ -0,0 +1,95 @@
+use super::RLE;
+
+/// Squared euclidean distance used for pixels which have no set pixel to measure to.
+const INF: f64 = 1e20;
+
+/// One dimensional squared distance transform of sampled function f
+/// (Felzenszwalb & Huttenlocher, lower envelope of parabolas).
+fn transform_1d(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+    for q in 1..n {
+        let intersection = |p: usize| {
+            ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2 * q - 2 * p) as f64
+        };
+        let mut s = intersection(v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+    k = 0;
+    for (q, d) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let dq = q as f64 - v[k] as f64;
+        *d = dq * dq + f[v[k]];
+    }
+}
+
+/// Squared euclidean distance of every pixel (row by row) to nearest pixel set in rle.
+/// Set pixels have distance 0, if rle has no set pixels all distances are very large (1e20).
+pub(crate) fn squared_distance_map(rle: &RLE) -> Vec<f64> {
+    let (w, h) = (rle.width(), rle.height());
+    let mut map = vec![INF; w * h];
+    for run in rle.canonical_runs().iter() {
+        let start = run.y as usize * w;
+        for d in &mut map[start + run.x_start as usize..=start + run.x_end as usize] {
+            *d = 0.0;
+        }
+    }
+    let n = std::cmp::max(w, h);
+    let mut f = vec![0.0; n];
+    let mut d = vec![0.0; n];
+    let mut v = vec![0; n];
+    let mut z = vec![0.0; n + 1];
+    // columns
+    for x in 0..w {
+        for y in 0..h {
+            f[y] = map[y * w + x];
+        }
+        transform_1d(&f[..h], &mut d[..h], &mut v, &mut z);
+        for y in 0..h {
+            map[y * w + x] = d[y];
+        }
+    }
+    // rows
+    for row in map.chunks_mut(std::cmp::max(w, 1)) {
+        f[..w].copy_from_slice(row);
+        transform_1d(&f[..w], row, &mut v, &mut z);
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn squared_distance_map_test() {
+        let rle = RLE::from(&Image::new(4, 3, vec![
+            0, 0, 0, 0,
+            0, 1, 0, 0,
+            0, 0, 0, 0,
+        ]));
+        assert_eq!(squared_distance_map(&rle), vec![
+            2.0, 1.0, 2.0, 5.0,
+            1.0, 0.0, 1.0, 4.0,
+            2.0, 1.0, 2.0, 5.0,
+        ]);
+        assert!(squared_distance_map(&RLE::new(2, 2)).iter().all(|&d| d >= INF));
+        assert!(squared_distance_map(&RLE::new(0, 3)).is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! Morphology pipelines for document (scanned text) images where text and lines are 1.
+use super::{Run, RLE};
+
+/// Horizontal line structuring element with odd length (rounded up) so it is symmetric.
+fn hline(len: usize) -> RLE {
+    let len = len | 1;
+    RLE::from_runs(len, 1, vec![Run::new(0, len as i32 - 1, 0)])
+}
+
+/// Vertical line structuring element with odd length (rounded up) so it is symmetric.
+fn vline(len: usize) -> RLE {
+    let len = len | 1;
+    RLE::from_runs(1, len, (0..len as i32).map(|y| Run::new(0, 0, y)).collect())
+}
+
+/// Opening with symmetric structuring element.
+fn open(mask: &RLE, s: &RLE) -> RLE {
+    mask.erode_zero_border(s).dilate(s)
+}
+
+/// Grow marker with structuring element s but only into pixels of limit,
+/// until nothing changes.
+fn grow_within(marker: &RLE, limit: &RLE, s: &RLE) -> RLE {
+    let mut current = marker & limit;
+    loop {
+        let next = &current.dilate(s) & limit;
+        if next == current {
+            return current;
+        }
+        current = next;
+    }
+}
+
+/// Remove horizontal and vertical lines (for example ruled lines or table borders) which
+/// are at least min_line_len pixels long (rounded up to odd) from document mask.
+///
+/// Lines are found by opening with long horizontal and vertical line structuring elements
+/// and removed. Pixels of line which are between text strokes on both sides of line
+/// (above and below for horizontal lines, left and right for vertical lines) are kept so
+/// strokes crossing lines are not cut.
+pub fn remove_lines(mask: &RLE, min_line_len: usize) -> RLE {
+    let horizontal = open(mask, &hline(min_line_len));
+    let vertical = open(mask, &vline(min_line_len));
+    let text = mask - &(&horizontal | &vertical);
+    if text.runs().is_empty() {
+        return text;
+    }
+    // 2 pixel structuring elements which shift image by one pixel in one direction
+    let down = RLE::from_runs(1, 3, vec![Run::new(0, 0, 0), Run::new(0, 0, 1)]);
+    let up = RLE::from_runs(1, 3, vec![Run::new(0, 0, 1), Run::new(0, 0, 2)]);
+    let right = RLE::from_runs(3, 1, vec![Run::new(0, 1, 0)]);
+    let left = RLE::from_runs(3, 1, vec![Run::new(1, 2, 0)]);
+
+    let restore = |lines: &RLE, a: &RLE, b: &RLE| {
+        let limit = &text | lines;
+        let from_a = &grow_within(&text, &limit, a) & lines;
+        let from_b = &grow_within(&text, &limit, b) & lines;
+        &from_a & &from_b
+    };
+    let mut result = text.clone();
+    result |= restore(&horizontal, &down, &up);
+    result |= restore(&vertical, &right, &left);
+    result
+}
+
+/// Horizontal run-length smoothing (RLSA): gaps of at most gap pixels between two runs
+/// in same row are filled. Gaps between runs and image border are not filled.
+pub fn rlsa_horizontal(mask: &RLE, gap: usize) -> RLE {
+    let mut runs: Vec<Run> = Vec::with_capacity(mask.runs().len());
+    for &run in mask.canonical_runs().iter() {
+        match runs.last_mut() {
+            Some(last) if last.y == run.y && (run.x_start - last.x_end - 1) as usize <= gap => last.x_end = run.x_end,
+            _ => runs.push(run),
+        }
+    }
+    RLE::from_runs(mask.width(), mask.height(), runs)
+}
+
+/// Vertical run-length smoothing, gaps of at most gap pixels between two runs
+/// in same column are filled (see `rlsa_horizontal`).
+pub fn rlsa_vertical(mask: &RLE, gap: usize) -> RLE {
+    rlsa_horizontal(&mask.transpose(), gap).transpose()
+}
+
+/// Classic RLSA used to find text blocks: intersection of horizontal smoothing with
+/// h_gap and vertical smoothing with v_gap.
+pub fn rlsa(mask: &RLE, h_gap: usize, v_gap: usize) -> RLE {
+    &rlsa_horizontal(mask, h_gap) & &rlsa_vertical(mask, v_gap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn remove_lines_test() {
+        let mask = RLE::from(&Image::new(10, 7, vec![
+            0, 0, 1, 0, 0, 0, 0, 1, 1, 0,
+            0, 0, 1, 0, 0, 0, 0, 1, 1, 0,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+        // vertical stroke crossing line is kept, stroke only touching line
+        // from one side is not extended
+        assert_eq!(remove_lines(&mask, 8).to_image(1), Image::new(10, 7, vec![
+            0, 0, 1, 0, 0, 0, 0, 1, 1, 0,
+            0, 0, 1, 0, 0, 0, 0, 1, 1, 0,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+    }
+
+    #[test]
+    fn remove_vertical_lines_test() {
+        let mask = RLE::from(&Image::new(7, 8, vec![
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+        ]));
+        assert_eq!(remove_lines(&mask, 7).to_image(1), Image::new(7, 8, vec![
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+    }
+
+    #[test]
+    fn rlsa_test() {
+        let mask = RLE::from(&Image::new(9, 4, vec![
+            0, 1, 0, 0, 1, 1, 0, 0, 0,
+            0, 1, 0, 0, 0, 0, 0, 1, 1,
+            0, 0, 0, 1, 0, 1, 0, 1, 0,
+            0, 1, 0, 1, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(rlsa_horizontal(&mask, 2).to_image(1), Image::new(9, 4, vec![
+            0, 1, 1, 1, 1, 1, 0, 0, 0,
+            0, 1, 0, 0, 0, 0, 0, 1, 1,
+            0, 0, 0, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(rlsa_vertical(&mask, 1).to_image(1), Image::new(9, 4, vec![
+            0, 1, 0, 0, 1, 1, 0, 0, 0,
+            0, 1, 0, 0, 0, 1, 0, 1, 1,
+            0, 1, 0, 1, 0, 1, 0, 1, 0,
+            0, 1, 0, 1, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(rlsa(&mask, 2, 1), &rlsa_horizontal(&mask, 2) & &rlsa_vertical(&mask, 1));
+        assert_eq!(rlsa_horizontal(&mask, 0), mask);
+    }
+}
@@ -0,0 +1,343 @@
+//! Drawing functions which set pixels of RLE to 1 (or 0) by changing runs directly,
+//! without going trough dense image.
+//! All shapes are clipped to image bounds.
+use super::{Run, RLE};
+
+/// Add runs to rle (OR), runs are clipped to image bounds.
+fn or_runs(rle: &mut RLE, runs: impl IntoIterator<Item = Run>) {
+    let (width, height) = (rle.width as i32, rle.height as i32);
+    let before = rle.runs.len();
+    rle.runs.extend(runs.into_iter()
+        .filter(|run| run.y >= 0 && run.y < height)
+        .map(|run| run
+            .with_x_start(std::cmp::max(0, run.x_start))
+            .with_x_end(std::cmp::min(width - 1, run.x_end))
+        )
+        .filter(|run| run.x_start <= run.x_end)
+    );
+    if rle.runs.len() != before {
+        rle.merge_overlapping_runs_mut();
+    }
+}
+
+/// Set rectangle with top left corner (x, y), width w and height h to 1.
+pub fn filled_rect(rle: &mut RLE, x: i32, y: i32, w: usize, h: usize) {
+    if w == 0 {
+        return;
+    }
+    or_runs(rle, (y..y + h as i32).map(|row| Run::new(x, x + w as i32 - 1, row)));
+}
+
+/// Set rectangle with top left corner (x, y), width w and height h to 0.
+pub fn clear_rect(rle: &mut RLE, x: i32, y: i32, w: usize, h: usize) {
+    if w == 0 || h == 0 {
+        return;
+    }
+    let (x_end, y_end) = (x + w as i32 - 1, y + h as i32 - 1);
+    let mut runs = Vec::with_capacity(rle.runs.len());
+    for &run in &rle.runs {
+        if run.y < y || run.y > y_end || run.x_end < x || run.x_start > x_end {
+            runs.push(run);
+            continue;
+        }
+        // keep parts of run left and right of rectangle
+        if run.x_start < x {
+            runs.push(run.with_x_end(x - 1));
+        }
+        if run.x_end > x_end {
+            runs.push(run.with_x_start(x_end + 1));
+        }
+    }
+    rle.runs = runs;
+}
+
+/// Runs of ellipse with center (cx, cy) and radii rx and ry.
+/// Pixel (cx + dx, cy + dy) is inside if (dx / rx)^2 + (dy / ry)^2 <= 1.
+pub(crate) fn ellipse_runs(cx: i32, cy: i32, rx: usize, ry: usize) -> impl Iterator<Item = Run> {
+    // i128 so that products of radii of large ellipses do not overflow
+    let (rx, ry) = (rx as i128, ry as i128);
+    (-ry..=ry).map(move |dy| {
+        // largest dx for which dx^2 * ry^2 + dy^2 * rx^2 <= rx^2 * ry^2
+        let dx = if ry == 0 {
+            rx
+        } else {
+            let limit = rx * rx * ry * ry - dy * dy * rx * rx;
+            let mut dx = ((limit as f64).sqrt() / ry as f64) as i128;
+            while dx * dx * ry * ry > limit {
+                dx -= 1;
+            }
+            while (dx + 1) * (dx + 1) * ry * ry <= limit {
+                dx += 1;
+            }
+            dx
+        };
+        Run::new(cx - dx as i32, cx + dx as i32, cy + dy as i32)
+    })
+}
+
+/// Set all pixels with distance to (cx, cy) at most r to 1.
+pub fn filled_circle(rle: &mut RLE, cx: i32, cy: i32, r: usize) {
+    or_runs(rle, ellipse_runs(cx, cy, r, r));
+}
+
+/// Set all pixels inside ellipse with center (cx, cy) and radii rx (horizontal)
+/// and ry (vertical) to 1.
+pub fn filled_ellipse(rle: &mut RLE, cx: i32, cy: i32, rx: usize, ry: usize) {
+    or_runs(rle, ellipse_runs(cx, cy, rx, ry));
+}
+
+/// Set pixels of line from (x0, y0) to (x1, y1) (both included) to 1.
+/// Uses Bresenham's algorithm so line is 8-connected.
+pub fn line(rle: &mut RLE, (x0, y0): (i32, i32), (x1, y1): (i32, i32)) {
+    // always go from left to right so pixels in same row are increasing
+    let ((x0, y0), (x1, y1)) = if x0 <= x1 { ((x0, y0), (x1, y1)) } else { ((x1, y1), (x0, y0)) };
+    let dx = x1 - x0;
+    let dy = -(y1 - y0).abs();
+    let step_y = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+    let mut runs: Vec<Run> = Vec::new();
+    loop {
+        match runs.last_mut() {
+            Some(last) if last.y == y => last.x_end = x,
+            _ => runs.push(Run::new(x, x, y)),
+        }
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += 1;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+    or_runs(rle, runs);
+}
+
+/// Runs of area inside polygons (rings) using even-odd rule, so rings inside other
+/// rings are holes. Pixel is inside if its center (x + 0.5, y + 0.5) is inside.
+/// If any vertex has NaN or infinite coordinate no runs are returned.
+pub(crate) fn polygon_runs(rings: &[&[(f32, f32)]], height: usize) -> Vec<Run> {
+    let mut runs = Vec::new();
+    if !rings.iter().flat_map(|ring| ring.iter()).all(|&(x, y)| x.is_finite() && y.is_finite()) {
+        return runs;
+    }
+    let mut xs: Vec<f32> = Vec::new();
+    let (min_y, max_y) = rings.iter().flat_map(|ring| ring.iter())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &(_, y)| (min.min(y), max.max(y)));
+    if min_y > max_y {
+        return runs;
+    }
+    let start = std::cmp::max(0, (min_y - 0.5).floor() as i64);
+    let end = std::cmp::min(height as i64 - 1, (max_y - 0.5).ceil() as i64);
+    for y in start..=end {
+        let yc = y as f32 + 0.5;
+        xs.clear();
+        for ring in rings {
+            for i in 0..ring.len() {
+                let (x0, y0) = ring[i];
+                let (x1, y1) = ring[(i + 1) % ring.len()];
+                // half open interval so vertices are not counted twice
+                if (y0 <= yc && yc < y1) || (y1 <= yc && yc < y0) {
+                    xs.push(x0 + (yc - y0) / (y1 - y0) * (x1 - x0));
+                }
+            }
+        }
+        xs.sort_unstable_by(f32::total_cmp);
+        for pair in xs.chunks_exact(2) {
+            // pixels with center in [pair[0], pair[1])
+            let x_start = (pair[0] - 0.5).ceil() as i32;
+            let x_end = (pair[1] - 0.5).ceil() as i32 - 1;
+            if x_start <= x_end {
+                runs.push(Run::new(x_start, x_end, y as i32));
+            }
+        }
+    }
+    runs
+}
+
+/// Set pixels inside polygon with given vertices to 1.
+/// Pixel is inside if its center (x + 0.5, y + 0.5) is inside polygon (even-odd rule).
+/// Polygon with NaN or infinite vertex coordinate is not drawn.
+pub fn filled_polygon(rle: &mut RLE, vertices: &[(f32, f32)]) {
+    let runs = polygon_runs(&[vertices], rle.height);
+    or_runs(rle, runs);
+}
+
+impl RLE {
+    /// Set rectangle to 1, see `draw::filled_rect`.
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: usize, h: usize) {
+        filled_rect(self, x, y, w, h);
+    }
+
+    /// Set rectangle to 0, see `draw::clear_rect`.
+    pub fn clear_rect(&mut self, x: i32, y: i32, w: usize, h: usize) {
+        clear_rect(self, x, y, w, h);
+    }
+
+    /// Set circle to 1, see `draw::filled_circle`.
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, r: usize) {
+        filled_circle(self, cx, cy, r);
+    }
+
+    /// Set ellipse to 1, see `draw::filled_ellipse`.
+    pub fn fill_ellipse(&mut self, cx: i32, cy: i32, rx: usize, ry: usize) {
+        filled_ellipse(self, cx, cy, rx, ry);
+    }
+
+    /// Rasterize polygon with given vertices into new RLE of given size,
+    /// same as `filled_polygon` on empty RLE.
+    pub fn from_polygon(width: usize, height: usize, vertices: &[(f32, f32)]) -> Self {
+        Self::from_polygons(width, height, &[vertices])
+    }
+
+    /// Rasterize several rings (polygons and their holes) into new RLE of given size.
+    /// Even-odd rule is used for all rings together, so ring inside other ring is hole
+    /// and pixels where two polygons overlap are not set. If any vertex has NaN or
+    /// infinite coordinate returned RLE is empty.
+    pub fn from_polygons(width: usize, height: usize, rings: &[&[(f32, f32)]]) -> Self {
+        let mut rle = Self::new(width, height);
+        or_runs(&mut rle, polygon_runs(rings, height));
+        rle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn filled_rect_test() {
+        let mut rle = RLE::new(5, 4);
+        filled_rect(&mut rle, 1, 1, 2, 2);
+        // clipped at border
+        filled_rect(&mut rle, 3, -1, 4, 2);
+        assert_eq!(rle.to_image(1), Image::new(5, 4, vec![
+            0, 0, 0, 1, 1,
+            0, 1, 1, 0, 0,
+            0, 1, 1, 0, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        filled_rect(&mut rle, 0, 0, 0, 3);
+        assert_eq!(rle.runs().len(), 3);
+    }
+
+    #[test]
+    fn clear_rect_test() {
+        let mut rle = RLE::ones(5, 4);
+        rle.clear_rect(1, 1, 3, 2);
+        // clipped at border
+        rle.clear_rect(-2, 3, 3, 5);
+        rle.clear_rect(0, 0, 0, 4);
+        assert_eq!(rle.to_image(1), Image::new(5, 4, vec![
+            1, 1, 1, 1, 1,
+            1, 0, 0, 0, 1,
+            1, 0, 0, 0, 1,
+            0, 1, 1, 1, 1,
+        ]));
+        let mut expected = RLE::new(5, 4);
+        expected.fill_rect(0, 0, 5, 4);
+        expected.clear_rect(1, 1, 3, 2);
+        expected.clear_rect(0, 3, 1, 1);
+        assert_eq!(rle, expected);
+    }
+
+    #[test]
+    fn filled_circle_test() {
+        let mut rle = RLE::new(7, 7);
+        filled_circle(&mut rle, 3, 3, 3);
+        assert_eq!(rle, RLE::disk_structuring(3));
+        let mut rle = RLE::new(7, 7);
+        rle.fill_ellipse(3, 3, 3, 3);
+        rle.fill_circle(3, 3, 1);
+        assert_eq!(rle, RLE::disk_structuring(3));
+    }
+
+    #[test]
+    fn filled_ellipse_test() {
+        let mut rle = RLE::new(7, 5);
+        filled_ellipse(&mut rle, 3, 2, 3, 2);
+        assert_eq!(rle.to_image(1), Image::new(7, 5, vec![
+            0, 0, 0, 1, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            1, 1, 1, 1, 1, 1, 1,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 1, 0, 0, 0,
+        ]));
+        let mut rle = RLE::new(5, 1);
+        filled_ellipse(&mut rle, 2, 0, 1, 0);
+        assert_eq!(rle.to_image(1), Image::new(5, 1, vec![0, 1, 1, 1, 0]));
+        // rx^2 * ry^2 does not fit in i64
+        let runs: Vec<Run> = ellipse_runs(0, 0, 200_000, 100_000).collect();
+        assert_eq!(runs.len(), 200_001);
+        assert_eq!(runs[0], Run::new(0, 0, -100_000));
+        assert_eq!(runs[100_000], Run::new(-200_000, 200_000, 0));
+        // (dy / ry)^2 = 1 / 4 so dx = rx * sqrt(3) / 2
+        assert_eq!(runs[150_000], Run::new(-173_205, 173_205, 50_000));
+    }
+
+    #[test]
+    fn line_test() {
+        let mut rle = RLE::new(5, 3);
+        line(&mut rle, (4, 2), (0, 0));
+        assert_eq!(rle.to_image(1), Image::new(5, 3, vec![
+            1, 0, 0, 0, 0,
+            0, 1, 1, 0, 0,
+            0, 0, 0, 1, 1,
+        ]));
+        let mut rle = RLE::new(3, 3);
+        line(&mut rle, (1, 0), (1, 2));
+        assert_eq!(rle.to_image(1), Image::new(3, 3, vec![
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+        ]));
+    }
+
+    #[test]
+    fn filled_polygon_test() {
+        let mut rle = RLE::new(5, 5);
+        // triangle, pixels with center on diagonal are not inside
+        filled_polygon(&mut rle, &[(0.0, 0.0), (5.0, 5.0), (0.0, 5.0)]);
+        assert_eq!(rle.to_image(1), Image::new(5, 5, vec![
+            0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0,
+            1, 1, 0, 0, 0,
+            1, 1, 1, 0, 0,
+            1, 1, 1, 1, 0,
+        ]));
+        let mut rle = RLE::new(4, 4);
+        filled_polygon(&mut rle, &[(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]);
+        let mut expected = RLE::new(4, 4);
+        filled_rect(&mut expected, 1, 1, 2, 2);
+        assert_eq!(rle, expected);
+        assert_eq!(RLE::from_polygon(4, 4, &[(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]), expected);
+        // polygons with non-finite vertices are ignored instead of panicking
+        filled_polygon(&mut rle, &[(0.0, 0.0), (f32::NAN, 2.0), (3.0, 3.0)]);
+        filled_polygon(&mut rle, &[(0.0, 0.0), (f32::INFINITY, 2.0), (3.0, 3.0)]);
+        assert_eq!(rle, expected);
+        assert_eq!(RLE::from_polygon(4, 4, &[(0.0, f32::NEG_INFINITY), (3.0, 1.0), (3.0, 3.0)]), RLE::new(4, 4));
+    }
+
+    #[test]
+    fn from_polygons_test() {
+        let outer: &[(f32, f32)] = &[(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 5.0)];
+        let hole: &[(f32, f32)] = &[(1.0, 1.0), (1.0, 4.0), (4.0, 4.0), (4.0, 1.0)];
+        // second polygon is partly outside of image
+        let other: &[(f32, f32)] = &[(6.0, 2.0), (9.0, 2.0), (9.0, 3.0), (6.0, 3.0)];
+        let rle = RLE::from_polygons(8, 5, &[outer, hole, other]);
+        assert_eq!(rle.to_image(1), Image::new(8, 5, vec![
+            1, 1, 1, 1, 1, 0, 0, 0,
+            1, 0, 0, 0, 1, 0, 0, 0,
+            1, 0, 0, 0, 1, 0, 1, 1,
+            1, 0, 0, 0, 1, 0, 0, 0,
+            1, 1, 1, 1, 1, 0, 0, 0,
+        ]));
+        assert!(RLE::from_polygons(8, 5, &[]).is_empty());
+    }
+}
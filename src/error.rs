@@ -0,0 +1,72 @@
+use super::Run;
+use std::fmt;
+
+/// Errors returned by fallible `RLE` operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RleError {
+    /// Raw pixel buffer does not have expected number of bytes.
+    DataLength {
+        /// number of bytes needed for given dimensions (and stride)
+        expected: usize,
+        /// number of bytes in given buffer
+        actual: usize,
+    },
+    /// Row stride is smaller than image width.
+    InvalidStride {
+        /// width of image
+        width: usize,
+        /// given row stride
+        stride: usize,
+    },
+    /// Operands of binary operation do not have same dimensions.
+    DimensionMismatch {
+        /// (width, height) of left operand
+        left: (usize, usize),
+        /// (width, height) of right operand
+        right: (usize, usize),
+    },
+    /// Run is empty, outside of image or out of order.
+    InvalidRun(Run),
+    /// Operation was cancelled with `CancellationToken`.
+    Cancelled,
+    /// Coordinate does not fit in i32. Morphology always checks coordinates, other
+    /// operations only with `strict` feature.
+    CoordinateOverflow,
+    /// Encoded data (for example COCO string) is malformed.
+    InvalidEncoding,
+    /// Serialized data uses format version which is not supported.
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RleError::DataLength { expected, actual } => write!(
+                f,
+                "invalid raw data length: expected {} bytes, got {}",
+                expected, actual
+            ),
+            RleError::InvalidStride { width, stride } => write!(
+                f,
+                "row stride {} is smaller than image width {}",
+                stride, width
+            ),
+            RleError::DimensionMismatch { left, right } => write!(
+                f,
+                "dimensions do not match: {}x{} and {}x{}",
+                left.0, left.1, right.0, right.1
+            ),
+            RleError::InvalidRun(run) => write!(
+                f,
+                "invalid run x {}..={} in row {}",
+                run.x_start, run.x_end, run.y
+            ),
+            RleError::Cancelled => write!(f, "operation was cancelled"),
+            RleError::CoordinateOverflow => write!(f, "coordinate overflow"),
+            RleError::InvalidEncoding => write!(f, "invalid encoded data"),
+            RleError::UnsupportedVersion(version) => write!(f, "unsupported format version {}", version),
+        }
+    }
+}
+
+impl std::error::Error for RleError {}
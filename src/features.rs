@@ -0,0 +1,330 @@
+//! Detection of simple features (corners, line endpoints, junctions) in binary images
+//! using sets of hit-or-miss kernels.
+use super::{Image, RLE};
+
+/// Pair of structuring elements for hit-or-miss transform (see `RLE::hit_or_miss`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HitMissKernel {
+    /// pixels which must be 1
+    pub hit: RLE,
+    /// pixels which must be 0
+    pub miss: RLE,
+}
+
+impl HitMissKernel {
+    /// Create kernel from pattern where each string is one row and
+    /// '1' is hit, '0' is miss and any other character ('-') is don't care.
+    /// If rows are not of same length this method will panic.
+    pub fn from_pattern(rows: &[&str]) -> Self {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        let mut hit = Image::empty(width, height);
+        let mut miss = Image::empty(width, height);
+        for (y, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), width, "all rows of pattern must have same length");
+            for (x, c) in row.bytes().enumerate() {
+                match c {
+                    b'1' => hit[y][x] = 1,
+                    b'0' => miss[y][x] = 1,
+                    _ => {}
+                }
+            }
+        }
+        Self { hit: RLE::from(&hit), miss: RLE::from(&miss) }
+    }
+
+    /// Get all pixels of mask matched by this kernel.
+    pub fn apply(&self, mask: &RLE) -> RLE {
+        mask.hit_or_miss(&self.hit, &self.miss)
+    }
+}
+
+/// Rotate pattern by 90 degrees clockwise.
+fn rotate_pattern(rows: &[String]) -> Vec<String> {
+    let height = rows.len();
+    let width = rows.first().map_or(0, |row| row.len());
+    (0..width)
+        .map(|x| (0..height).rev().map(|y| rows[y].as_bytes()[x] as char).collect())
+        .collect()
+}
+
+/// Create kernels from all 4 rotations of each pattern.
+fn rotations(patterns: &[&[&str]]) -> Vec<HitMissKernel> {
+    let mut kernels = Vec::with_capacity(4 * patterns.len());
+    for pattern in patterns {
+        let mut rows: Vec<String> = pattern.iter().map(|row| row.to_string()).collect();
+        for _ in 0..4 {
+            let refs: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+            kernels.push(HitMissKernel::from_pattern(&refs));
+            rows = rotate_pattern(&rows);
+        }
+    }
+    kernels
+}
+
+/// Kernels matching convex corners of objects (pixel with background on two
+/// perpendicular sides), in all 4 orientations.
+pub fn convex_corner_kernels() -> Vec<HitMissKernel> {
+    rotations(&[&[
+        "00-",
+        "011",
+        "-1-",
+    ]])
+}
+
+/// Kernels matching endpoints of 1 pixel wide lines (pixel with exactly one
+/// 8-connected neighbor), in all 8 orientations.
+pub fn endpoint_kernels() -> Vec<HitMissKernel> {
+    rotations(&[
+        &[
+            "000",
+            "010",
+            "010",
+        ],
+        &[
+            "000",
+            "010",
+            "001",
+        ],
+    ])
+}
+
+/// Kernels matching T-junctions and Y-junctions of 1 pixel wide lines,
+/// in all orientations.
+pub fn t_junction_kernels() -> Vec<HitMissKernel> {
+    rotations(&[
+        &[
+            "000",
+            "111",
+            "010",
+        ],
+        &[
+            "101",
+            "010",
+            "010",
+        ],
+        &[
+            "100",
+            "011",
+            "010",
+        ],
+    ])
+}
+
+/// Set of hit-or-miss kernels matched together, see `RLE::match_templates`.
+/// Predefined banks contain all orientations of their templates.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TemplateBank {
+    kernels: Vec<HitMissKernel>,
+}
+
+impl TemplateBank {
+    /// Create bank from kernels, they are matched as given (without rotations).
+    pub fn new(kernels: Vec<HitMissKernel>) -> Self {
+        Self { kernels }
+    }
+
+    /// Create bank from all 4 rotations of each pattern (see `HitMissKernel::from_pattern`),
+    /// rotations equal to some kernel already in bank are skipped.
+    pub fn from_patterns(patterns: &[&[&str]]) -> Self {
+        let mut bank = Self::default();
+        bank.extend(rotations(patterns));
+        bank
+    }
+
+    /// Convex corners of objects, see `convex_corner_kernels`.
+    pub fn convex_corners() -> Self {
+        Self::new(convex_corner_kernels())
+    }
+
+    /// Endpoints of 1 pixel wide lines, see `endpoint_kernels`.
+    pub fn line_ends() -> Self {
+        Self::new(endpoint_kernels())
+    }
+
+    /// Junctions of 1 pixel wide lines where 3 or 4 branches meet: T-junctions,
+    /// Y-junctions and crossings of straight and diagonal lines.
+    pub fn junctions() -> Self {
+        let mut bank = Self::new(t_junction_kernels());
+        bank.extend(rotations(&[
+            &[
+                "010",
+                "111",
+                "010",
+            ],
+            &[
+                "101",
+                "010",
+                "101",
+            ],
+        ]));
+        bank
+    }
+
+    /// Get kernels of bank.
+    pub fn kernels(&self) -> &[HitMissKernel] {
+        &self.kernels
+    }
+
+    /// Add kernels which are not already in bank.
+    pub fn extend<I: IntoIterator<Item = HitMissKernel>>(&mut self, kernels: I) {
+        for kernel in kernels {
+            if !self.kernels.contains(&kernel) {
+                self.kernels.push(kernel);
+            }
+        }
+    }
+}
+
+/// Get union of pixels matched by any of kernels.
+pub fn detect(mask: &RLE, kernels: &[HitMissKernel]) -> RLE {
+    kernels.iter().fold(RLE::new(mask.width(), mask.height()), |mut acc, kernel| {
+        acc |= kernel.apply(mask);
+        acc
+    })
+}
+
+/// Get coordinates (x, y) of pixels matched by any of kernels, sorted by row then column.
+pub fn detect_points(mask: &RLE, kernels: &[HitMissKernel]) -> Vec<(i32, i32)> {
+    detect(mask, kernels).runs().iter()
+        .flat_map(|run| (run.x_start..=run.x_end).map(move |x| (x, run.y)))
+        .collect()
+}
+
+/// Get convex corners of objects in mask.
+pub fn convex_corners(mask: &RLE) -> RLE {
+    detect(mask, &convex_corner_kernels())
+}
+
+/// Get endpoints of 1 pixel wide lines in mask.
+pub fn endpoints(mask: &RLE) -> RLE {
+    detect(mask, &endpoint_kernels())
+}
+
+/// Get T-junctions of 1 pixel wide lines in mask.
+pub fn t_junctions(mask: &RLE) -> RLE {
+    detect(mask, &t_junction_kernels())
+}
+
+impl RLE {
+    /// Get union of pixels matched by any kernel of bank.
+    pub fn match_templates(&self, bank: &TemplateBank) -> RLE {
+        detect(self, bank.kernels())
+    }
+
+    /// Thickening, dual of thinning: in every iteration kernels are applied in order
+    /// and pixels matched by kernel are added to image before next kernel is applied.
+    /// Stops after given number of iterations or when iteration adds no pixel.
+    /// Kernels which do not have center pixel in `miss` can not add pixels.
+    pub fn thicken(&self, kernels: &[HitMissKernel], iterations: usize) -> RLE {
+        let mut current = RLE::from_runs(self.width(), self.height(), self.canonical_runs().into_owned());
+        for _ in 0..iterations {
+            let area = current.area();
+            for kernel in kernels {
+                let matched = kernel.apply(&current);
+                if !matched.is_empty() {
+                    current |= matched;
+                }
+            }
+            if current.area() == area {
+                break;
+            }
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Run;
+
+    #[test]
+    fn from_pattern_test() {
+        let kernel = HitMissKernel::from_pattern(&["1-0"]);
+        assert_eq!(kernel.hit, RLE::from(&Image::new(3, 1, vec![1, 0, 0])));
+        assert_eq!(kernel.miss, RLE::from(&Image::new(3, 1, vec![0, 0, 1])));
+    }
+
+    #[test]
+    fn rotate_pattern_test() {
+        let rows = vec!["10".to_string(), "00".to_string()];
+        assert_eq!(rotate_pattern(&rows), vec!["01".to_string(), "00".to_string()]);
+    }
+
+    #[test]
+    fn convex_corners_test() {
+        let mask = RLE::from(&Image::new(5, 4, vec![
+            0, 0, 0, 0, 0,
+            0, 1, 1, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(detect_points(&mask, &convex_corner_kernels()), vec![(1, 1), (3, 1), (1, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn endpoints_and_junctions_test() {
+        // T shape made of 1 pixel wide lines
+        let mask = RLE::from(&Image::new(7, 5, vec![
+            0, 0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(detect_points(&mask, &endpoint_kernels()), vec![(1, 1), (5, 1), (3, 3)]);
+        assert_eq!(detect_points(&mask, &t_junction_kernels()), vec![(3, 1)]);
+        // same with line touching image border
+        let mask = RLE::from(&Image::new(5, 3, vec![
+            1, 1, 1, 1, 1,
+            0, 0, 1, 0, 0,
+            0, 0, 1, 0, 0,
+        ]));
+        assert_eq!(detect_points(&mask, &endpoint_kernels()), vec![(0, 0), (4, 0), (2, 2)]);
+        assert_eq!(endpoints(&mask).runs().len(), 3);
+        assert_eq!(detect_points(&mask, &t_junction_kernels()), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn thicken_test() {
+        let mask = RLE::from(&Image::new(7, 2, vec![
+            1, 0, 1, 0, 0, 1, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        // kernel filling background pixel between two pixels of row and kernel
+        // growing lines to the right by one pixel per iteration
+        let kernels = [HitMissKernel::from_pattern(&["101"]), HitMissKernel::from_pattern(&["10"])];
+        assert_eq!(mask.thicken(&kernels[..1], 5).to_image(1), Image::new(7, 2, vec![
+            1, 1, 1, 0, 0, 1, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(mask.thicken(&kernels[1..], 1).runs(), &[Run::new(0, 3, 0), Run::new(5, 6, 0)]);
+        assert_eq!(mask.thicken(&kernels, 10), RLE::from_runs(7, 2, vec![Run::new(0, 6, 0)]));
+        assert_eq!(mask.thicken(&convex_corner_kernels(), 10), mask);
+    }
+
+    #[test]
+    fn template_bank_test() {
+        // plus shape made of 1 pixel wide lines
+        let mask = RLE::from(&Image::new(7, 7, vec![
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(detect_points(&mask, TemplateBank::line_ends().kernels()), vec![(3, 1), (1, 3), (5, 3), (3, 5)]);
+        assert_eq!(mask.match_templates(&TemplateBank::line_ends()), endpoints(&mask));
+        // crossing is not matched by T-junction kernels
+        assert!(t_junctions(&mask).is_empty());
+        assert_eq!(detect_points(&mask, TemplateBank::junctions().kernels()), vec![(3, 3)]);
+        assert_eq!(mask.match_templates(&TemplateBank::convex_corners()), detect(&mask, &convex_corner_kernels()));
+        // symmetric pattern has only one distinct rotation
+        let bank = TemplateBank::from_patterns(&[&["-1-", "111", "-1-"], &["1-", "0-"]]);
+        assert_eq!(bank.kernels().len(), 5);
+        assert!(RLE::new(3, 3).match_templates(&TemplateBank::default()).is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+//! C ABI enabled with `ffi` feature, declared in `include/rle_morph.h`.
+//!
+//! Images are passed as opaque `RLE *` handles created by `rle_encode` (or functions
+//! returning new image) and released with `rle_free`. Functions returning handle
+//! return null on invalid arguments instead of panicking across FFI boundary.
+use super::{RleError, RLE};
+use std::os::raw::c_int;
+use std::ptr;
+
+/// Move result to heap and return its handle, null on error.
+fn into_handle(res: Result<RLE, RleError>) -> *mut RLE {
+    match res {
+        Ok(rle) => Box::into_raw(Box::new(rle)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Encode 8-bit image where each row starts `stride` bytes after previous one,
+/// pixels greater than 0 are 1. Returns null if data is null or stride < width.
+///
+/// # Safety
+/// data must point to at least stride * (height - 1) + width readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rle_encode(data: *const u8, width: usize, height: usize, stride: usize) -> *mut RLE {
+    if data.is_null() || stride < width {
+        return ptr::null_mut();
+    }
+    let len = if height == 0 { 0 } else { stride * (height - 1) + width };
+    let data = std::slice::from_raw_parts(data, len);
+    into_handle(RLE::try_from_raw_data_with_stride(width, height, stride, data))
+}
+
+/// Release image, null is ignored.
+///
+/// # Safety
+/// rle must be null or handle returned by this library which was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rle_free(rle: *mut RLE) {
+    if !rle.is_null() {
+        drop(Box::from_raw(rle));
+    }
+}
+
+/// Get image width, 0 for null.
+///
+/// # Safety
+/// rle must be null or valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn rle_width(rle: *const RLE) -> usize {
+    rle.as_ref().map_or(0, |rle| rle.width())
+}
+
+/// Get image height, 0 for null.
+///
+/// # Safety
+/// rle must be null or valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn rle_height(rle: *const RLE) -> usize {
+    rle.as_ref().map_or(0, |rle| rle.height())
+}
+
+/// Get number of pixels set to 1, 0 for null.
+///
+/// # Safety
+/// rle must be null or valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn rle_area(rle: *const RLE) -> usize {
+    rle.as_ref().map_or(0, |rle| rle.area())
+}
+
+/// Create square structuring element of size 2k + 1 (see `RLE::linf_structuring`).
+#[no_mangle]
+pub extern "C" fn rle_linf_structuring(k: usize) -> *mut RLE {
+    into_handle(Ok(RLE::linf_structuring(k)))
+}
+
+/// Create diamond structuring element of size 2k + 1 (see `RLE::l1_structuring`).
+#[no_mangle]
+pub extern "C" fn rle_l1_structuring(k: usize) -> *mut RLE {
+    into_handle(Ok(RLE::l1_structuring(k)))
+}
+
+/// Dilate image with structuring element centered in its middle pixel, returns
+/// new image or null if some argument is null or coordinates overflow.
+///
+/// # Safety
+/// rle and se must be null or valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn rle_dilate(rle: *const RLE, se: *const RLE) -> *mut RLE {
+    match (rle.as_ref(), se.as_ref()) {
+        (Some(rle), Some(se)) => into_handle(rle.try_dilate(se)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Erode image with structuring element, see `rle_dilate`.
+///
+/// # Safety
+/// rle and se must be null or valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn rle_erode(rle: *const RLE, se: *const RLE) -> *mut RLE {
+    match (rle.as_ref(), se.as_ref()) {
+        (Some(rle), Some(se)) => into_handle(rle.try_erode(se)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Intersection of images, returns null if some argument is null or dimensions differ.
+///
+/// # Safety
+/// a and b must be null or valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn rle_and(a: *const RLE, b: *const RLE) -> *mut RLE {
+    match (a.as_ref(), b.as_ref()) {
+        (Some(a), Some(b)) => into_handle(a.try_bitand(b)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Union of images, returns null if some argument is null or dimensions differ.
+///
+/// # Safety
+/// a and b must be null or valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn rle_or(a: *const RLE, b: *const RLE) -> *mut RLE {
+    match (a.as_ref(), b.as_ref()) {
+        (Some(a), Some(b)) => into_handle(a.try_bitor(b)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Decode image into buffer of len bytes (row after row, without padding), pixels
+/// set to 1 get pixel_val and other pixels 0. Returns 0 on success and -1 if some
+/// argument is null or len is not width * height.
+///
+/// # Safety
+/// rle must be null or valid handle and out must point to len writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rle_to_buffer(rle: *const RLE, out: *mut u8, len: usize, pixel_val: u8) -> c_int {
+    let rle = match rle.as_ref() {
+        Some(rle) if !out.is_null() && len == rle.width() * rle.height() => rle,
+        _ => return -1,
+    };
+    rle.to_image_into(std::slice::from_raw_parts_mut(out, len), pixel_val);
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffi_test() {
+        // 3x2 image in buffer with stride 4
+        let data = [0, 1, 0, 9, 0, 0, 0, 9];
+        unsafe {
+            let rle = rle_encode(data.as_ptr(), 3, 2, 4);
+            assert!(!rle.is_null());
+            assert_eq!((rle_width(rle), rle_height(rle), rle_area(rle)), (3, 2, 1));
+            let se = rle_linf_structuring(1);
+            let dilated = rle_dilate(rle, se);
+            assert_eq!(rle_area(dilated), 6);
+            let eroded = rle_erode(dilated, se);
+            assert_eq!(*eroded, (*dilated).erode(&*se));
+            let and = rle_and(rle, dilated);
+            assert_eq!(*and, *rle);
+            let mut out = [7u8; 6];
+            assert_eq!(rle_to_buffer(and, out.as_mut_ptr(), 6, 255), 0);
+            assert_eq!(out, [0, 255, 0, 0, 0, 0]);
+            assert_eq!(rle_to_buffer(and, out.as_mut_ptr(), 5, 255), -1);
+            // invalid arguments
+            assert!(rle_encode(data.as_ptr(), 3, 2, 2).is_null());
+            assert!(rle_dilate(ptr::null(), se).is_null());
+            assert!(rle_or(rle, se).is_null());
+            assert_eq!(rle_area(ptr::null()), 0);
+            for handle in [rle, se, dilated, eroded, and, ptr::null_mut()].iter() {
+                rle_free(*handle);
+            }
+        }
+    }
+}
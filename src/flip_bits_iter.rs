@@ -17,20 +17,27 @@ enum IterState {
 
 impl<'rle> FlipBitsIter<'rle> {
     pub fn new(rle: &'rle RLE) -> Self {
-        if rle.runs().is_empty() {
+        Self::from_runs(rle.runs(), rle.width(), rle.height())
+    }
+
+    /// Iterate over complement of canonical runs of image with given dimensions.
+    pub(crate) fn from_runs(runs: &'rle [Run], width: usize, height: usize) -> Self {
+        if runs.is_empty() || width == 0 {
+            // image without columns has nothing to flip so start at the end
+            let pos = if width == 0 { height } else { 0 };
             return Self {
-                runs: rle.runs().iter(),
-                width: rle.width(),
-                height: rle.height(),
-                state: IterState::Empty{ pos: 0 }
+                runs: runs.iter(),
+                width,
+                height,
+                state: IterState::Empty{ pos }
             };
         }
-        let mut runs = rle.runs().iter();
+        let mut runs = runs.iter();
         let first_run = runs.next().unwrap();
         Self {
             runs,
-            width: rle.width(),
-            height: rle.height(),
+            width,
+            height,
             state: IterState::BlankLines { pos: 0, end: first_run.y, run: *first_run },
         }
     }
@@ -48,7 +55,9 @@ impl<'rle> FlipBitsIter<'rle> {
                 self.state = IterState::BlankLines { pos: run.y + 2, end: next_run.y, run: next_run };
                 return Some(Run { x_start: 0, x_end: self.width as i32 - 1, y: run.y + 1 });
             }
-            return self.advance_between_next_run(next_run);
+            // next run is in next row, there can still be 0s before it
+            self.state = IterState::BlankLines { pos: next_run.y, end: next_run.y, run: next_run };
+            return self.next();
         }
         if run.x_end < self.width as i32 - 1 {
             self.state = IterState::BlankLinesAfterLastRun { pos: run.y + 1, end: self.height as i32 };
@@ -58,7 +67,7 @@ impl<'rle> FlipBitsIter<'rle> {
             return None;
         }
         self.state = IterState::BlankLinesAfterLastRun { pos: run.y + 2, end: self.height as i32 };
-        return Some(Run { x_start: 0, x_end: self.width as i32 - 1, y: run.y + 1 });
+        Some(Run { x_start: 0, x_end: self.width as i32 - 1, y: run.y + 1 })
     }
 }
 impl<'rle> Iterator for FlipBitsIter<'rle> {
@@ -147,4 +156,17 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn flip_bits_row_ending_at_border_test() {
+        // run ending at last column followed by run in next row which does not start at 0
+        let img = Image::new(4, 3, vec![
+            0, 0, 1, 1,
+            0, 1, 1, 1,
+            1, 0, 0, 0,
+        ]);
+        let rle = RLE::from(&img);
+        let runs: Vec<Run> = rle.flip_bits_iter().collect();
+        assert_eq!(runs, (!&rle).runs().to_vec());
+    }
 }
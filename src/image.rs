@@ -1,26 +1,24 @@
 use std::ops::{Index, IndexMut};
 use std::fmt;
 
+use super::RLE;
+
+/// Dense image with pixels stored row after row.
+/// Pixels are bytes by default. Images of bytes and of `bool` can be encoded to `RLE`
+/// with `From`, images with other pixel types (for example `u16`) with
+/// `RLE::from_image_with`.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Image {
+pub struct Image<T = u8> {
     w: usize,
     h: usize,
-    data: Vec<u8>,
+    data: Vec<T>,
 }
 
-impl Image {
-    pub fn new(w: usize, h: usize, data: Vec<u8>) -> Self {
+impl<T> Image<T> {
+    pub fn new(w: usize, h: usize, data: Vec<T>) -> Self {
         Self { w, h, data }
     }
 
-    pub fn empty(w: usize, h: usize) -> Self {
-        Self {
-            w,
-            h,
-            data: vec![0; w * h],
-        }
-    }
-
     pub fn w(&self) -> usize {
         self.w
     }
@@ -29,14 +27,36 @@ impl Image {
         self.h
     }
 
-    pub fn data(&self) -> &[u8] {
+    pub fn data(&self) -> &[T] {
         &self.data
     }
 
     /// Get underlying vec used to store pixels
-    pub fn into_raw(self) -> Vec<u8> {
+    pub fn into_raw(self) -> Vec<T> {
         self.data
     }
+}
+
+impl<T: Clone + Default> Image<T> {
+    /// Create image with all pixels set to default value (0 or false).
+    pub fn empty(w: usize, h: usize) -> Self {
+        Self {
+            w,
+            h,
+            data: vec![T::default(); w * h],
+        }
+    }
+}
+
+impl Image {
+    /// Decode rle into this image, image is resized to dimensions of rle and its
+    /// buffer is reused (see `RLE::to_image_into`).
+    pub fn fill_from_rle(&mut self, rle: &RLE, pixel_val: u8) {
+        self.w = rle.width();
+        self.h = rle.height();
+        self.data.resize(self.w * self.h, 0);
+        rle.to_image_into(&mut self.data, pixel_val);
+    }
 
     /// Flip bits in image (nonzero to 0, 0 to 1)
     pub fn flip_bits_mut(&mut self) {
@@ -56,23 +76,23 @@ impl Image {
     }
 }
 
-impl fmt::Display for Image {
+impl<T: fmt::Debug> fmt::Display for Image<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for i in 0..self.h {
-            write!(f, "{:?}\n", &self.data[i * self.w..(i + 1) * self.w])?;
+            writeln!(f, "{:?}", &self.data[i * self.w..(i + 1) * self.w])?;
         }
         Ok(())
     }
 }
 
-impl Index<usize> for Image {
-    type Output = [u8];
+impl<T> Index<usize> for Image<T> {
+    type Output = [T];
     fn index(&self, index: usize) -> &Self::Output {
         &self.data[index * self.w..(index+1)*self.w]
     }
 }
 
-impl IndexMut<usize> for Image {
+impl<T> IndexMut<usize> for Image<T> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.data[index * self.w..(index+1)*self.w]
     }
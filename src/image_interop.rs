@@ -0,0 +1,78 @@
+//! Conversions between `RLE` and images of `image` crate, enabled with `image-interop`
+//! feature. Pixels with value greater than 0 are treated as 1.
+use super::{RleError, RLE};
+use ::image::{DynamicImage, GrayImage, Luma};
+use std::convert::TryFrom;
+
+impl From<&GrayImage> for RLE {
+    fn from(img: &GrayImage) -> Self {
+        let (width, height) = img.dimensions();
+        RLE::try_from_raw_data_with_stride(width as _, height as _, width as _, img.as_raw()).unwrap()
+    }
+}
+
+/// Image is converted to 8 bit grayscale first, so pixel is 1 if its luma is
+/// greater than 0. Returns `RleError::CoordinateOverflow` if image is wider or
+/// higher than i32::MAX.
+impl TryFrom<DynamicImage> for RLE {
+    type Error = RleError;
+
+    fn try_from(img: DynamicImage) -> Result<Self, Self::Error> {
+        let gray = img.into_luma8();
+        let (width, height) = gray.dimensions();
+        if width > i32::MAX as u32 || height > i32::MAX as u32 {
+            return Err(RleError::CoordinateOverflow);
+        }
+        Ok(RLE::from(&gray))
+    }
+}
+
+impl RLE {
+    /// Convert to grayscale image with pixels set to fg where mask is 1 and to bg elsewhere.
+    pub fn to_gray_image(&self, fg: u8, bg: u8) -> GrayImage {
+        let mut img = GrayImage::from_pixel(self.width() as _, self.height() as _, Luma([bg]));
+        let width = self.width();
+        let data: &mut [u8] = &mut img;
+        for run in self.canonical_runs().iter() {
+            let row = run.y as usize * width;
+            for pixel in &mut data[row + run.x_start as usize..=row + run.x_end as usize] {
+                *pixel = fg;
+            }
+        }
+        img
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn gray_image_test() {
+        let gray = GrayImage::from_raw(4, 2, vec![
+            0, 7, 255, 0,
+            1, 0, 0, 0,
+        ]).unwrap();
+        let rle = RLE::from(&gray);
+        assert_eq!(rle, RLE::from(&Image::new(4, 2, vec![
+            0, 1, 1, 0,
+            1, 0, 0, 0,
+        ])));
+        assert_eq!(rle.to_gray_image(200, 10).into_raw(), vec![
+            10, 200, 200, 10,
+            200, 10, 10, 10,
+        ]);
+        assert_eq!(RLE::from(&rle.to_gray_image(255, 0)), rle);
+    }
+
+    #[test]
+    fn dynamic_image_test() {
+        let gray = GrayImage::from_raw(3, 1, vec![0, 9, 0]).unwrap();
+        let rle = RLE::try_from(DynamicImage::ImageLuma8(gray)).unwrap();
+        assert_eq!(rle, RLE::from(&Image::new(3, 1, vec![0, 1, 0])));
+        let rgb = ::image::RgbImage::from_raw(2, 1, vec![0, 0, 0, 255, 255, 255]).unwrap();
+        let rle = RLE::try_from(DynamicImage::ImageRgb8(rgb)).unwrap();
+        assert_eq!(rle, RLE::from(&Image::new(2, 1, vec![0, 1])));
+    }
+}
@@ -0,0 +1,161 @@
+use super::{intersect_iter, Run, RLE};
+use std::borrow::Cow;
+
+/// View of RLE with index of first run of every row, see `RLE::indexed`.
+/// Runs of any row are found in O(1) and rows which are empty in one of operands
+/// are skipped in binary operations without looking at their runs.
+#[derive(Debug, Clone)]
+pub struct IndexedRle<'a> {
+    width: usize,
+    height: usize,
+    runs: Cow<'a, [Run]>,
+    /// runs of row y are runs[row_start[y]..row_start[y + 1]]
+    row_start: Vec<usize>,
+}
+
+impl RLE {
+    /// Build index of rows in one pass over runs. Runs are clipped to image first
+    /// (borrowed if they are already canonical).
+    pub fn indexed(&self) -> IndexedRle<'_> {
+        let runs = self.canonical_runs();
+        let mut row_start = Vec::with_capacity(self.height + 1);
+        let mut i = 0;
+        for y in 0..=self.height {
+            while i < runs.len() && (runs[i].y as usize) < y {
+                i += 1;
+            }
+            row_start.push(i);
+        }
+        IndexedRle { width: self.width, height: self.height, runs, row_start }
+    }
+}
+
+impl<'a> IndexedRle<'a> {
+    /// Get image width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get image height.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get all runs (in canonical form).
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    /// Get runs of row y, empty for y outside of image.
+    pub fn row(&self, y: i32) -> &[Run] {
+        if y < 0 || y as usize >= self.height {
+            return &[];
+        }
+        &self.runs[self.row_start[y as usize]..self.row_start[y as usize + 1]]
+    }
+
+    /// Get runs of rows y_start..=y_end (clipped to image).
+    pub fn rows(&self, y_start: i32, y_end: i32) -> &[Run] {
+        let start = y_start.max(0).min(self.height as i32) as usize;
+        let end = (y_end.max(-1) + 1).min(self.height as i32) as usize;
+        if start >= end {
+            return &[];
+        }
+        &self.runs[self.row_start[start]..self.row_start[end]]
+    }
+
+    /// Check if pixel (x, y) is set, O(log(runs in row)).
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let row = self.row(y);
+        let i = row.partition_point(|run| run.x_start <= x);
+        i > 0 && row[i - 1].x_end >= x
+    }
+
+    /// Check if any pixel x_start..=x_end of row y is set.
+    pub fn row_intersects(&self, y: i32, x_start: i32, x_end: i32) -> bool {
+        let row = self.row(y);
+        let i = row.partition_point(|run| run.x_end < x_start);
+        i < row.len() && row[i].x_start <= x_end
+    }
+
+    /// Iterate over pairs of runs of rows which are not empty in self and in other.
+    fn common_rows<'b>(&'b self, other: &'b IndexedRle<'_>) -> impl Iterator<Item = (&'b [Run], &'b [Run])> + 'b {
+        assert!(self.width == other.width && self.height == other.height);
+        (0..self.height as i32)
+            .map(move |y| (self.row(y), other.row(y)))
+            .filter(|(a, b)| !a.is_empty() && !b.is_empty())
+    }
+
+    /// Intersection with other, rows empty in any of images are skipped.
+    /// If dimensions of self and other are not same this method will panic.
+    pub fn and(&self, other: &IndexedRle<'_>) -> RLE {
+        let runs = self.common_rows(other)
+            .flat_map(|(a, b)| intersect_iter(a.iter().copied(), b.iter().copied()))
+            .collect();
+        RLE::from_runs(self.width, self.height, runs)
+    }
+
+    /// Check if self and other have some common pixel.
+    /// If dimensions of self and other are not same this method will panic.
+    pub fn intersects(&self, other: &IndexedRle<'_>) -> bool {
+        self.common_rows(other)
+            .any(|(a, b)| intersect_iter(a.iter().copied(), b.iter().copied()).next().is_some())
+    }
+
+    /// Number of pixels set in both self and other.
+    /// If dimensions of self and other are not same this method will panic.
+    pub fn intersection_area(&self, other: &IndexedRle<'_>) -> usize {
+        self.common_rows(other)
+            .flat_map(|(a, b)| intersect_iter(a.iter().copied(), b.iter().copied()))
+            .map(|run| (run.x_end - run.x_start + 1) as usize)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn indexed_test() {
+        let img = Image::new(6, 5, vec![
+            0, 1, 1, 0, 0, 1,
+            0, 0, 0, 0, 0, 0,
+            1, 1, 0, 1, 1, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 1, 1, 1, 1,
+        ]);
+        let rle = RLE::from(&img);
+        let indexed = rle.indexed();
+        for y in -1..7 {
+            assert_eq!(indexed.row(y), rle.row_runs(y));
+            for x in -1..7 {
+                assert_eq!(indexed.contains(x, y), rle.contains(x, y), "{} {}", x, y);
+            }
+        }
+        assert_eq!(indexed.rows(1, 2), rle.row_runs(2));
+        assert_eq!(indexed.rows(-5, 10), rle.runs());
+        assert!(indexed.rows(3, 2).is_empty());
+        assert!(indexed.row_intersects(2, 2, 3));
+        assert!(!indexed.row_intersects(2, 2, 2));
+        assert!(!indexed.row_intersects(1, 0, 5));
+
+        let other = RLE::from(&Image::new(6, 5, vec![
+            1, 1, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1,
+            0, 0, 1, 0, 0, 1,
+            1, 1, 1, 1, 1, 1,
+            0, 0, 0, 1, 0, 0,
+        ]));
+        let other_indexed = other.indexed();
+        assert_eq!(indexed.and(&other_indexed), &rle & &other);
+        assert_eq!(indexed.intersection_area(&other_indexed), 2);
+        assert!(indexed.intersects(&other_indexed));
+        assert!(!indexed.intersects(&RLE::new(6, 5).indexed()));
+
+        // runs outside of image are clipped
+        let outside = RLE::from_runs(6, 5, vec![Run::new(-3, 1, 0), Run::new(2, 3, 7)]);
+        assert_eq!(outside.indexed().runs(), &[Run::new(0, 1, 0)]);
+    }
+}
@@ -0,0 +1,404 @@
+use super::rle::primary_run;
+use super::{Kernel, Run, RleError, RLE};
+use std::collections::{BTreeMap, HashMap};
+
+/// Run of pixels which all have same label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LabelRun {
+    /// pixels of run
+    pub run: Run,
+    /// label id of all pixels in run
+    pub label: u32,
+}
+
+/// How to resolve pixels claimed by more than one label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Pixel gets smallest of claiming labels.
+    MinLabel,
+    /// Pixel gets largest of claiming labels.
+    MaxLabel,
+    /// Pixel stays background.
+    Background,
+}
+
+/// Representation of label map (segmentation map) using runs where each run
+/// carries label id. Label 0 is background and it is never stored.
+/// Runs are sorted and do not overlap, touching runs with same label are merged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelRle {
+    /// width of image
+    pub(crate) width: usize,
+    /// height of image
+    pub(crate) height: usize,
+    /// runs of image
+    pub(crate) runs: Vec<LabelRun>,
+}
+
+impl LabelRle {
+    /// Create label map with all pixels set to background.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, runs: Vec::new() }
+    }
+
+    /// Create label map from dense labels stored row by row.
+    /// Returns error if width * height != labels.len(), if width * height overflows
+    /// expected length in error is usize::MAX.
+    pub fn from_label_map(width: usize, height: usize, labels: &[u32]) -> Result<Self, RleError> {
+        match width.checked_mul(height) {
+            Some(len) if len == labels.len() => {}
+            len => return Err(RleError::DataLength { expected: len.unwrap_or(usize::MAX), actual: labels.len() }),
+        }
+        let mut runs = Vec::new();
+        for y in 0..height {
+            let row = &labels[y * width..(y + 1) * width];
+            let mut x = 0;
+            while x < width {
+                let label = row[x];
+                let start = x;
+                while x < width && row[x] == label {
+                    x += 1;
+                }
+                if label != 0 {
+                    runs.push(LabelRun { run: Run::new(start as _, x as i32 - 1, y as _), label });
+                }
+            }
+        }
+        Ok(Self { width, height, runs })
+    }
+
+    /// Create label map from masks and their labels.
+    /// Pixels set in more than one mask are resolved with given policy.
+    /// If dimensions of masks and label map are not same this method will panic.
+    pub fn from_masks(width: usize, height: usize, masks: &[(u32, &RLE)], policy: ConflictPolicy) -> Self {
+        let mut runs = Vec::new();
+        for &(label, mask) in masks {
+            assert!(mask.width() == width && mask.height() == height);
+            if label == 0 {
+                continue;
+            }
+            runs.extend(mask.canonical_runs().iter().map(|&run| LabelRun { run, label }));
+        }
+        Self { width, height, runs: resolve_conflicts(runs, policy) }
+    }
+
+    /// Decode to dense labels stored row by row.
+    pub fn to_label_map(&self) -> Vec<u32> {
+        let mut output = vec![0; self.width * self.height];
+        for lr in &self.runs {
+            let start = lr.run.y as usize * self.width;
+            for label in &mut output[start + lr.run.x_start as usize..=start + lr.run.x_end as usize] {
+                *label = lr.label;
+            }
+        }
+        output
+    }
+
+    /// Get all labels (except background) present in label map, sorted.
+    pub fn labels(&self) -> Vec<u32> {
+        let mut labels: Vec<u32> = self.runs.iter().map(|lr| lr.label).collect();
+        labels.sort_unstable();
+        labels.dedup();
+        labels
+    }
+
+    /// Get binary mask of pixels with given label.
+    pub fn extract(&self, label: u32) -> RLE {
+        let runs = self.runs.iter().filter(|lr| lr.label == label).map(|lr| lr.run).collect();
+        RLE::from_runs(self.width, self.height, runs)
+    }
+
+    /// Get binary mask of all labeled (non background) pixels.
+    pub fn foreground(&self) -> RLE {
+        RLE::from_runs(self.width, self.height, self.runs.iter().map(|lr| lr.run).collect())
+            .merge_overlapping_runs()
+    }
+
+    /// Change labels using given mapping. Pixels mapped to 0 become background.
+    pub fn relabel<F: FnMut(u32) -> u32>(&mut self, mut f: F) {
+        for lr in &mut self.runs {
+            lr.label = f(lr.label);
+        }
+        self.runs.retain(|lr| lr.label != 0);
+        self.merge_touching_runs();
+    }
+
+    /// Dilate each label with structuring element s (see `RLE::dilate`).
+    /// Labeled pixels keep their label, only background pixels are taken by dilated labels.
+    /// Background pixels reached by more than one label are resolved with given policy.
+    /// Primary runs of all labels are generated and resolved in one sweep, so time does
+    /// not grow with number of labels.
+    /// If some coordinate does not fit in i32 this method will panic.
+    pub fn dilate(&self, s: &RLE, policy: ConflictPolicy) -> Self {
+        let anchor = s.anchor();
+        let grown = self.runs.iter()
+            .flat_map(|lr| s.kernel_runs().iter().map(move |&b| {
+                primary_run(lr.run, b, anchor).map(|run| LabelRun { run, label: lr.label })
+            }))
+            .collect::<Result<Vec<LabelRun>, RleError>>();
+        let grown = match grown {
+            Ok(grown) => resolve_conflicts(grown, policy),
+            Err(e) => panic!("{}", e),
+        };
+        let free = !&self.foreground();
+        let mut runs = intersect_labels(&grown, free.runs());
+        runs.extend(&self.runs);
+        runs.sort_unstable_by_key(|lr| lr.run);
+        let mut res = Self { width: self.width, height: self.height, runs };
+        res.merge_touching_runs();
+        res
+    }
+
+    #[inline]
+    pub fn runs(&self) -> &[LabelRun] {
+        &self.runs
+    }
+
+    /// Get image width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get image height.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Merge touching runs with same label, runs must be sorted and must not overlap.
+    fn merge_touching_runs(&mut self) {
+        let mut res: Vec<LabelRun> = Vec::with_capacity(self.runs.len());
+        for &lr in &self.runs {
+            match res.last_mut() {
+                Some(last) if last.label == lr.label && last.run.y == lr.run.y &&
+                    last.run.x_end + 1 == lr.run.x_start => last.run.x_end = lr.run.x_end,
+                _ => res.push(lr),
+            }
+        }
+        self.runs = res;
+    }
+}
+
+impl RLE {
+    /// Get fraction of pixels of every label in labels which are covered by self.
+    /// Every label present in label map is in result, labels not touched by self have 0.
+    /// If dimensions of self and labels are not same this method will panic.
+    pub fn coverage(&self, labels: &LabelRle) -> HashMap<u32, f64> {
+        assert!(self.width() == labels.width && self.height() == labels.height);
+        // (covered pixels, all pixels) of every label
+        let mut counts: HashMap<u32, (usize, usize)> = HashMap::new();
+        for lr in &labels.runs {
+            counts.entry(lr.label).or_insert((0, 0)).1 += (lr.run.x_end - lr.run.x_start + 1) as usize;
+        }
+        let runs = self.canonical_runs();
+        let mut i = 0;
+        let mut j = 0;
+        while i < runs.len() && j < labels.runs.len() {
+            let lr = labels.runs[j];
+            if runs[i].y < lr.run.y {
+                i += 1;
+                continue;
+            } else if runs[i].y > lr.run.y {
+                j += 1;
+                continue;
+            } else if let Some(run) = runs[i].intersect(lr.run) {
+                counts.get_mut(&lr.label).unwrap().0 += (run.x_end - run.x_start + 1) as usize;
+            }
+            if runs[i].x_end < lr.run.x_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        counts.into_iter()
+            .map(|(label, (covered, total))| (label, covered as f64 / total as f64))
+            .collect()
+    }
+}
+
+/// Parts of sorted, non overlapping labeled runs which are inside of sorted mask runs.
+fn intersect_labels(runs: &[LabelRun], mask: &[Run]) -> Vec<LabelRun> {
+    let mut res = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < runs.len() && j < mask.len() {
+        let (lr, run) = (runs[i], mask[j]);
+        if let Some(run) = lr.run.intersect(run) {
+            res.push(LabelRun { run, label: lr.label });
+        }
+        // run which ends first can not intersect any later run
+        if (lr.run.y, lr.run.x_end) < (run.y, run.x_end) {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    res
+}
+
+/// Turn possibly overlapping labeled runs into sorted, non overlapping runs
+/// where pixels claimed by more than one label are resolved with policy.
+fn resolve_conflicts(mut runs: Vec<LabelRun>, policy: ConflictPolicy) -> Vec<LabelRun> {
+    runs.sort_unstable_by_key(|lr| lr.run);
+    let mut res: Vec<LabelRun> = Vec::with_capacity(runs.len());
+    let mut row_start = 0;
+    while row_start < runs.len() {
+        let y = runs[row_start].run.y;
+        let row_end = row_start + runs[row_start..].iter().take_while(|lr| lr.run.y == y).count();
+        // events: (x, label, +1 when label starts at x, -1 when label ends before x)
+        let mut events: Vec<(i32, u32, i32)> = Vec::with_capacity(2 * (row_end - row_start));
+        for lr in &runs[row_start..row_end] {
+            events.push((lr.run.x_start, lr.label, 1));
+            events.push((lr.run.x_end + 1, lr.label, -1));
+        }
+        events.sort_unstable();
+        // active labels with number of runs covering current position
+        let mut active: BTreeMap<u32, usize> = BTreeMap::new();
+        let mut i = 0;
+        while i < events.len() {
+            let x = events[i].0;
+            while i < events.len() && events[i].0 == x {
+                let (_, label, delta) = events[i];
+                let cnt = active.entry(label).or_insert(0);
+                if delta > 0 {
+                    *cnt += 1;
+                } else {
+                    *cnt -= 1;
+                    if *cnt == 0 {
+                        active.remove(&label);
+                    }
+                }
+                i += 1;
+            }
+            if i == events.len() {
+                break;
+            }
+            let label = match policy {
+                ConflictPolicy::MinLabel => active.keys().next().copied(),
+                ConflictPolicy::MaxLabel => active.keys().next_back().copied(),
+                ConflictPolicy::Background if active.len() == 1 => active.keys().next().copied(),
+                ConflictPolicy::Background => None,
+            };
+            if let Some(label) = label {
+                let run = Run::new(x, events[i].0 - 1, y);
+                match res.last_mut() {
+                    Some(last) if last.label == label && last.run.y == y && last.run.x_end + 1 == x => {
+                        last.run.x_end = run.x_end;
+                    }
+                    _ => res.push(LabelRun { run, label }),
+                }
+            }
+        }
+        row_start = row_end;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::XorShift;
+    use crate::Image;
+
+    #[test]
+    fn label_map_round_trip_test() {
+        let labels = vec![
+            0, 1, 1, 2,
+            3, 3, 0, 0,
+            0, 0, 0, 5,
+        ];
+        let lrle = LabelRle::from_label_map(4, 3, &labels).unwrap();
+        assert_eq!(lrle.runs(), &[
+            LabelRun { run: Run::new(1, 2, 0), label: 1 },
+            LabelRun { run: Run::new(3, 3, 0), label: 2 },
+            LabelRun { run: Run::new(0, 1, 1), label: 3 },
+            LabelRun { run: Run::new(3, 3, 2), label: 5 },
+        ]);
+        assert_eq!(lrle.to_label_map(), labels);
+        assert_eq!(lrle.labels(), vec![1, 2, 3, 5]);
+        assert_eq!(
+            LabelRle::from_label_map(4, 3, &labels[1..]),
+            Err(RleError::DataLength { expected: 12, actual: 11 })
+        );
+        assert_eq!(
+            LabelRle::from_label_map(usize::MAX, 2, &labels),
+            Err(RleError::DataLength { expected: usize::MAX, actual: 12 })
+        );
+    }
+
+    #[test]
+    fn extract_and_relabel_test() {
+        let mut lrle = LabelRle::from_label_map(3, 2, &[
+            1, 2, 2,
+            1, 1, 0,
+        ]).unwrap();
+        assert_eq!(lrle.extract(1).to_image(1), Image::new(3, 2, vec![
+            1, 0, 0,
+            1, 1, 0,
+        ]));
+        // merge label 2 into 1 and check that runs are merged
+        lrle.relabel(|l| if l == 2 { 1 } else { l });
+        assert_eq!(lrle.runs(), &[
+            LabelRun { run: Run::new(0, 2, 0), label: 1 },
+            LabelRun { run: Run::new(0, 1, 1), label: 1 },
+        ]);
+        lrle.relabel(|_| 0);
+        assert!(lrle.runs().is_empty());
+    }
+
+    #[test]
+    fn from_masks_test() {
+        let a = RLE::from(&Image::new(4, 1, vec![1, 1, 1, 0]));
+        let b = RLE::from(&Image::new(4, 1, vec![0, 1, 1, 1]));
+        let masks = [(1, &a), (2, &b)];
+        assert_eq!(LabelRle::from_masks(4, 1, &masks, ConflictPolicy::MinLabel).to_label_map(), vec![1, 1, 1, 2]);
+        assert_eq!(LabelRle::from_masks(4, 1, &masks, ConflictPolicy::MaxLabel).to_label_map(), vec![1, 2, 2, 2]);
+        assert_eq!(LabelRle::from_masks(4, 1, &masks, ConflictPolicy::Background).to_label_map(), vec![1, 0, 0, 2]);
+    }
+
+    #[test]
+    fn dilate_test() {
+        let lrle = LabelRle::from_label_map(6, 1, &[1, 0, 0, 0, 2, 0]).unwrap();
+        let s = RLE::linf_structuring(1);
+        assert_eq!(lrle.dilate(&s, ConflictPolicy::MinLabel).to_label_map(), vec![1, 1, 0, 2, 2, 2]);
+        let s = RLE::linf_structuring(2);
+        assert_eq!(lrle.dilate(&s, ConflictPolicy::MinLabel).to_label_map(), vec![1, 1, 1, 2, 2, 2]);
+        assert_eq!(lrle.dilate(&s, ConflictPolicy::MaxLabel).to_label_map(), vec![1, 1, 2, 2, 2, 2]);
+        assert_eq!(lrle.dilate(&s, ConflictPolicy::Background).to_label_map(), vec![1, 1, 0, 2, 2, 2]);
+
+        // same as dilating every label separately
+        let labels: Vec<u32> = XorShift::new(5).take(13 * 9).map(|n| if n % 3 == 0 { n % 5 } else { 0 }).collect();
+        let lrle = LabelRle::from_label_map(13, 9, &labels).unwrap();
+        let free = !&lrle.foreground();
+        for s in &[RLE::l1_structuring(1), RLE::rect_structuring(2, 3)] {
+            for &policy in &[ConflictPolicy::MinLabel, ConflictPolicy::MaxLabel, ConflictPolicy::Background] {
+                let mut grown = Vec::new();
+                for label in lrle.labels() {
+                    let dilated = &lrle.extract(label).dilate(s) & &free;
+                    grown.extend(dilated.runs().iter().map(|&run| LabelRun { run, label }));
+                }
+                let mut runs = resolve_conflicts(grown, policy);
+                runs.extend(lrle.runs());
+                runs.sort_unstable_by_key(|lr| lr.run);
+                let mut expected = LabelRle { width: 13, height: 9, runs };
+                expected.merge_touching_runs();
+                assert_eq!(lrle.dilate(s, policy), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn coverage_test() {
+        let labels = LabelRle::from_label_map(4, 2, &[
+            1, 1, 2, 2,
+            1, 1, 0, 3,
+        ]).unwrap();
+        let mask = RLE::from(&Image::new(4, 2, vec![
+            0, 1, 1, 1,
+            0, 0, 1, 0,
+        ]));
+        let coverage = mask.coverage(&labels);
+        assert_eq!(coverage.len(), 3);
+        assert_eq!(coverage[&1], 0.25);
+        assert_eq!(coverage[&2], 1.0);
+        assert_eq!(coverage[&3], 0.0);
+    }
+}
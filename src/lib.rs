@@ -1,10 +1,97 @@
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls;
+#[cfg(feature = "bumpalo")]
+mod arena;
+mod ascii;
+mod bitmap;
+mod border;
+mod boundary;
+mod change;
+#[cfg(feature = "coco")]
+mod coco;
+mod col_rle;
+pub mod connected_components;
+mod context;
+mod contour;
+mod coord;
+mod decompose;
+mod distance;
+pub mod document;
+pub mod draw;
+mod error;
+pub mod features;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod flip_bits_iter;
 mod image;
+#[cfg(feature = "image-interop")]
+mod image_interop;
+mod indexed;
+mod label;
+mod mask;
+pub mod metrics;
+mod moments;
+mod morphology;
+pub mod nav;
+mod paste;
+mod patch;
+mod pipeline;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "png")]
+mod png_stream;
+mod quadtree;
+#[cfg(feature = "rand")]
+mod random;
+mod reconstruction;
+mod report;
 mod rle;
 mod ops;
+pub mod overlay;
+mod parallel;
 mod run;
+mod sequence;
+mod serialize;
+mod set_iter;
+pub mod skeleton;
+mod smooth;
+mod structuring;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(test)]
+mod test_util;
+mod threshold;
+mod tile;
+mod trace;
+mod transform;
+mod view;
 
 pub(crate) use flip_bits_iter::FlipBitsIter;
+pub use border::BorderMode;
+pub use boundary::BandSide;
+pub use change::{ChangeStats, ComponentChange};
+pub use col_rle::ColRle;
+pub use connected_components::{ComponentStats, Connectivity};
+pub use context::{CancellationToken, Context};
+pub use contour::{Contour, ContourKind};
+pub use error::RleError;
 pub use self::image::Image;
+pub use indexed::IndexedRle;
+pub use label::{ConflictPolicy, LabelRle, LabelRun};
+pub use mask::{CostModel, Mask, Representation, RepresentationAdvice};
+pub use moments::Moments;
+pub use morphology::Morphology;
+pub use paste::PasteMode;
+pub use patch::MaskPatch;
+pub use pipeline::Pipeline;
+#[cfg(feature = "png")]
+pub use png_stream::PngError;
+pub use quadtree::{QuadNode, QuadTree};
+pub use report::{MaskReport, RunStats};
 pub use run::Run;
-pub use rle::RLE;
+pub use rle::{CollectRle, RLE};
+pub use sequence::{MaskDelta, MaskSequence};
+pub use smooth::StructuringShape;
+pub use set_iter::{difference_iter, intersect_iter, union_iter, DifferenceIter, IntersectIter, UnionIter};
+pub use structuring::{Kernel, StructuringElement};
+pub use view::RleView;
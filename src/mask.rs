@@ -0,0 +1,340 @@
+use super::{Image, Morphology, Run, RLE};
+use std::borrow::Cow;
+
+/// Binary image stored either densely or as RLE.
+/// Operations estimate cost of both representations (from number of runs, image size
+/// and structuring element) and run on cheaper one, converting only when needed.
+/// Sparse masks therefore stay RLE while noisy masks with many short runs fall back
+/// to dense image. Dense images use nonzero pixels as 1 and results contain 0s and 1s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mask {
+    Dense(Image),
+    Rle(RLE),
+}
+
+/// Representation of binary image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    Dense,
+    Rle,
+}
+
+/// Cost model used to choose representation for morphological operation.
+/// Costs are in abstract units where one unit is processing of one pair of runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostModel {
+    /// relative cost of processing one pixel of dense image per run of structuring element
+    pub dense_pixel_cost: f64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self { dense_pixel_cost: 0.25 }
+    }
+}
+
+impl CostModel {
+    /// Cost of dilation or erosion of RLE with given number of runs with structuring
+    /// element with s_runs runs: all p = runs * s_runs pairs of runs have to be sorted
+    /// and merged, so cost is p * log2(p) (p and log2(p) are at least 1).
+    pub fn rle_cost(&self, runs: usize, s_runs: usize) -> f64 {
+        let primary = (runs as f64 * s_runs as f64).max(1.0);
+        primary * primary.log2().max(1.0)
+    }
+
+    /// Cost of dilation or erosion of dense image with given number of pixels
+    /// with structuring element with s_runs runs, pixels * s_runs * dense_pixel_cost.
+    pub fn dense_cost(&self, pixels: usize, s_runs: usize) -> f64 {
+        pixels as f64 * s_runs as f64 * self.dense_pixel_cost
+    }
+
+    /// Cost of writing given number of runs of result of operation on RLE, one unit
+    /// per run.
+    pub fn output_cost(&self, runs: usize) -> f64 {
+        runs as f64
+    }
+
+    /// Cost of converting image with given number of pixels to other representation,
+    /// one unit per pixel.
+    pub fn conversion_cost(&self, pixels: usize) -> f64 {
+        pixels as f64
+    }
+}
+
+/// Estimated costs of dilating (or eroding) RLE with structuring element,
+/// see `RLE::suggest_representation`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepresentationAdvice {
+    /// number of runs of input
+    pub input_runs: usize,
+    /// rough estimate of number of runs after dilation, runs separated by gaps
+    /// narrower than structuring element are counted as one
+    pub estimated_output_runs: usize,
+    /// cost of operation on RLE including writing estimated output runs
+    pub rle_cost: f64,
+    /// cost of operation on dense image including conversion to dense image
+    pub dense_cost: f64,
+    /// cheaper representation
+    pub suggested: Representation,
+}
+
+impl RLE {
+    /// Estimate cost of dilation (or erosion) with structuring element s for RLE and
+    /// for dense image using default `CostModel`, useful to decide when to re-encode
+    /// masks which became too noisy for RLE.
+    /// RLE cost is `rle_cost(input runs, runs of s) + output_cost(estimated output runs)`
+    /// and dense cost is `dense_cost(pixels, runs of s) + conversion_cost(pixels)`,
+    /// dense image is suggested only if it is cheaper.
+    pub fn suggest_representation(&self, s: &RLE) -> RepresentationAdvice {
+        let model = CostModel::default();
+        let runs = self.canonical_runs();
+        let gap = s.width() as i32;
+        let estimated_output_runs = runs.iter().enumerate()
+            .filter(|&(i, run)| i == 0 || runs[i - 1].y != run.y || run.x_start - runs[i - 1].x_end > gap)
+            .count();
+        let pixels = self.width * self.height;
+        let rle_cost = model.rle_cost(runs.len(), s.runs().len()) + model.output_cost(estimated_output_runs);
+        let dense_cost = model.dense_cost(pixels, s.runs().len()) + model.conversion_cost(pixels);
+        RepresentationAdvice {
+            input_runs: runs.len(),
+            estimated_output_runs,
+            rle_cost,
+            dense_cost,
+            suggested: if dense_cost < rle_cost { Representation::Dense } else { Representation::Rle },
+        }
+    }
+}
+
+impl Mask {
+    /// Get image width.
+    pub fn width(&self) -> usize {
+        match self {
+            Mask::Dense(img) => img.w(),
+            Mask::Rle(rle) => rle.width(),
+        }
+    }
+
+    /// Get image height.
+    pub fn height(&self) -> usize {
+        match self {
+            Mask::Dense(img) => img.h(),
+            Mask::Rle(rle) => rle.height(),
+        }
+    }
+
+    /// Check if mask is currently stored densely.
+    pub fn is_dense(&self) -> bool {
+        matches!(self, Mask::Dense(_))
+    }
+
+    /// Get mask as RLE, converting if it is stored densely.
+    pub fn to_rle(&self) -> Cow<'_, RLE> {
+        match self {
+            Mask::Dense(img) => Cow::Owned(RLE::from(img)),
+            Mask::Rle(rle) => Cow::Borrowed(rle),
+        }
+    }
+
+    /// Get mask as dense image with 0s and 1s, converting if it is stored as RLE.
+    pub fn to_image(&self) -> Cow<'_, Image> {
+        match self {
+            Mask::Dense(img) => Cow::Borrowed(img),
+            Mask::Rle(rle) => Cow::Owned(rle.to_image(1)),
+        }
+    }
+
+    /// Convert mask to RLE.
+    pub fn into_rle(self) -> RLE {
+        match self {
+            Mask::Dense(img) => RLE::from(&img),
+            Mask::Rle(rle) => rle,
+        }
+    }
+
+    /// Convert mask to dense image with 0s and 1s.
+    pub fn into_image(self) -> Image {
+        match self {
+            Mask::Dense(img) => img,
+            Mask::Rle(rle) => rle.to_image(1),
+        }
+    }
+
+    /// Fraction of pixels set to 1, 0 for image without pixels.
+    pub fn density(&self) -> f64 {
+        let pixels = self.width() * self.height();
+        if pixels == 0 {
+            return 0.0;
+        }
+        let area = match self {
+            Mask::Dense(img) => img.data().iter().filter(|&&p| p > 0).count(),
+            Mask::Rle(rle) => rle.area(),
+        };
+        area as f64 / pixels as f64
+    }
+
+    /// Number of runs of mask (for dense image runs are counted without encoding).
+    fn run_count(&self) -> usize {
+        match self {
+            Mask::Dense(img) => (0..img.h())
+                .map(|y| {
+                    let row = &img[y];
+                    (0..row.len()).filter(|&x| row[x] > 0 && (x == 0 || row[x - 1] == 0)).count()
+                })
+                .sum(),
+            Mask::Rle(rle) => rle.runs().len(),
+        }
+    }
+
+    /// Decide if operation with structuring element s should be done on dense image.
+    fn prefer_dense(&self, s: &RLE) -> bool {
+        let model = CostModel::default();
+        let pixels = self.width() * self.height();
+        let mut rle_cost = model.rle_cost(self.run_count(), s.runs().len());
+        let mut dense_cost = model.dense_cost(pixels, s.runs().len());
+        if self.is_dense() {
+            rle_cost += model.conversion_cost(pixels);
+        } else {
+            dense_cost += model.conversion_cost(pixels);
+        }
+        dense_cost < rle_cost
+    }
+
+    fn apply(&self, s: &RLE, dilate: bool) -> Self {
+        if self.prefer_dense(s) {
+            Mask::Dense(dense_apply(&self.to_image(), s, dilate))
+        } else if dilate {
+            Mask::Rle(self.to_rle().dilate(s))
+        } else {
+            Mask::Rle(self.to_rle().erode(s))
+        }
+    }
+}
+
+impl From<RLE> for Mask {
+    fn from(rle: RLE) -> Self {
+        Mask::Rle(rle)
+    }
+}
+
+impl From<Image> for Mask {
+    fn from(img: Image) -> Self {
+        Mask::Dense(img)
+    }
+}
+
+/// Structuring element of mask is always used as RLE.
+impl Morphology for Mask {
+    fn dilate(&self, s: &Self) -> Self {
+        self.apply(&s.to_rle(), true)
+    }
+
+    fn erode(&self, s: &Self) -> Self {
+        self.apply(&s.to_rle(), false)
+    }
+
+    fn reflect(&self) -> Self {
+        match self {
+            Mask::Dense(img) => Mask::Dense(img.reflect()),
+            Mask::Rle(rle) => Mask::Rle(rle.reflect()),
+        }
+    }
+
+    /// Flipping is done in current representation.
+    fn invert(&self) -> Self {
+        match self {
+            Mask::Dense(img) => Mask::Dense(img.invert()),
+            Mask::Rle(rle) => Mask::Rle(!rle),
+        }
+    }
+}
+
+/// Dilate (or erode) dense image row by row: for every run of structuring element
+/// count of 1s (or 0s) in window is read from prefix sums of shifted row.
+fn dense_apply(img: &Image, s: &RLE, dilate: bool) -> Image {
+    let (w, h) = (img.w(), img.h());
+    let (cx, cy) = ((s.width() / 2) as i64, (s.height() / 2) as i64);
+    // prefix[y * (w + 1) + x] is number of counted pixels in row y before column x
+    let mut prefix = vec![0u32; (w + 1) * h];
+    for y in 0..h {
+        let row = &img[y];
+        for x in 0..w {
+            let counted = (row[x] > 0) == dilate;
+            prefix[y * (w + 1) + x + 1] = prefix[y * (w + 1) + x] + counted as u32;
+        }
+    }
+    let s_runs: Vec<Run> = s.canonical_runs().into_owned();
+    let mut out = Image::empty(w, h);
+    for y in 0..h {
+        for x in 0..w {
+            let hit = s_runs.iter().any(|run| {
+                let sy = y as i64 + run.y as i64 - cy;
+                if sy < 0 || sy >= h as i64 {
+                    return false;
+                }
+                let start = (x as i64 + run.x_start as i64 - cx).max(0).min(w as i64) as usize;
+                let end = (x as i64 + run.x_end as i64 - cx + 1).max(0).min(w as i64) as usize;
+                let row = sy as usize * (w + 1);
+                prefix[row + end] > prefix[row + start]
+            });
+            // for erosion hit means that some 0 pixel is under structuring element
+            out[y][x] = (hit == dilate) as u8;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn noise(w: usize, h: usize) -> Image {
+        test_util::noise(w, h, 0x2545_f491, |state| state & 1 == 1)
+    }
+
+    #[test]
+    fn backends_agree_test() {
+        let img = noise(23, 17);
+        let rle = RLE::from(&img);
+        let s = RLE::from_raw_data(3, 2, &[0, 1, 1, 1, 0, 1]);
+        for &dilate in &[true, false] {
+            let expected = if dilate { rle.dilate(&s) } else { rle.erode(&s) };
+            assert_eq!(dense_apply(&img, &s, dilate), expected.to_image(1));
+        }
+    }
+
+    #[test]
+    fn backend_selection_test() {
+        let s = Mask::from(RLE::linf_structuring(1));
+        // sparse mask stays RLE
+        let mut sparse = RLE::new(200, 200);
+        crate::draw::filled_rect(&mut sparse, 10, 10, 50, 50);
+        let dilated = Mask::from(sparse.clone()).dilate(&s);
+        assert!(!dilated.is_dense());
+        assert_eq!(dilated.into_rle(), sparse.dilate(&RLE::linf_structuring(1)));
+        // noise with many short runs is processed densely
+        let img = noise(200, 200);
+        let rle = RLE::from(&img);
+        let eroded = Mask::from(rle.clone()).erode(&s);
+        assert!(eroded.is_dense());
+        assert_eq!(eroded.to_rle().into_owned(), rle.erode(&RLE::linf_structuring(1)));
+        assert_eq!(Mask::from(img.clone()).invert().into_image(), img.flip_bits());
+    }
+
+    #[test]
+    fn suggest_representation_test() {
+        let mut sparse = RLE::new(200, 200);
+        crate::draw::filled_rect(&mut sparse, 10, 10, 50, 50);
+        let advice = sparse.suggest_representation(&RLE::linf_structuring(1));
+        assert_eq!(advice.input_runs, 50);
+        assert_eq!(advice.estimated_output_runs, 50);
+        let model = CostModel::default();
+        assert_eq!(advice.rle_cost, model.rle_cost(50, 3) + model.output_cost(50));
+        assert_eq!(advice.suggested, Representation::Rle);
+
+        let noisy = RLE::from(&noise(200, 200));
+        let advice = noisy.suggest_representation(&RLE::linf_structuring(1));
+        assert!(advice.estimated_output_runs < advice.input_runs);
+        assert_eq!(advice.suggested, Representation::Dense);
+    }
+}
@@ -0,0 +1,276 @@
+//! Metrics for comparing sets of masks, for example for evaluation of
+//! detection/segmentation results.
+use std::borrow::Cow;
+
+use super::distance::squared_distance_map;
+use super::parallel;
+use super::{Run, RLE};
+
+/// Canonical runs of mask together with its area and bounding box,
+/// computed once per mask so that pairwise comparisons are cheap.
+struct Summary<'a> {
+    runs: Cow<'a, [Run]>,
+    area: usize,
+    /// (x_min, y_min, x_max, y_max), None if mask is empty
+    bbox: Option<(i32, i32, i32, i32)>,
+}
+
+impl<'a> Summary<'a> {
+    fn new(rle: &'a RLE) -> Self {
+        let runs = rle.canonical_runs();
+        let area = runs.iter().map(|run| (run.x_end - run.x_start + 1) as usize).sum();
+        let bbox = match (runs.first(), runs.last()) {
+            (Some(first), Some(last)) => {
+                let x_min = runs.iter().map(|run| run.x_start).min().unwrap();
+                let x_max = runs.iter().map(|run| run.x_end).max().unwrap();
+                Some((x_min, first.y, x_max, last.y))
+            }
+            _ => None,
+        };
+        Self { runs, area, bbox }
+    }
+
+    fn bbox_intersects(&self, other: &Self) -> bool {
+        match (self.bbox, other.bbox) {
+            (Some(a), Some(b)) => a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3,
+            _ => false,
+        }
+    }
+
+    /// Number of pixels set in both masks.
+    fn intersection_area(&self, other: &Self) -> usize {
+        if !self.bbox_intersects(other) {
+            return 0;
+        }
+        let (a, b) = (&self.runs, &other.runs);
+        let mut area = 0;
+        let mut i = 0;
+        let mut j = 0;
+        while i < a.len() && j < b.len() {
+            if a[i].y < b[j].y {
+                i += 1;
+                continue;
+            } else if a[i].y > b[j].y {
+                j += 1;
+                continue;
+            } else if let Some(run) = a[i].intersect(b[j]) {
+                area += (run.x_end - run.x_start + 1) as usize;
+            }
+            if a[i].x_end < b[j].x_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        area
+    }
+
+    /// Intersection over union, 1 if both masks are empty.
+    fn iou(&self, other: &Self) -> f64 {
+        let intersection = self.intersection_area(other);
+        let union = self.area + other.area - intersection;
+        if union == 0 {
+            1.0
+        } else {
+            intersection as f64 / union as f64
+        }
+    }
+}
+
+fn summaries(masks: &[RLE]) -> Vec<Summary<'_>> {
+    masks.iter().map(Summary::new).collect()
+}
+
+/// Intersection over union of every mask in a with every mask in b,
+/// result[i][j] is IoU of a[i] and b[j]. IoU of two empty masks is 1.
+/// Pairs whose bounding boxes do not overlap are skipped without looking at runs.
+/// With `rayon` feature rows of result are computed in parallel, result is same
+/// as with serial computation.
+/// If dimensions of all masks are not same this method will panic.
+pub fn iou_matrix(a: &[RLE], b: &[RLE]) -> Vec<Vec<f64>> {
+    if let Some(first) = a.first().or_else(|| b.first()) {
+        assert!(
+            a.iter().chain(b).all(|m| m.width == first.width && m.height == first.height),
+            "all masks must have same dimensions"
+        );
+    }
+    let a = summaries(a);
+    let b = summaries(b);
+    parallel::map(&a, |sa| b.iter().map(|sb| sa.iou(sb)).collect())
+}
+
+/// Greedy mask non-maximum suppression.
+/// Masks are visited from highest to lowest score and mask is kept if its IoU with
+/// every already kept mask is not greater than iou_threshold.
+/// Returns indices of kept masks ordered by descending score.
+/// If masks and scores do not have same length or dimensions of masks are not same
+/// this method will panic.
+pub fn nms(masks: &[RLE], scores: &[f32], iou_threshold: f32) -> Vec<usize> {
+    assert_eq!(masks.len(), scores.len(), "every mask needs a score");
+    if let Some(first) = masks.first() {
+        assert!(
+            masks.iter().all(|m| m.width == first.width && m.height == first.height),
+            "all masks must have same dimensions"
+        );
+    }
+    let mut order: Vec<usize> = (0..masks.len()).collect();
+    // stable sort so that masks with equal scores keep their order, NaN scores go last
+    order.sort_by(|&i, &j| {
+        scores[j].partial_cmp(&scores[i]).unwrap_or_else(|| scores[i].is_nan().cmp(&scores[j].is_nan()))
+    });
+    let summaries = summaries(masks);
+    let mut kept: Vec<usize> = Vec::new();
+    for i in order {
+        let suppressed = kept.iter()
+            .any(|&j| summaries[i].iou(&summaries[j]) > iou_threshold as f64);
+        if !suppressed {
+            kept.push(i);
+        }
+    }
+    kept
+}
+
+/// Distances from boundary pixels of a to nearest boundary pixel of b and from
+/// boundary pixels of b to nearest boundary pixel of a. Boundary pixels are mask
+/// pixels which have 4-connected background neighbour (pixels outside of image are
+/// background). If some boundary is empty error holds distance between masks, 0 if
+/// both are empty and infinite otherwise.
+fn boundary_distances(a: &RLE, b: &RLE) -> Result<(Vec<f64>, Vec<f64>), f64> {
+    assert!(a.width == b.width && a.height == b.height);
+    let boundary_a = a.boundary_band_side(1, super::BandSide::Inner);
+    let boundary_b = b.boundary_band_side(1, super::BandSide::Inner);
+    match (boundary_a.runs().is_empty(), boundary_b.runs().is_empty()) {
+        (true, true) => return Err(0.0),
+        (true, false) | (false, true) => return Err(f64::INFINITY),
+        _ => {}
+    }
+    let distances = |from: &RLE, to: &RLE| {
+        let map = squared_distance_map(to);
+        let mut distances = Vec::with_capacity(from.area());
+        for run in from.runs() {
+            let start = run.y as usize * from.width;
+            distances.extend(map[start + run.x_start as usize..=start + run.x_end as usize].iter().map(|d| d.sqrt()));
+        }
+        distances
+    };
+    Ok((distances(&boundary_a, &boundary_b), distances(&boundary_b, &boundary_a)))
+}
+
+/// Symmetric chamfer distance between boundaries of two masks, that is average of
+/// mean distance from boundary pixels of a to nearest boundary pixel of b and
+/// mean distance from boundary pixels of b to nearest boundary pixel of a.
+/// Boundary pixels are mask pixels which have 4-connected background neighbour
+/// (pixels outside of image are background).
+/// Distance is 0 if both masks are empty and infinite if only one of them is empty.
+/// If dimensions of a and b are not same this method will panic.
+pub fn chamfer_distance(a: &RLE, b: &RLE) -> f64 {
+    let mean = |d: &[f64]| d.iter().sum::<f64>() / d.len() as f64;
+    match boundary_distances(a, b) {
+        Ok((ab, ba)) => (mean(&ab) + mean(&ba)) / 2.0,
+        Err(distance) => distance,
+    }
+}
+
+/// Hausdorff distance between boundaries of two masks, largest distance from
+/// boundary pixel of one mask to nearest boundary pixel of other mask.
+/// Boundaries and empty masks are handled same as in `chamfer_distance`.
+/// If dimensions of a and b are not same this method will panic.
+pub fn hausdorff_distance(a: &RLE, b: &RLE) -> f64 {
+    match boundary_distances(a, b) {
+        Ok((ab, ba)) => ab.iter().chain(&ba).fold(0.0, |max: f64, &d| max.max(d)),
+        Err(distance) => distance,
+    }
+}
+
+/// Average symmetric surface distance (ASSD) between boundaries of two masks, mean
+/// distance from all boundary pixels of both masks to nearest boundary pixel of
+/// other mask. Unlike `chamfer_distance` every boundary pixel has same weight, so
+/// mask with longer boundary contributes more.
+/// Boundaries and empty masks are handled same as in `chamfer_distance`.
+/// If dimensions of a and b are not same this method will panic.
+pub fn average_surface_distance(a: &RLE, b: &RLE) -> f64 {
+    match boundary_distances(a, b) {
+        Ok((ab, ba)) => (ab.iter().sum::<f64>() + ba.iter().sum::<f64>()) / (ab.len() + ba.len()) as f64,
+        Err(distance) => distance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn iou_matrix_test() {
+        let a = vec![
+            RLE::from(&Image::new(4, 2, vec![
+                1, 1, 0, 0,
+                1, 1, 0, 0,
+            ])),
+            RLE::new(4, 2),
+        ];
+        let b = vec![
+            RLE::from(&Image::new(4, 2, vec![
+                0, 1, 1, 0,
+                0, 1, 1, 0,
+            ])),
+            RLE::from(&Image::new(4, 2, vec![
+                0, 0, 0, 1,
+                0, 0, 0, 1,
+            ])),
+            RLE::new(4, 2),
+        ];
+        let m = iou_matrix(&a, &b);
+        assert_eq!(m, vec![
+            vec![2.0 / 6.0, 0.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+        ]);
+        assert!(iou_matrix(&[], &b).is_empty());
+        assert_eq!(iou_matrix(&a, &[]), vec![Vec::<f64>::new(); 2]);
+    }
+
+    #[test]
+    fn nms_test() {
+        let rect = |x, w| {
+            let mut rle = RLE::new(8, 2);
+            crate::draw::filled_rect(&mut rle, x, 0, w, 2);
+            rle
+        };
+        let masks = vec![rect(0, 4), rect(1, 4), rect(5, 3), rect(0, 2)];
+        // IoU(0, 1) = 6/10, IoU(0, 3) = 4/8, IoU(1, 3) = 2/10
+        assert_eq!(nms(&masks, &[0.9, 0.95, 0.5, 0.3], 0.5), vec![1, 2, 3]);
+        assert_eq!(nms(&masks, &[0.9, 0.8, 0.5, 0.3], 0.5), vec![0, 2, 3]);
+        assert_eq!(nms(&masks, &[0.9, 0.8, 0.5, 0.3], 0.4), vec![0, 2]);
+        assert_eq!(nms(&masks, &[f32::NAN, 0.8, 0.5, 0.3], 0.7), vec![1, 2, 3, 0]);
+        assert!(nms(&[], &[], 0.5).is_empty());
+    }
+
+    #[test]
+    fn chamfer_distance_test() {
+        let rect = |x| {
+            let mut rle = RLE::new(8, 3);
+            crate::draw::filled_rect(&mut rle, x, 0, 1, 3);
+            rle
+        };
+        assert_eq!(chamfer_distance(&rect(1), &rect(1)), 0.0);
+        // every boundary pixel is 2 pixels away from other boundary
+        assert_eq!(chamfer_distance(&rect(1), &rect(3)), 2.0);
+        assert_eq!(chamfer_distance(&rect(1), &rect(4)), chamfer_distance(&rect(4), &rect(1)));
+        assert_eq!(chamfer_distance(&RLE::new(8, 3), &RLE::new(8, 3)), 0.0);
+        assert_eq!(chamfer_distance(&rect(1), &RLE::new(8, 3)), f64::INFINITY);
+    }
+
+    #[test]
+    fn surface_distance_test() {
+        let a = RLE::from(&Image::new(6, 1, vec![1, 0, 0, 0, 0, 0]));
+        let b = RLE::from(&Image::new(6, 1, vec![0, 0, 1, 1, 1, 1]));
+        // distances from a are [2], from b [2, 3, 4, 5]
+        assert_eq!(hausdorff_distance(&a, &b), 5.0);
+        assert_eq!(hausdorff_distance(&b, &a), 5.0);
+        assert_eq!(average_surface_distance(&a, &b), 16.0 / 5.0);
+        assert_eq!(chamfer_distance(&a, &b), 2.75);
+        assert_eq!(hausdorff_distance(&a, &a), 0.0);
+        assert_eq!(average_surface_distance(&a, &RLE::new(6, 1)), f64::INFINITY);
+        assert_eq!(hausdorff_distance(&RLE::new(6, 1), &RLE::new(6, 1)), 0.0);
+    }
+}
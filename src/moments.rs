@@ -0,0 +1,148 @@
+use super::RLE;
+
+/// Raw image moments up to second order, m_pq = sum of x^p * y^q over all pixels set to 1
+/// where (x, y) are pixel coordinates (same as OpenCV moments of binary image).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Moments {
+    /// number of pixels
+    pub m00: f64,
+    /// sum of x
+    pub m10: f64,
+    /// sum of y
+    pub m01: f64,
+    /// sum of x * x
+    pub m20: f64,
+    /// sum of x * y
+    pub m11: f64,
+    /// sum of y * y
+    pub m02: f64,
+}
+
+impl Moments {
+    /// Centroid (x, y) of pixels, None if there are no pixels.
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        if self.m00 == 0.0 {
+            return None;
+        }
+        Some((self.m10 / self.m00, self.m01 / self.m00))
+    }
+
+    /// Central moment mu20, 0 if there are no pixels.
+    pub fn mu20(&self) -> f64 {
+        self.centroid().map_or(0.0, |(cx, _)| self.m20 - cx * self.m10)
+    }
+
+    /// Central moment mu11, 0 if there are no pixels.
+    pub fn mu11(&self) -> f64 {
+        self.centroid().map_or(0.0, |(cx, _)| self.m11 - cx * self.m01)
+    }
+
+    /// Central moment mu02, 0 if there are no pixels.
+    pub fn mu02(&self) -> f64 {
+        self.centroid().map_or(0.0, |(_, cy)| self.m02 - cy * self.m01)
+    }
+
+    /// Angle in radians between x axis and major axis of ellipse with same second order
+    /// central moments, in range [-pi/2, pi/2]. Y axis points down, so positive angle
+    /// is clockwise on screen. None if there are no pixels.
+    pub fn orientation(&self) -> Option<f64> {
+        if self.m00 == 0.0 {
+            return None;
+        }
+        Some(0.5 * (2.0 * self.mu11()).atan2(self.mu20() - self.mu02()))
+    }
+}
+
+/// Sum of x^p for x in x_start..=x_end, p = 0, 1, 2.
+fn power_sums(x_start: i32, x_end: i32) -> (i128, i128, i128) {
+    let s1 = |k: i128| k * (k + 1) / 2;
+    let s2 = |k: i128| k * (k + 1) * (2 * k + 1) / 6;
+    let (a, b) = (x_start as i128, x_end as i128);
+    (b - a + 1, s1(b) - s1(a - 1), s2(b) - s2(a - 1))
+}
+
+impl RLE {
+    /// Compute moments of mask, each run is summed in closed form so image is
+    /// never decoded.
+    pub fn moments(&self) -> Moments {
+        // sums are exact, converted to floating point only at the end
+        let (mut m00, mut m10, mut m01, mut m20, mut m11, mut m02) = (0i128, 0i128, 0i128, 0i128, 0i128, 0i128);
+        for run in self.canonical_runs().iter() {
+            let (n, sx, sxx) = power_sums(run.x_start, run.x_end);
+            let y = run.y as i128;
+            m00 += n;
+            m10 += sx;
+            m01 += n * y;
+            m20 += sxx;
+            m11 += sx * y;
+            m02 += n * y * y;
+        }
+        Moments {
+            m00: m00 as f64,
+            m10: m10 as f64,
+            m01: m01 as f64,
+            m20: m20 as f64,
+            m11: m11 as f64,
+            m02: m02 as f64,
+        }
+    }
+
+    /// Centroid (x, y) of pixels set to 1, None if mask is empty.
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        self.moments().centroid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Image, RLE};
+
+    #[test]
+    fn moments_test() {
+        let img = Image::new(7, 5, vec![
+            0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 0, 1, 1, 1, 1, 1,
+            1, 0, 0, 0, 0, 1, 1,
+        ]);
+        let m = RLE::from(&img).moments();
+        let mut expected = [0.0; 6];
+        for y in 0..img.h() {
+            for x in 0..img.w() {
+                if img[y][x] > 0 {
+                    let (x, y) = (x as f64, y as f64);
+                    for (e, v) in expected.iter_mut().zip(&[1.0, x, y, x * x, x * y, y * y]) {
+                        *e += v;
+                    }
+                }
+            }
+        }
+        assert_eq!([m.m00, m.m10, m.m01, m.m20, m.m11, m.m02], expected);
+        let (cx, cy) = m.centroid().unwrap();
+        assert!((cx - expected[1] / expected[0]).abs() < 1e-12);
+        assert!((cy - expected[2] / expected[0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn orientation_test() {
+        assert_eq!(RLE::new(4, 4).centroid(), None);
+        assert_eq!(RLE::new(4, 4).moments().orientation(), None);
+
+        let horizontal = RLE::from_raw_data(5, 3, &[0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0]);
+        let m = horizontal.moments();
+        assert_eq!(m.centroid(), Some((2.0, 1.0)));
+        assert_eq!(m.mu20(), 10.0);
+        assert_eq!(m.mu02(), 0.0);
+        assert_eq!(m.orientation(), Some(0.0));
+
+        let vertical = horizontal.transpose();
+        assert!((vertical.moments().orientation().unwrap().abs() - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+
+        // diagonal from top left to bottom right, y axis points down
+        let diagonal = RLE::from_raw_data(3, 3, &[1, 0, 0, 0, 1, 0, 0, 0, 1]);
+        let m = diagonal.moments();
+        assert_eq!(m.mu11(), 2.0);
+        assert!((m.orientation().unwrap() - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Image, RLE};
+
+/// Basic morphological operations on binary images, implemented by `RLE` and by dense
+/// `Image` (where nonzero pixels are 1) so that generic code can work with both.
+/// Structuring element has same type as image and all operations follow semantics
+/// of `RLE::dilate` and `RLE::erode`.
+pub trait Morphology: Sized {
+    /// Dilate image with structuring element s.
+    fn dilate(&self, s: &Self) -> Self;
+
+    /// Erode image with structuring element s, pixels outside of image are treated as 1.
+    fn erode(&self, s: &Self) -> Self;
+
+    /// Flip all pixels (1s -> 0s, 0s -> 1s).
+    fn invert(&self) -> Self;
+
+    /// Reflect structuring element through its center (see `RLE::reflect`).
+    fn reflect(&self) -> Self;
+
+    /// Erode with structuring element s and then dilate with reflected s.
+    fn open(&self, s: &Self) -> Self {
+        self.erode(s).dilate(&s.reflect())
+    }
+
+    /// Dilate with structuring element s and then erode with reflected s.
+    fn close(&self, s: &Self) -> Self {
+        self.dilate(s).erode(&s.reflect())
+    }
+}
+
+impl Morphology for RLE {
+    fn dilate(&self, s: &Self) -> Self {
+        RLE::dilate(self, s)
+    }
+
+    fn erode(&self, s: &Self) -> Self {
+        RLE::erode(self, s)
+    }
+
+    fn invert(&self) -> Self {
+        !self
+    }
+
+    fn reflect(&self) -> Self {
+        RLE::reflect(self)
+    }
+}
+
+/// Get value of pixel in binary image, None if (x, y) is outside of image.
+fn pixel(img: &Image, x: i64, y: i64) -> Option<bool> {
+    if x < 0 || y < 0 || x >= img.w() as i64 || y >= img.h() as i64 {
+        return None;
+    }
+    Some(img[y as usize][x as usize] > 0)
+}
+
+/// Offsets (sx - cx, sy - cy) of all nonzero pixels of structuring element.
+fn offsets(s: &Image) -> Vec<(i64, i64)> {
+    let (cx, cy) = ((s.w() / 2) as i64, (s.h() / 2) as i64);
+    let mut res = Vec::new();
+    for sy in 0..s.h() {
+        for sx in 0..s.w() {
+            if s[sy][sx] > 0 {
+                res.push((sx as i64 - cx, sy as i64 - cy));
+            }
+        }
+    }
+    res
+}
+
+/// Naive implementation checking every pixel of structuring element for every pixel
+/// of image, result pixels are 0 or 1.
+impl Morphology for Image {
+    fn dilate(&self, s: &Self) -> Self {
+        let offsets = offsets(s);
+        let mut out = Image::empty(self.w(), self.h());
+        for y in 0..self.h() {
+            for x in 0..self.w() {
+                let set = offsets.iter()
+                    .any(|&(dx, dy)| pixel(self, x as i64 + dx, y as i64 + dy) == Some(true));
+                out[y][x] = set as u8;
+            }
+        }
+        out
+    }
+
+    fn erode(&self, s: &Self) -> Self {
+        let offsets = offsets(s);
+        let mut out = Image::empty(self.w(), self.h());
+        for y in 0..self.h() {
+            for x in 0..self.w() {
+                let set = offsets.iter()
+                    .all(|&(dx, dy)| pixel(self, x as i64 + dx, y as i64 + dy) != Some(false));
+                out[y][x] = set as u8;
+            }
+        }
+        out
+    }
+
+    fn invert(&self) -> Self {
+        self.clone().flip_bits()
+    }
+
+    fn reflect(&self) -> Self {
+        let (cx, cy) = (self.w() / 2, self.h() / 2);
+        let mut out = Image::empty(2 * cx + 1, 2 * cy + 1);
+        for y in 0..self.h() {
+            for x in 0..self.w() {
+                out[2 * cy - y][2 * cx - x] = self[y][x];
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Same generic code runs on both backends.
+    fn hollow<M: Morphology>(img: &M, s: &M) -> (M, M) {
+        (img.open(s).invert(), img.close(s))
+    }
+
+    #[test]
+    fn backends_agree_test() {
+        let img = Image::new(7, 6, vec![
+            0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 0, 0, 0,
+            0, 1, 0, 1, 1, 1, 0,
+            0, 1, 1, 1, 0, 1, 0,
+            0, 0, 0, 1, 1, 1, 0,
+            1, 0, 0, 0, 0, 0, 0,
+        ]);
+        for s in &[
+            RLE::linf_structuring(1),
+            RLE::l1_structuring(1),
+            RLE::from_raw_data(2, 1, &[1, 1]),
+            RLE::from_raw_data(2, 2, &[1, 1, 0, 1]),
+        ] {
+            let (opened, closed) = hollow(&RLE::from(&img), s);
+            let (dense_opened, dense_closed) = hollow(&img, &s.to_image(1));
+            assert_eq!(opened.to_image(1), dense_opened);
+            assert_eq!(closed.to_image(1), dense_closed);
+            assert_eq!(Morphology::reflect(&s.to_image(1)), s.reflect().to_image(1));
+            assert_eq!(Morphology::dilate(&img, &s.to_image(1)), RLE::from(&img).dilate(s).to_image(1));
+            assert_eq!(Morphology::erode(&img, &s.to_image(1)), RLE::from(&img).erode(s).to_image(1));
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Helpers for using RLE as robot occupancy grid where pixels set to 1 are occupied
+//! cells and pixels set to 0 are free cells.
+use super::{Run, RLE};
+
+/// How to decide if cell is occupied when merging several maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Cell is occupied if any map marks it as occupied.
+    Conservative,
+    /// Cell is occupied only if all maps mark it as occupied.
+    Optimistic,
+    /// Cell is occupied if at least given number of maps mark it as occupied.
+    AtLeast(usize),
+}
+
+/// Number of pixels needed to cover distance in meters.
+fn radius_px(radius_m: f64, resolution_m_per_px: f64) -> usize {
+    assert!(resolution_m_per_px > 0.0, "resolution must be positive");
+    if radius_m <= 0.0 {
+        return 0;
+    }
+    (radius_m / resolution_m_per_px).ceil() as usize
+}
+
+/// Inflate obstacles of occupancy grid by radius (in meters), usually radius of robot.
+/// This is dilation with disk of radius_m / resolution_m_per_px pixels (rounded up).
+pub fn inflate(grid: &RLE, radius_m: f64, resolution_m_per_px: f64) -> RLE {
+    let k = radius_px(radius_m, resolution_m_per_px);
+    if k == 0 {
+        return grid.clone();
+    }
+    grid.dilate(&RLE::disk_structuring(k))
+}
+
+/// Get cells where center of robot with given radius (in meters) can be placed,
+/// that is all cells which are not occupied after inflation.
+pub fn free_space(grid: &RLE, radius_m: f64, resolution_m_per_px: f64) -> RLE {
+    !&inflate(grid, radius_m, resolution_m_per_px)
+}
+
+/// Merge several occupancy grids using given policy.
+/// If maps is empty or dimensions of maps are not same this method will panic.
+pub fn merge(maps: &[&RLE], policy: MergePolicy) -> RLE {
+    assert!(!maps.is_empty(), "at least one map is needed");
+    let k = match policy {
+        MergePolicy::Conservative => 1,
+        MergePolicy::Optimistic => maps.len(),
+        MergePolicy::AtLeast(k) => k,
+    };
+    RLE::vote(maps, k)
+}
+
+/// Merge several occupancy grids where each map has its confidence.
+/// Cell is occupied if sum of confidences of maps which mark it as occupied
+/// is at least threshold. Sums are compared with small tolerance (relative to sum of
+/// all confidences) so rounding does not matter, for example confidences 0.7 and 0.1
+/// reach threshold 0.8.
+/// If maps is empty or dimensions of maps are not same this method will panic.
+pub fn merge_weighted(maps: &[(&RLE, f64)], threshold: f64) -> RLE {
+    assert!(!maps.is_empty(), "at least one map is needed");
+    let (width, height) = (maps[0].0.width(), maps[0].0.height());
+    if threshold <= 0.0 {
+        return RLE::ones(width, height);
+    }
+    // rounding error of running sum is bounded by sum of all confidences times epsilon
+    // for every addition, which is much less than this tolerance
+    let tolerance = 1e-9 * maps.iter().map(|&(_, confidence)| confidence.abs()).sum::<f64>();
+    // (y, x, confidence change at x)
+    let mut events: Vec<(i32, i32, f64)> = Vec::new();
+    for &(map, confidence) in maps {
+        assert!(map.width() == width && map.height() == height);
+        for run in map.canonical_runs().iter() {
+            events.push((run.y, run.x_start, confidence));
+            events.push((run.y, run.x_end + 1, -confidence));
+        }
+    }
+    events.sort_unstable_by_key(|&(y, x, _)| (y, x));
+    let mut runs: Vec<Run> = Vec::new();
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < events.len() {
+        let (y, x, _) = events[i];
+        while i < events.len() && events[i].0 == y && events[i].1 == x {
+            sum += events[i].2;
+            i += 1;
+        }
+        // sum is valid until next event in same row
+        if sum >= threshold - tolerance && i < events.len() && events[i].0 == y {
+            let run = Run::new(x, events[i].1 - 1, y);
+            match runs.last_mut() {
+                Some(last) if last.y == y && last.x_end + 1 == x => last.x_end = run.x_end,
+                _ => runs.push(run),
+            }
+        }
+    }
+    RLE::from_runs(width, height, runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn inflate_test() {
+        let grid = RLE::from(&Image::new(5, 5, vec![
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        // 0.15m with 0.1m per pixel is rounded up to 2 pixels
+        assert_eq!(inflate(&grid, 0.15, 0.1), grid.dilate(&RLE::disk_structuring(2)));
+        assert_eq!(inflate(&grid, 0.0, 0.1), grid);
+        assert_eq!(free_space(&grid, 0.1, 0.1).to_image(1), Image::new(5, 5, vec![
+            1, 1, 1, 1, 1,
+            1, 1, 0, 1, 1,
+            1, 0, 0, 0, 1,
+            1, 1, 0, 1, 1,
+            1, 1, 1, 1, 1,
+        ]));
+    }
+
+    #[test]
+    fn merge_test() {
+        let a = RLE::from(&Image::new(3, 1, vec![1, 1, 0]));
+        let b = RLE::from(&Image::new(3, 1, vec![0, 1, 1]));
+        let c = RLE::from(&Image::new(3, 1, vec![0, 1, 0]));
+        let maps = [&a, &b, &c];
+        assert_eq!(merge(&maps, MergePolicy::Conservative).to_image(1), Image::new(3, 1, vec![1, 1, 1]));
+        assert_eq!(merge(&maps, MergePolicy::Optimistic).to_image(1), Image::new(3, 1, vec![0, 1, 0]));
+        assert_eq!(merge(&maps[..2], MergePolicy::AtLeast(2)).to_image(1), Image::new(3, 1, vec![0, 1, 0]));
+    }
+
+    #[test]
+    fn merge_weighted_test() {
+        let a = RLE::from(&Image::new(3, 1, vec![1, 1, 0]));
+        let b = RLE::from(&Image::new(3, 1, vec![0, 1, 1]));
+        let merged = merge_weighted(&[(&a, 0.7), (&b, 0.2)], 0.5);
+        assert_eq!(merged.to_image(1), Image::new(3, 1, vec![1, 1, 0]));
+        let merged = merge_weighted(&[(&a, 0.3), (&b, 0.3)], 0.5);
+        assert_eq!(merged.to_image(1), Image::new(3, 1, vec![0, 1, 0]));
+        // 0.7 + 0.1 is slightly less than 0.8 and 0.7 + 0.1 - 0.7 slightly less than 0.1
+        let ones = RLE::ones(3, 1);
+        let merged = merge_weighted(&[(&a, 0.7), (&ones, 0.1)], 0.8);
+        assert_eq!(merged.to_image(1), Image::new(3, 1, vec![1, 1, 0]));
+        let merged = merge_weighted(&[(&a, 0.7), (&ones, 0.1)], 0.1);
+        assert_eq!(merged, RLE::ones(3, 1));
+        let merged = merge_weighted(&[(&a, 0.1), (&b, 0.2)], 0.3);
+        assert_eq!(merged.to_image(1), Image::new(3, 1, vec![0, 1, 0]));
+    }
+}
@@ -1,112 +1,177 @@
-use super::{Run, RLE};
-use std::ops::{BitAnd, Sub, BitOr, BitOrAssign, BitAndAssign, Not};
+use super::rle::row_chunks;
+use super::{coord, parallel, trace, Run, RleError, RLE};
+use std::ops::{BitAnd, BitAndAssign, Sub, SubAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
 
 /// Flip bits (1s -> 0s, 0s -> 1s)
-impl<'a> Not for &'a RLE {
+/// If width or height does not fit in i32 this will panic, complement would have
+/// runs which can not be represented.
+impl Not for &RLE {
     type Output = RLE;
     fn not(self) -> Self::Output {
-        // if all bits are 0, then set all 'height' runs with 'width' length 
-        if self.runs.is_empty() {
-            return RLE {
-                width: self.width,
-                height: self.height,
-                runs: (0..self.height).map(|i| Run {
-                    x_start: 0,
-                    x_end: self.width as i32 - 1,
-                    y: i as i32
-                }).collect(),
-            };
+        // image without pixels stays without pixels
+        if self.width == 0 || self.height == 0 {
+            return RLE::new(self.width, self.height);
         }
-        // runs has at least 1 element, set first as last
-        let mut last_run = self.runs[0];
-        let mut runs = Vec::new();
-        // first create all 0 rows with y less than last_run.y to ones 
-        runs.extend(
-            (0..last_run.y).map(|y| Run { x_start: 0, x_end: self.width as i32 - 1, y: y as _ })
-        );
-        // if first run does not start from 0 then add run from 0 to x_start - 1
-        if 0 < last_run.x_start {
+        if let Err(e) = coord::dimension(self.width).and(coord::dimension(self.height)) {
+            panic!("{}", e);
+        }
+        // runs outside of image (for example after dilation) must not be flipped
+        let self_runs = self.canonical_runs();
+        let chunks = row_chunks(&self_runs, OP_CHUNK_RUNS);
+        if chunks.len() <= 1 {
+            return RLE { width: self.width, height: self.height, runs: flip_rows(&self_runs, self.width, 0, self.height as i32) };
+        }
+        // chunk i flips rows from its first row to first row of chunk i + 1
+        let ranges: Vec<(&[Run], i32, i32)> = chunks.iter().enumerate()
+            .map(|(i, chunk)| {
+                let y_start = if i == 0 { 0 } else { chunk[0].y };
+                let y_end = chunks.get(i + 1).map_or(self.height as i32, |next| next[0].y);
+                (*chunk, y_start, y_end)
+            })
+            .collect();
+        let flipped = parallel::map(&ranges, |&(runs, y_start, y_end)| flip_rows(runs, self.width, y_start, y_end));
+        RLE { width: self.width, height: self.height, runs: flipped.concat() }
+    }
+}
+
+/// Flip bits of rows y_start..y_end of image with given width, runs must be canonical
+/// and inside of these rows.
+fn flip_rows(self_runs: &[Run], width: usize, y_start: i32, y_end: i32) -> Vec<Run> {
+    // if all bits are 0, then set all rows to runs with 'width' length
+    if self_runs.is_empty() {
+        return (y_start..y_end).map(|y| Run {
+            x_start: 0,
+            x_end: width as i32 - 1,
+            y,
+        }).collect();
+    }
+    // runs has at least 1 element, set first as last
+    let mut last_run = self_runs[0];
+    let mut runs = Vec::new();
+    // first create all 0 rows with y less than last_run.y to ones 
+    runs.extend(
+        (y_start..last_run.y).map(|y| Run { x_start: 0, x_end: width as i32 - 1, y: y as _ })
+    );
+    // if first run does not start from 0 then add run from 0 to x_start - 1
+    if 0 < last_run.x_start {
+        runs.push(Run {
+            x_start: 0,
+            x_end: last_run.x_start - 1,
+            y: last_run.y,
+        });
+    }
+    // go trough all other runs
+    for &run in self_runs.iter().skip(1) {
+        // if we are in same row 
+        if run.y == last_run.y {
+            // add run between last and current run (between them are zeroes)
             runs.push(Run {
-                x_start: 0,
-                x_end: last_run.x_start - 1,
-                y: last_run.y,
+                x_start: last_run.x_end + 1,
+                x_end: run.x_start - 1,
+                y: run.y,
             });
-        }
-        // go trough all other runs
-        for &run in self.runs.iter().skip(1) {
-            // if we are in same row 
-            if run.y == last_run.y {
-                // add run between last and current run (between them are zeroes)
+        } else { // we are not in the same row
+            // if last_run (run from previous row) did not go to end (width)
+            // then add ones starting from last_run to end of width
+            if width as i32 - 1 > last_run.x_end {
                 runs.push(Run {
                     x_start: last_run.x_end + 1,
+                    x_end: width as i32 - 1,
+                    y: last_run.y,
+                });
+            }
+            // add run for each row between last and current run
+            // if current run row is right after last run row then this will not loop
+            for i in last_run.y+1..run.y {
+                runs.push(Run {
+                    x_start: 0,
+                    x_end: width as i32 - 1,
+                    y: i,
+                });
+            }
+            // again if first run in this row does not start from 0 then add run with 1 
+            // from start to run.x_start
+            if 0 < run.x_start {
+                runs.push(Run {
+                    x_start: 0,
                     x_end: run.x_start - 1,
                     y: run.y,
                 });
-            } else { // we are not in the same row
-                // if last_run (run from previous row) did not go to end (width)
-                // then add ones starting from last_run to end of width
-                if self.width as i32 - 1 > last_run.x_end {
-                    runs.push(Run {
-                        x_start: last_run.x_end + 1,
-                        x_end: self.width as i32 - 1,
-                        y: last_run.y,
-                    });
-                }
-                // add run for each row between last and current run
-                // if current run row is right after last run row then this will not loop
-                for i in last_run.y+1..run.y {
-                    runs.push(Run {
-                        x_start: 0,
-                        x_end: self.width as i32 - 1,
-                        y: i,
-                    });
-                }
-                // again if first run in this row does not start from 0 then add run with 1 
-                // from start to run.x_start
-                if 0 < run.x_start {
-                    runs.push(Run {
-                        x_start: 0,
-                        x_end: run.x_start - 1,
-                        y: run.y,
-                    });
-                }
             }
-            last_run = run;
-        }
-        if self.width as i32 - 1 > last_run.x_end {
-            runs.push(Run {
-                x_start: last_run.x_end + 1,
-                x_end: self.width as i32 - 1,
-                y: last_run.y,
-            });
-        }
-        // in the end if last_run is not really last row
-        // all rows after that are zeroes which are flipped to ones
-        runs.extend(
-            (last_run.y + 1..self.height as i32).map(|y| Run { x_start: 0, x_end: self.width as i32 - 1, y: y as _ })
-        );
-        RLE {
-            width: self.width,
-            height: self.height,
-            runs
         }
+        last_run = run;
+    }
+    if width as i32 - 1 > last_run.x_end {
+        runs.push(Run {
+            x_start: last_run.x_end + 1,
+            x_end: width as i32 - 1,
+            y: last_run.y,
+        });
+    }
+    // in the end if last_run is not really last row
+    // all rows after that are zeroes which are flipped to ones
+    runs.extend(
+        (last_run.y + 1..y_end).map(|y| Run { x_start: 0, x_end: width as i32 - 1, y: y as _ })
+    );
+    runs
+}
+
+/// Number of runs of longer operand processed together by binary operations and
+/// `not`, see `row_chunked`.
+const OP_CHUNK_RUNS: usize = 1 << 14;
+
+/// Apply row local operation to parts of a and b with same rows. Rows are split to
+/// chunks by runs of longer operand (see `row_chunks`), chunks are processed in
+/// parallel with `rayon` feature and their results are concatenated in order.
+fn row_chunked<F>(a: &[Run], b: &[Run], op: F) -> Vec<Run>
+where
+    F: Fn(&[Run], &[Run]) -> Vec<Run> + Sync + Send,
+{
+    let chunks = row_chunks(if a.len() >= b.len() { a } else { b }, OP_CHUNK_RUNS);
+    if chunks.len() <= 1 {
+        return op(a, b);
+    }
+    // rows above first chunk belong to it and rows below last chunk to last one
+    let starts: Vec<i32> = chunks[1..].iter().map(|chunk| chunk[0].y).collect();
+    let pairs: Vec<(&[Run], &[Run])> = split_rows(a, &starts).into_iter().zip(split_rows(b, &starts)).collect();
+    parallel::map(&pairs, |&(a, b)| op(a, b)).concat()
+}
+
+/// Split sorted runs before first run of each of rows in starts.
+fn split_rows<'a>(runs: &'a [Run], starts: &[i32]) -> Vec<&'a [Run]> {
+    let mut parts = Vec::with_capacity(starts.len() + 1);
+    let mut rest = runs;
+    for &y in starts {
+        let (part, tail) = rest.split_at(rest.partition_point(|run| run.y < y));
+        parts.push(part);
+        rest = tail;
     }
+    parts.push(rest);
+    parts
+}
+
+/// Union of sorted runs.
+fn or_runs(a: &[Run], b: &[Run]) -> Vec<Run> {
+    let mut runs = Vec::with_capacity(a.len() + b.len());
+    runs.extend_from_slice(a);
+    runs.extend_from_slice(b);
+    Run::merge_overlapping_runs_mut(&mut runs);
+    runs
 }
 
 /// Binary or on image pixels, mutable version.
-impl<'a, 'b> BitOr<&'a RLE> for &'b RLE {
+impl<'a> BitOr<&'a RLE> for &RLE {
     type Output = RLE;
     /// If dimensions of self and rhs are not same this method will panic.
     fn bitor(self, rhs: &'a RLE) -> Self::Output {
         assert!(self.width == rhs.width && self.height == rhs.height);
-        let mut runs = Vec::with_capacity(self.runs.len() + rhs.runs.len());
-        runs.extend(&self.runs);
-        runs.extend(&rhs.runs);
-        RLE {
-            runs,
-            width: self.width(),
-            height: self.height(),
-        }.merge_overlapping_runs()
+        trace::instrument("or", self.runs.len() + rhs.runs.len(), || {
+            RLE {
+                runs: row_chunked(&self.runs, &rhs.runs, or_runs),
+                width: self.width(),
+                height: self.height(),
+            }
+        })
     }
 }
 
@@ -150,7 +215,7 @@ impl BitOrAssign for RLE {
 }
 
 /// Get all 1s in self which are not in other, same as set difference.
-impl<'a, 'b> Sub<&'a RLE> for &'b RLE {
+impl<'a> Sub<&'a RLE> for &RLE {
     type Output = RLE;
     /// If dimensions of self and rhs are not same this method will panic.
     fn sub(self, rhs: &'a RLE) -> Self::Output {
@@ -167,46 +232,299 @@ impl BitAnd for RLE {
 }
 
 /// Binary and on image pixels.
-impl<'a, 'b> BitAnd<&'a RLE> for &'b RLE {
+impl<'a> BitAnd<&'a RLE> for &RLE {
     type Output = RLE;
     /// If dimensions of self and rhs are not same this method will panic.
     fn bitand(self, rhs: &'a RLE) -> Self::Output {
         assert!(self.width == rhs.width && self.height == rhs.height);
-        if self.runs.is_empty() || rhs.runs.is_empty() {
-            return RLE {
-                runs: Vec::new(),
+        debug_assert!(Run::is_normalized(&self.runs) && Run::is_normalized(&rhs.runs));
+        trace::instrument("and", self.runs.len() + rhs.runs.len(), || {
+            if self.runs.is_empty() || rhs.runs.is_empty() {
+                return RLE {
+                    runs: Vec::new(),
+                    width: self.width,
+                    height: self.height,
+                };
+            }
+            RLE {
+                runs: row_chunked(&self.runs, &rhs.runs, and_runs),
                 width: self.width,
                 height: self.height,
-            };
-        }
-        let mut runs = Vec::with_capacity(self.runs.len());
-        let mut i = 0;
-        let mut j = 0;
-        while i < self.runs.len() && j < rhs.runs.len() {
-            if self.runs[i].y < rhs.runs[j].y {
-                i += 1;
-                continue;
             }
-            else if self.runs[i].y > rhs.runs[j].y {
+        })
+    }
+}
+
+/// Intersection of sorted runs.
+fn and_runs(a: &[Run], b: &[Run]) -> Vec<Run> {
+    let mut runs = Vec::with_capacity(a.len());
+    let mut i = 0;
+    let mut j = 0;
+    while i < a.len() && j < b.len() {
+        if a[i].y < b[j].y {
+            i += 1;
+            continue;
+        }
+        else if a[i].y > b[j].y {
+            j += 1;
+            continue;
+        }
+        else if a[i].intersects(b[j]) {
+            runs.push(a[i].intersect(b[j]).unwrap());
+        }
+        if a[i].x_end < b[j].x_end  {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    Run::merge_overlapping_runs_mut(&mut runs);
+    runs
+}
+
+/// Binary and on image pixels, mutable version.
+/// Result is written to run buffer of self.
+impl<'a> BitAndAssign<&'a RLE> for RLE {
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn bitand_assign(&mut self, rhs: &'a RLE) {
+        apply_in_place(self, rhs, and_in_place);
+    }
+}
+
+/// Binary and on image pixels, mutable version.
+impl BitAndAssign for RLE {
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn bitand_assign(&mut self, rhs: RLE) {
+        apply_in_place(self, &rhs, and_in_place);
+    }
+}
+
+/// Set difference, mutable version.
+/// Result is written to run buffer of self.
+impl<'a> SubAssign<&'a RLE> for RLE {
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn sub_assign(&mut self, rhs: &'a RLE) {
+        apply_in_place(self, rhs, sub_in_place);
+    }
+}
+
+/// Set difference, mutable version.
+impl SubAssign for RLE {
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn sub_assign(&mut self, rhs: RLE) {
+        apply_in_place(self, &rhs, sub_in_place);
+    }
+}
+
+/// Operation on canonical runs done in place. Runs of left operand are stored in
+/// buffer starting at given offset (which is at least number of runs of right operand),
+/// result is written from start of buffer and its length is returned.
+/// Every written run is followed by advancing to next run of left or right operand,
+/// so write position never passes read position.
+type InPlaceOp = fn(&mut [Run], usize, &[Run]) -> usize;
+
+/// Intersection of runs in place, see `InPlaceOp`.
+fn and_in_place(buf: &mut [Run], offset: usize, b: &[Run]) -> usize {
+    let (mut i, mut j, mut k) = (offset, 0, 0);
+    while i < buf.len() && j < b.len() {
+        let (a, r) = (buf[i], b[j]);
+        if a.y == r.y && a.x_start <= r.x_end && r.x_start <= a.x_end {
+            buf[k] = Run::new(a.x_start.max(r.x_start), a.x_end.min(r.x_end), a.y);
+            k += 1;
+        }
+        if (a.y, a.x_end) < (r.y, r.x_end) {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    k
+}
+
+/// Difference of runs in place, see `InPlaceOp`.
+fn sub_in_place(buf: &mut [Run], offset: usize, b: &[Run]) -> usize {
+    let (mut i, mut j, mut k) = (offset, 0, 0);
+    while i < buf.len() {
+        // part of current run which is not yet processed
+        let mut a = buf[i];
+        i += 1;
+        loop {
+            while j < b.len() && (b[j].y, b[j].x_end) < (a.y, a.x_start) {
                 j += 1;
-                continue;
             }
-            else if self.runs[i].intersects(rhs.runs[j]) {
-                runs.push(self.runs[i].intersect(rhs.runs[j]).unwrap());
+            if j == b.len() || b[j].y != a.y || b[j].x_start > a.x_end {
+                buf[k] = a;
+                k += 1;
+                break;
+            }
+            if b[j].x_start > a.x_start {
+                buf[k] = a.with_x_end(b[j].x_start - 1);
+                k += 1;
             }
-            if self.runs[i].x_end < rhs.runs[j].x_end  {
+            if b[j].x_end >= a.x_end {
+                break;
+            }
+            a = a.with_x_start(b[j].x_end + 1);
+            j += 1;
+        }
+    }
+    k
+}
+
+/// Symmetric difference of runs in place, see `InPlaceOp`.
+/// Every run toggles pixels between its start and end + 1, so boundaries of both
+/// operands are merged in (y, x) order, same boundary in both operands cancels out
+/// and remaining boundaries form result runs pairwise.
+fn xor_in_place(buf: &mut [Run], offset: usize, b: &[Run]) -> usize {
+    let boundary = |run: Run, end: bool| if end { (run.y, run.x_end + 1) } else { (run.y, run.x_start) };
+    // current run and whether its start was already used, for both operands
+    let (mut i, mut j, mut k) = (offset, 0, 0);
+    let mut a_cur = (buf.get(i).copied(), false);
+    let mut b_cur = (b.first().copied(), false);
+    let mut open: Option<(i32, i32)> = None;
+    loop {
+        let next_a = a_cur.0.map(|run| boundary(run, a_cur.1));
+        let next_b = b_cur.0.map(|run| boundary(run, b_cur.1));
+        let (take_a, take_b) = match (next_a, next_b) {
+            (None, None) => break,
+            (Some(_), None) => (true, false),
+            (None, Some(_)) => (false, true),
+            (Some(x), Some(y)) => (x <= y, y <= x),
+        };
+        let next = if take_a { next_a } else { next_b }.unwrap();
+        if take_a {
+            if a_cur.1 {
                 i += 1;
+                a_cur = (buf.get(i).copied(), false);
             } else {
+                a_cur.1 = true;
+            }
+        }
+        if take_b {
+            if b_cur.1 {
                 j += 1;
+                b_cur = (b.get(j).copied(), false);
+            } else {
+                b_cur.1 = true;
             }
         }
-        RLE {
-            runs,
-            width: self.width,
-            height: self.height,
-        }.merge_overlapping_runs()
+        // boundary in both operands cancels out
+        if take_a && take_b {
+            continue;
+        }
+        match open.take() {
+            None => open = Some(next),
+            Some((y, x_start)) => {
+                buf[k] = Run::new(x_start, next.1 - 1, y);
+                k += 1;
+            }
+        }
+    }
+    k
+}
+
+/// Run op with runs of a as left and runs of b as right operand in buffer of a,
+/// canonical runs of a are moved to end of buffer before running.
+fn apply_in_place(a: &mut RLE, b: &RLE, op: InPlaceOp) {
+    assert!(a.width == b.width && a.height == b.height);
+    if !a.is_canonical() {
+        a.runs = a.canonical_runs().into_owned();
+    }
+    let b_runs = b.canonical_runs();
+    let a_len = a.runs.len();
+    a.runs.resize(a_len + b_runs.len(), Run::default());
+    a.runs.copy_within(0..a_len, b_runs.len());
+    let len = op(&mut a.runs, b_runs.len(), &b_runs);
+    a.runs.truncate(len);
+    debug_assert!(a.is_canonical());
+}
+
+/// Run op on canonical runs of a and b into new buffer.
+fn apply(a: &RLE, b: &RLE, op: InPlaceOp) -> RLE {
+    assert!(a.width == b.width && a.height == b.height);
+    let (a_runs, b_runs) = (a.canonical_runs(), b.canonical_runs());
+    let mut runs = vec![Run::default(); b_runs.len()];
+    runs.extend_from_slice(&a_runs);
+    let len = op(&mut runs, b_runs.len(), &b_runs);
+    runs.truncate(len);
+    let res = RLE { runs, width: a.width, height: a.height };
+    debug_assert!(res.is_canonical());
+    res
+}
+
+/// Binary xor on image pixels (symmetric difference), pixels which are 1 in exactly one image.
+impl<'a> BitXor<&'a RLE> for &RLE {
+    type Output = RLE;
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn bitxor(self, rhs: &'a RLE) -> Self::Output {
+        assert!(self.width == rhs.width && self.height == rhs.height);
+        trace::instrument("xor", self.runs.len() + rhs.runs.len(), || apply(self, rhs, xor_in_place))
+    }
+}
+
+/// Binary xor on image pixels (symmetric difference).
+impl BitXor for RLE {
+    type Output = RLE;
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn bitxor(self, rhs: RLE) -> Self::Output {
+        &self ^ &rhs
+    }
+}
+
+/// Binary xor on image pixels, mutable version.
+impl<'a> BitXorAssign<&'a RLE> for RLE {
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn bitxor_assign(&mut self, rhs: &'a RLE) {
+        apply_in_place(self, rhs, xor_in_place);
+    }
+}
+
+/// Binary xor on image pixels, mutable version.
+impl BitXorAssign for RLE {
+    /// If dimensions of self and rhs are not same this method will panic.
+    fn bitxor_assign(&mut self, rhs: RLE) {
+        apply_in_place(self, &rhs, xor_in_place);
     }
 }
+
+impl RLE {
+    /// Check that other has same dimensions as self, binary operations need this.
+    pub fn check_same_dimensions(&self, other: &RLE) -> Result<(), RleError> {
+        if self.width == other.width && self.height == other.height {
+            Ok(())
+        } else {
+            Err(RleError::DimensionMismatch {
+                left: (self.width, self.height),
+                right: (other.width, other.height),
+            })
+        }
+    }
+
+    /// Same as `self & rhs` but returns `RleError::DimensionMismatch` instead of panicking.
+    pub fn try_bitand(&self, rhs: &RLE) -> Result<RLE, RleError> {
+        self.check_same_dimensions(rhs)?;
+        Ok(self & rhs)
+    }
+
+    /// Same as `self | rhs` but returns `RleError::DimensionMismatch` instead of panicking.
+    pub fn try_bitor(&self, rhs: &RLE) -> Result<RLE, RleError> {
+        self.check_same_dimensions(rhs)?;
+        Ok(self | rhs)
+    }
+
+    /// Same as `self ^ rhs` but returns `RleError::DimensionMismatch` instead of panicking.
+    pub fn try_bitxor(&self, rhs: &RLE) -> Result<RLE, RleError> {
+        self.check_same_dimensions(rhs)?;
+        Ok(self ^ rhs)
+    }
+
+    /// Same as `self - rhs` but returns `RleError::DimensionMismatch` instead of panicking.
+    pub fn try_sub(&self, rhs: &RLE) -> Result<RLE, RleError> {
+        self.check_same_dimensions(rhs)?;
+        Ok(self - rhs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -346,4 +664,138 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn not_outside_runs_test() {
+        // runs outside of image (for example after dilation) are ignored
+        let rle = RLE::from_runs(4, 2, vec![Run::new(-2, 1, 0), Run::new(3, 6, 1), Run::new(0, 1, 5)]);
+        let mut clipped = rle.clone();
+        clipped.clip_to_bounds();
+        assert_eq!(!&rle, !&clipped);
+        assert_eq!((!&rle).runs(), &[Run::new(2, 3, 0), Run::new(0, 2, 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "coordinate overflow")]
+    fn not_overflow_test() {
+        // complement would be run from 0 to i32::MAX + 1
+        let _ = !&RLE::new(i32::MAX as usize + 2, 1);
+    }
+
+    #[test]
+    fn xor_test() {
+        let a = Image::new(6, 6, vec![
+            1, 0, 0, 0, 0, 1,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 1, 1, 1, 0,
+            0, 0, 1, 0, 0, 0,
+            0, 0, 1, 1, 1, 1,
+            1, 1, 1, 0, 0, 0,
+        ]);
+        let b = Image::new(6, 6, vec![
+            0, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 0, 0,
+            0, 0, 1, 1, 1, 0,
+            0, 0, 1, 0, 0, 0,
+            0, 0, 1, 1, 0, 1,
+            0, 0, 0, 1, 1, 1,
+        ]);
+        let rle = &RLE::from(&a) ^ &RLE::from(&b);
+        assert_eq!(
+            rle.to_image(1),
+            Image::new(6, 6,vec![
+            1, 1, 1, 1, 0, 1,
+            0, 0, 0, 1, 0, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 0,
+            1, 1, 1, 1, 1, 1,
+            ])
+        );
+        assert_eq!(
+            rle,
+            RLE {
+                width: 6,
+                height: 6,
+                runs: vec![
+                    Run { x_start: 0, x_end: 3, y: 0 },
+                    Run { x_start: 5, x_end: 5, y: 0 },
+                    Run { x_start: 3, x_end: 3, y: 1 },
+                    Run { x_start: 4, x_end: 4, y: 4 },
+                    Run { x_start: 0, x_end: 5, y: 5 },
+                ]
+            }
+        );
+        assert!(rle.is_canonical());
+        let (a, b) = (RLE::from(&a), RLE::from(&b));
+        assert_eq!(rle, &(&a - &b) | &(&b - &a));
+        assert_eq!(&a ^ &a, RLE::new(6, 6));
+        let mut c = a.clone();
+        c ^= &b;
+        assert_eq!(c, rle);
+        c ^= b.clone();
+        assert_eq!(c, a);
+        assert_eq!(a ^ b, rle);
+    }
+
+    #[test]
+    fn assign_ops_test() {
+        let a = RLE::from(&Image::new(7, 4, vec![
+            1, 1, 1, 1, 1, 1, 1,
+            0, 1, 1, 0, 1, 1, 0,
+            1, 0, 0, 0, 0, 0, 1,
+            0, 0, 1, 1, 1, 0, 0,
+        ]));
+        let b = RLE::from(&Image::new(7, 4, vec![
+            0, 1, 0, 1, 0, 1, 0,
+            1, 1, 1, 1, 1, 1, 1,
+            0, 0, 0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 1,
+        ]));
+        let mut c = a.clone();
+        c &= &b;
+        assert_eq!(c, &a & &b);
+        assert!(c.is_canonical());
+        let mut c = a.clone();
+        c -= &b;
+        assert_eq!(c.to_image(1), Image::new(7, 4, vec![
+            1, 0, 1, 0, 1, 0, 1,
+            0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 1,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(c, &a - &b);
+        assert!(c.is_canonical());
+        let mut c = b.clone();
+        c -= a.clone();
+        assert_eq!(c, &b - &a);
+        let mut c = b.clone();
+        c &= a.clone();
+        assert_eq!(c, &a & &b);
+        let mut c = a.clone();
+        c ^= &b;
+        assert_eq!(c, &(&a - &b) | &(&b - &a));
+        assert!(c.is_canonical());
+        // runs outside of image and overlapping runs are ignored or merged
+        let mut c = RLE::from_runs(7, 4, vec![Run::new(-3, 2, 0), Run::new(1, 9, 0), Run::new(0, 3, 5)]);
+        c -= &b;
+        assert_eq!(c, &RLE::from_runs(7, 4, vec![Run::new(0, 6, 0)]) - &b);
+    }
+
+    #[test]
+    fn try_ops_test() {
+        let a = RLE::from_raw_data(3, 2, &[1, 1, 0, 0, 1, 1]);
+        let b = RLE::from_raw_data(3, 2, &[0, 1, 1, 0, 1, 0]);
+        assert_eq!(a.try_bitand(&b), Ok(&a & &b));
+        assert_eq!(a.try_bitor(&b), Ok(&a | &b));
+        assert_eq!(a.try_bitxor(&b), Ok(&a ^ &b));
+        assert_eq!(a.try_sub(&b), Ok(&a - &b));
+        let c = RLE::new(2, 3);
+        let err = RleError::DimensionMismatch { left: (3, 2), right: (2, 3) };
+        assert_eq!(a.try_bitand(&c), Err(err));
+        assert_eq!(a.try_bitor(&c), Err(err));
+        assert_eq!(a.try_bitxor(&c), Err(err));
+        assert_eq!(a.try_sub(&c), Err(err));
+        assert_eq!(err.to_string(), "dimensions do not match: 3x2 and 2x3");
+    }
 }
@@ -0,0 +1,237 @@
+//! Applying masks to images, statistics of pixels under masks and drawing masks over
+//! images for visualization. Images are processed run by run, pixels outside of mask
+//! are never looked at one by one.
+use super::{Image, RLE};
+#[cfg(feature = "image-interop")]
+use ::image::{Rgb, RgbImage};
+
+/// Value of pixels set in neither mask in `RLE::diff_image`.
+pub const DIFF_TRUE_NEGATIVE: u8 = 0;
+/// Value of pixels set in both masks in `RLE::diff_image`.
+pub const DIFF_TRUE_POSITIVE: u8 = 1;
+/// Value of pixels set only in predicted mask in `RLE::diff_image`.
+pub const DIFF_FALSE_POSITIVE: u8 = 2;
+/// Value of pixels set only in reference mask in `RLE::diff_image`.
+pub const DIFF_FALSE_NEGATIVE: u8 = 3;
+
+/// Keep pixels of interleaved image with given number of channels under mask and
+/// set other pixels to background.
+fn apply_channels(src: &[u8], channels: usize, mask: &RLE, background: &[u8]) -> Vec<u8> {
+    let mut res: Vec<u8> = background.iter().copied().cycle().take(src.len()).collect();
+    let row_len = mask.width() * channels;
+    for run in mask.canonical_runs().iter() {
+        let start = run.y as usize * row_len + run.x_start as usize * channels;
+        let end = run.y as usize * row_len + (run.x_end as usize + 1) * channels;
+        res[start..end].copy_from_slice(&src[start..end]);
+    }
+    res
+}
+
+/// Blend pixels of interleaved image under mask with color, alpha 1 replaces them.
+fn blend_channels(data: &mut [u8], channels: usize, mask: &RLE, color: &[u8], alpha: f32) {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let row_len = mask.width() * channels;
+    for run in mask.canonical_runs().iter() {
+        let start = run.y as usize * row_len + run.x_start as usize * channels;
+        let end = run.y as usize * row_len + (run.x_end as usize + 1) * channels;
+        for pixel in data[start..end].chunks_mut(channels) {
+            for (value, &c) in pixel.iter_mut().zip(color) {
+                *value = (*value as f32 * (1.0 - alpha) + c as f32 * alpha).round() as u8;
+            }
+        }
+    }
+}
+
+impl RLE {
+    /// Call f with row slice of src under every run, in row-major order.
+    fn for_each_slice<F: FnMut(&[u8])>(&self, src: &Image, mut f: F) {
+        assert!(src.w() == self.width && src.h() == self.height);
+        for run in self.canonical_runs().iter() {
+            let start = run.y as usize * self.width;
+            f(&src.data()[start + run.x_start as usize..=start + run.x_end as usize]);
+        }
+    }
+
+    /// Get values of src pixels set in this mask, in row-major order.
+    /// If dimensions of src and mask are not same this function will panic.
+    pub fn extract_values(&self, src: &Image) -> Vec<u8> {
+        let mut values = Vec::with_capacity(self.area());
+        self.for_each_slice(src, |slice| values.extend_from_slice(slice));
+        values
+    }
+
+    /// Get mean value of src pixels under mask, None for empty mask.
+    /// If dimensions of src and mask are not same this function will panic.
+    pub fn mean_under_mask(&self, src: &Image) -> Option<f64> {
+        let (mut sum, mut count) = (0u64, 0usize);
+        self.for_each_slice(src, |slice| {
+            sum += slice.iter().map(|&v| v as u64).sum::<u64>();
+            count += slice.len();
+        });
+        if count == 0 {
+            None
+        } else {
+            Some(sum as f64 / count as f64)
+        }
+    }
+
+    /// Get minimal value of src pixels under mask, None for empty mask.
+    /// If dimensions of src and mask are not same this function will panic.
+    pub fn min_under_mask(&self, src: &Image) -> Option<u8> {
+        let mut min = None;
+        self.for_each_slice(src, |slice| min = min.into_iter().chain(slice.iter().copied()).min());
+        min
+    }
+
+    /// Get maximal value of src pixels under mask, None for empty mask.
+    /// If dimensions of src and mask are not same this function will panic.
+    pub fn max_under_mask(&self, src: &Image) -> Option<u8> {
+        let mut max = None;
+        self.for_each_slice(src, |slice| max = max.into_iter().chain(slice.iter().copied()).max());
+        max
+    }
+
+    /// Compare this (predicted) mask with reference mask, pixels of result are
+    /// `DIFF_TRUE_NEGATIVE`, `DIFF_TRUE_POSITIVE`, `DIFF_FALSE_POSITIVE` or
+    /// `DIFF_FALSE_NEGATIVE`. Masks are compared with run operations and only
+    /// differing parts are written to image.
+    /// If dimensions of masks are not same this function will panic.
+    pub fn diff_image(&self, other: &RLE) -> Image {
+        let mut data = vec![DIFF_TRUE_NEGATIVE; self.width * self.height];
+        (self & other).render_into(&mut data, self.width, 0, 0, DIFF_TRUE_POSITIVE);
+        (self - other).render_into(&mut data, self.width, 0, 0, DIFF_FALSE_POSITIVE);
+        (other - self).render_into(&mut data, self.width, 0, 0, DIFF_FALSE_NEGATIVE);
+        Image::new(self.width, self.height, data)
+    }
+
+    /// Same as `diff_image` with true positives green, false positives red, false
+    /// negatives blue and true negatives black, enabled with `image-interop` feature.
+    /// If dimensions of masks are not same this function will panic.
+    #[cfg(feature = "image-interop")]
+    pub fn diff_image_rgb(&self, other: &RLE) -> RgbImage {
+        const COLORS: [[u8; 3]; 4] = [[0, 0, 0], [0, 255, 0], [255, 0, 0], [0, 0, 255]];
+        let diff = self.diff_image(other);
+        let data = diff.data().iter().flat_map(|&v| COLORS[v as usize].iter().copied()).collect();
+        RgbImage::from_raw(self.width as u32, self.height as u32, data).unwrap()
+    }
+
+    /// Count src pixels under mask for every value.
+    /// If dimensions of src and mask are not same this function will panic.
+    pub fn histogram_under_mask(&self, src: &Image) -> [usize; 256] {
+        let mut histogram = [0; 256];
+        self.for_each_slice(src, |slice| {
+            for &v in slice {
+                histogram[v as usize] += 1;
+            }
+        });
+        histogram
+    }
+}
+
+/// Copy of src with pixels outside of mask set to background.
+/// If dimensions of src and mask are not same this function will panic.
+pub fn mask_apply(src: &Image, mask: &RLE, background: u8) -> Image {
+    assert!(src.w() == mask.width() && src.h() == mask.height());
+    Image::new(src.w(), src.h(), apply_channels(src.data(), 1, mask, &[background]))
+}
+
+/// Copy of src with pixels under mask blended with value, new pixel is
+/// `(1 - alpha) * pixel + alpha * value`.
+/// If dimensions of src and mask are not same this function will panic.
+pub fn overlay(src: &Image, mask: &RLE, value: u8, alpha: f32) -> Image {
+    assert!(src.w() == mask.width() && src.h() == mask.height());
+    let mut data = src.data().to_vec();
+    blend_channels(&mut data, 1, mask, &[value], alpha);
+    Image::new(src.w(), src.h(), data)
+}
+
+/// Same as `mask_apply` for RGB images, enabled with `image-interop` feature.
+/// If dimensions of src and mask are not same this function will panic.
+#[cfg(feature = "image-interop")]
+pub fn mask_apply_rgb(src: &RgbImage, mask: &RLE, background: Rgb<u8>) -> RgbImage {
+    assert!(src.width() as usize == mask.width() && src.height() as usize == mask.height());
+    let data = apply_channels(src.as_raw(), 3, mask, &background.0);
+    RgbImage::from_raw(src.width(), src.height(), data).unwrap()
+}
+
+/// Same as `overlay` for RGB images, enabled with `image-interop` feature.
+/// If dimensions of src and mask are not same this function will panic.
+#[cfg(feature = "image-interop")]
+pub fn overlay_rgb(src: &RgbImage, mask: &RLE, color: Rgb<u8>, alpha: f32) -> RgbImage {
+    assert!(src.width() as usize == mask.width() && src.height() as usize == mask.height());
+    let mut res = src.clone();
+    blend_channels(&mut res, 3, mask, &color.0, alpha);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Run;
+
+    #[test]
+    fn mask_apply_test() {
+        let src = Image::new(4, 2, vec![
+            10, 20, 30, 40,
+            50, 60, 70, 80,
+        ]);
+        // runs outside of image are ignored
+        let mask = RLE::from_runs(4, 2, vec![Run::new(1, 2, 0), Run::new(-2, 0, 1), Run::new(0, 3, 2)]);
+        assert_eq!(mask_apply(&src, &mask, 0), Image::new(4, 2, vec![
+            0, 20, 30, 0,
+            50, 0, 0, 0,
+        ]));
+        assert_eq!(overlay(&src, &mask, 255, 0.5), Image::new(4, 2, vec![
+            10, 138, 143, 40,
+            153, 60, 70, 80,
+        ]));
+        assert_eq!(overlay(&src, &mask, 1, 2.0), mask_apply(&src, &!&mask, 1));
+    }
+
+    #[test]
+    fn stats_test() {
+        let src = Image::new(4, 3, vec![
+            10, 20, 30, 40,
+            50, 60, 70, 80,
+            90, 20, 20, 0,
+        ]);
+        let mask = RLE::from_runs(4, 3, vec![Run::new(1, 2, 0), Run::new(3, 5, 1), Run::new(0, 2, 2)]);
+        assert_eq!(mask.extract_values(&src), vec![20, 30, 80, 90, 20, 20]);
+        assert_eq!(mask.mean_under_mask(&src), Some(43.333333333333336));
+        assert_eq!(mask.min_under_mask(&src), Some(20));
+        assert_eq!(mask.max_under_mask(&src), Some(90));
+        let histogram = mask.histogram_under_mask(&src);
+        assert_eq!((histogram[20], histogram[30], histogram[0]), (3, 1, 0));
+        assert_eq!(histogram.iter().sum::<usize>(), mask.area());
+        let empty = RLE::new(4, 3);
+        assert_eq!(empty.extract_values(&src), Vec::<u8>::new());
+        assert_eq!((empty.mean_under_mask(&src), empty.max_under_mask(&src)), (None, None));
+    }
+
+    #[test]
+    fn diff_image_test() {
+        let predicted = RLE::from(&Image::new(3, 2, vec![
+            1, 1, 0,
+            0, 1, 0,
+        ]));
+        let reference = RLE::from(&Image::new(3, 2, vec![
+            0, 1, 1,
+            0, 1, 0,
+        ]));
+        assert_eq!(predicted.diff_image(&reference), Image::new(3, 2, vec![
+            DIFF_FALSE_POSITIVE, DIFF_TRUE_POSITIVE, DIFF_FALSE_NEGATIVE,
+            DIFF_TRUE_NEGATIVE, DIFF_TRUE_POSITIVE, DIFF_TRUE_NEGATIVE,
+        ]));
+    }
+
+    #[cfg(feature = "image-interop")]
+    #[test]
+    fn rgb_test() {
+        let src = RgbImage::from_raw(2, 1, vec![10, 20, 30, 40, 50, 60]).unwrap();
+        let mask = RLE::from_runs(2, 1, vec![Run::new(1, 1, 0)]);
+        assert_eq!(mask_apply_rgb(&src, &mask, Rgb([1, 2, 3])).into_raw(), vec![1, 2, 3, 40, 50, 60]);
+        assert_eq!(overlay_rgb(&src, &mask, Rgb([240, 0, 0]), 0.5).into_raw(), vec![10, 20, 30, 140, 25, 30]);
+        let other = RLE::from_runs(2, 1, vec![Run::new(0, 0, 0)]);
+        assert_eq!(mask.diff_image_rgb(&other).into_raw(), vec![0, 0, 255, 255, 0, 0]);
+    }
+}
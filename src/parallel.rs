@@ -0,0 +1,117 @@
+//! Running independent work items in parallel when `rayon` feature is enabled.
+//!
+//! Determinism contract: every parallel path in this crate splits work into items
+//! which are computed by same code as serial path and collects results in input
+//! order, so output (including order of runs) is bit-identical to serial
+//! implementation no matter how many threads are used. New parallel paths must use
+//! these helpers and add a test to `determinism` tests below.
+
+/// Apply f to all items and collect results in order of items,
+/// in parallel with `rayon` feature.
+#[inline]
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        map_serial(items, f)
+    }
+}
+
+/// Serial version of `map`.
+#[cfg_attr(feature = "rayon", allow(dead_code))]
+#[inline]
+pub(crate) fn map_serial<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    F: Fn(&T) -> R,
+{
+    items.iter().map(f).collect()
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod determinism {
+    use super::*;
+    use crate::{metrics, test_util, Connectivity, RLE};
+
+    fn noise(w: usize, h: usize, seed: u32) -> RLE {
+        RLE::from(&test_util::noise(w, h, seed | 1, |state| state & 3 == 0))
+    }
+
+    /// Run f with thread pools of different sizes and check that all results are
+    /// same as serial result.
+    fn check<R: PartialEq + std::fmt::Debug + Send, F: Fn() -> R + Sync + Send>(serial: R, f: F) {
+        for &threads in &[1, 2, 3, 8] {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            for _ in 0..4 {
+                assert_eq!(pool.install(&f), serial, "threads: {}", threads);
+            }
+        }
+    }
+
+    #[test]
+    fn map_test() {
+        let items: Vec<u64> = (0..1000).collect();
+        let serial = map_serial(&items, |i| i * i);
+        check(serial, || map(&items, |i| i * i));
+    }
+
+    #[test]
+    fn iou_matrix_test() {
+        let a: Vec<RLE> = (0..17).map(|i| noise(31, 19, i)).collect();
+        let b: Vec<RLE> = (0..9).map(|i| noise(31, 19, 100 + i)).collect();
+        let serial: Vec<Vec<u64>> = a.iter()
+            .map(|ma| b.iter().map(|mb| ma.change_stats(mb).iou.to_bits()).collect())
+            .collect();
+        let bits = |m: Vec<Vec<f64>>| -> Vec<Vec<u64>> {
+            m.into_iter().map(|row| row.into_iter().map(f64::to_bits).collect()).collect()
+        };
+        check(serial, || bits(metrics::iou_matrix(&a, &b)));
+    }
+
+    #[test]
+    fn dilate_erode_test() {
+        // enough runs for several chunks of rows
+        let rle = noise(211, 97, 7);
+        let s = RLE::disk_structuring(3);
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let serial = single.install(|| (rle.dilate(&s).runs().to_vec(), rle.erode(&s).runs().to_vec()));
+        check(serial, || (rle.dilate(&s).runs().to_vec(), rle.erode(&s).runs().to_vec()));
+    }
+
+    #[test]
+    fn boolean_ops_test() {
+        // enough runs for several chunks of rows
+        let (a, b) = (noise(600, 400, 11), noise(600, 400, 12));
+        assert!(a.runs().len() > 2 * (1 << 14));
+        // in place operations and FlipBitsIter do not split rows into chunks
+        let mut and = a.clone();
+        and &= &b;
+        let mut or = a.clone();
+        or |= &b;
+        let not: Vec<_> = crate::FlipBitsIter::from_runs(a.runs(), 600, 400).collect();
+        let serial = (and.runs().to_vec(), or.runs().to_vec(), not);
+        check(serial, || ((&a & &b).runs().to_vec(), (&a | &b).runs().to_vec(), (!&a).runs().to_vec()));
+    }
+
+    #[test]
+    fn components_test() {
+        let rle = noise(600, 400, 13);
+        for &connectivity in &[Connectivity::Four, Connectivity::Eight] {
+            // rows of transposed image are split into chunks at other places
+            let count = rle.transpose().count_objects(connectivity);
+            let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+            let serial = single.install(|| rle.label_components(connectivity));
+            assert_eq!(serial.labels().len(), count);
+            check(serial, || rle.label_components(connectivity));
+            check(count, || rle.count_objects(connectivity));
+        }
+    }
+}
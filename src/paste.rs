@@ -0,0 +1,102 @@
+use super::{draw, Run, RLE};
+
+/// How pixels of pasted image are combined with pixels of destination,
+/// see `RLE::paste`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteMode {
+    /// destination | source
+    Or,
+    /// destination & source inside of pasted rectangle
+    And,
+    /// destination ^ source
+    Xor,
+    /// source inside of pasted rectangle
+    Replace,
+}
+
+impl RLE {
+    /// Paste other with its top left corner at (dx, dy) of self, pixels are combined
+    /// as given by mode. Only rectangle covered by other can change and parts of
+    /// other outside of self are clipped.
+    pub fn paste(&mut self, other: &RLE, dx: i32, dy: i32, mode: PasteMode) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        // shifted runs are computed in i64 so that they can not overflow before clipping
+        let runs = other.canonical_runs().iter()
+            .map(|run| (run.x_start as i64 + dx as i64, run.x_end as i64 + dx as i64, run.y as i64 + dy as i64))
+            .filter(|&(x_start, x_end, y)| y >= 0 && y < height && x_end >= 0 && x_start < width)
+            .map(|(x_start, x_end, y)| Run::new(x_start.max(0) as i32, x_end.min(width - 1) as i32, y as i32))
+            .collect();
+        // shifting and clipping keeps canonical runs canonical
+        let source = RLE { width: self.width, height: self.height, runs };
+        // pasted rectangle clipped to self
+        let (x_start, y_start) = ((dx as i64).max(0), (dy as i64).max(0));
+        let x_end = (dx as i64 + other.width as i64).min(width);
+        let y_end = (dy as i64 + other.height as i64).min(height);
+        let clear_rect = |rle: &mut RLE| if x_start < x_end && y_start < y_end {
+            draw::clear_rect(rle, x_start as i32, y_start as i32, (x_end - x_start) as usize, (y_end - y_start) as usize);
+        };
+        match mode {
+            PasteMode::Or => *self |= &source,
+            PasteMode::Xor => *self ^= &source,
+            PasteMode::And => {
+                let inside = &*self & &source;
+                clear_rect(self);
+                *self |= &inside;
+            }
+            PasteMode::Replace => {
+                clear_rect(self);
+                *self |= &source;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    fn dense_paste(dst: &Image, src: &Image, dx: i32, dy: i32, mode: PasteMode) -> Image {
+        let mut out = dst.clone();
+        for y in 0..src.h() as i64 {
+            for x in 0..src.w() as i64 {
+                let (px, py) = (x + dx as i64, y + dy as i64);
+                if px < 0 || py < 0 || px >= dst.w() as i64 || py >= dst.h() as i64 {
+                    continue;
+                }
+                let (a, b) = (dst[py as usize][px as usize] > 0, src[y as usize][x as usize] > 0);
+                out[py as usize][px as usize] = match mode {
+                    PasteMode::Or => a | b,
+                    PasteMode::And => a & b,
+                    PasteMode::Xor => a ^ b,
+                    PasteMode::Replace => b,
+                } as u8;
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn paste_test() {
+        let dst = Image::new(6, 5, vec![
+            1, 1, 0, 0, 1, 1,
+            0, 1, 1, 1, 0, 0,
+            1, 1, 1, 1, 1, 1,
+            0, 0, 0, 1, 0, 1,
+            1, 0, 1, 0, 1, 0,
+        ]);
+        let src = Image::new(3, 2, vec![
+            1, 0, 1,
+            0, 1, 1,
+        ]);
+        let (rle, tile) = (RLE::from(&dst), RLE::from(&src));
+        for &mode in &[PasteMode::Or, PasteMode::And, PasteMode::Xor, PasteMode::Replace] {
+            for &(dx, dy) in &[(0, 0), (2, 1), (-1, -1), (4, 4), (5, 0), (-3, 0), (6, 2), (i32::MAX, i32::MIN)] {
+                let mut pasted = rle.clone();
+                pasted.paste(&tile, dx, dy, mode);
+                assert!(pasted.is_canonical());
+                assert_eq!(pasted.to_image(1), dense_paste(&dst, &src, dx, dy, mode), "{:?} at ({}, {})", mode, dx, dy);
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use super::{Run, RLE};
+
+/// Run level difference between two versions of mask.
+/// Patch stores runs of pixels which are different in two versions so applying it
+/// flips those pixels. Because of that same patch converts older version to newer
+/// and newer version back to older (undo).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskPatch {
+    /// width of mask
+    width: usize,
+    /// height of mask
+    height: usize,
+    /// runs of pixels which are flipped
+    runs: Vec<Run>,
+}
+
+impl MaskPatch {
+    /// Create patch from runs of pixels which should be flipped.
+    /// Runs are clipped to image bounds and merged.
+    pub fn from_runs(width: usize, height: usize, runs: Vec<Run>) -> Self {
+        let runs = RLE::from_runs(width, height, runs).canonical_runs().into_owned();
+        Self { width, height, runs }
+    }
+
+    /// Get runs of pixels which are flipped by patch.
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    /// Check if patch does not change anything.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Get image width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get image height.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl RLE {
+    /// Create patch which converts self to newer (and newer back to self).
+    /// If dimensions of self and newer are not same this method will panic.
+    pub fn make_patch(&self, newer: &RLE) -> MaskPatch {
+        assert!(self.width == newer.width && self.height == newer.height);
+        // pixels which are only in one of versions, both parts are disjoint
+        let mut runs = (self - newer).runs;
+        runs.extend((newer - self).runs);
+        MaskPatch::from_runs(self.width, self.height, runs)
+    }
+
+    /// Apply patch to self, flipping all pixels of patch.
+    /// If dimensions of self and patch are not same this method will panic.
+    pub fn apply_patch(&self, patch: &MaskPatch) -> RLE {
+        assert!(self.width == patch.width && self.height == patch.height);
+        let flips = RLE::from_runs(patch.width, patch.height, patch.runs.clone());
+        let mut res = self - &flips;
+        res |= &flips - self;
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn patch_round_trip_test() {
+        let old = RLE::from(&Image::new(5, 3, vec![
+            1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 1, 1, 1, 0,
+        ]));
+        let new = RLE::from(&Image::new(5, 3, vec![
+            1, 1, 1, 0, 0,
+            0, 1, 0, 0, 0,
+            0, 0, 1, 1, 1,
+        ]));
+        let patch = old.make_patch(&new);
+        assert_eq!(patch.runs(), &[
+            Run::new(1, 1, 1),
+            Run::new(1, 1, 2),
+            Run::new(4, 4, 2),
+        ]);
+        assert_eq!(old.apply_patch(&patch), new);
+        // same patch is undo
+        assert_eq!(new.apply_patch(&patch), old);
+        assert!(old.make_patch(&old).is_empty());
+    }
+}
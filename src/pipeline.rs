@@ -0,0 +1,231 @@
+use super::{Run, RLE};
+
+/// One step of pipeline.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Dilate(RLE),
+    Erode(RLE),
+    Invert,
+    SubtractOriginal,
+    IntersectOriginal,
+    UnionOriginal,
+}
+
+/// Sequence of morphological operations which are run one after another.
+///
+/// Before running, steps are planned: consecutive dilations are fused into one
+/// dilation with combined structuring element and consecutive inversions cancel out.
+/// Erosions are not fused, each of them treats pixels outside of image as 1 so two
+/// erosions differ from one combined erosion near border.
+/// Result is same as calling operations one by one.
+///
+/// ```
+/// use rle_morph::{Pipeline, RLE};
+///
+/// let mut rle = RLE::new(8, 8);
+/// rle_morph::draw::filled_rect(&mut rle, 1, 1, 6, 6);
+/// // one pixel wide ring around mask
+/// let ring = Pipeline::new()
+///     .dilate(RLE::linf_structuring(1))
+///     .subtract_original()
+///     .run(&rle);
+/// assert_eq!(ring, &rle.dilate(&RLE::linf_structuring(1)) - &rle);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    /// Create pipeline without steps, running it returns copy of input.
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Dilate with structuring element s (see `RLE::dilate`).
+    pub fn dilate(mut self, s: RLE) -> Self {
+        self.steps.push(Step::Dilate(s));
+        self
+    }
+
+    /// Erode with structuring element s (see `RLE::erode`).
+    pub fn erode(mut self, s: RLE) -> Self {
+        self.steps.push(Step::Erode(s));
+        self
+    }
+
+    /// Opening with structuring element s (see `RLE::open`).
+    pub fn open(self, s: RLE) -> Self {
+        let reflected = s.reflect();
+        self.erode(s).dilate(reflected)
+    }
+
+    /// Closing with structuring element s (see `RLE::close`).
+    pub fn close(self, s: RLE) -> Self {
+        let reflected = s.reflect();
+        self.dilate(s).erode(reflected)
+    }
+
+    /// Flip all pixels.
+    pub fn invert(mut self) -> Self {
+        self.steps.push(Step::Invert);
+        self
+    }
+
+    /// Set to 0 all pixels which are 1 in pipeline input, result = current - input.
+    /// If input is not changed before this step result is empty.
+    pub fn subtract_original(mut self) -> Self {
+        self.steps.push(Step::SubtractOriginal);
+        self
+    }
+
+    /// Keep only pixels which are 1 in pipeline input, result = current & input.
+    pub fn intersect_original(mut self) -> Self {
+        self.steps.push(Step::IntersectOriginal);
+        self
+    }
+
+    /// Add pixels which are 1 in pipeline input, result = current | input.
+    pub fn union_original(mut self) -> Self {
+        self.steps.push(Step::UnionOriginal);
+        self
+    }
+
+    /// Number of steps which will be run after planning.
+    pub fn planned_len(&self) -> usize {
+        self.plan().len()
+    }
+
+    /// Run all steps on rle.
+    /// Input is copied once, then every step writes its result to one of two run
+    /// buffers which are swapped (see `RLE::dilate_mut_with`), so steps do not
+    /// allocate once buffers are large enough.
+    pub fn run(&self, rle: &RLE) -> RLE {
+        let steps = self.plan();
+        let mut current = rle.clone();
+        let mut scratch = Vec::new();
+        for step in &steps {
+            match step {
+                Step::Dilate(s) => current.dilate_mut_with(s, &mut scratch),
+                Step::Erode(s) => current.erode_mut_with(s, &mut scratch),
+                Step::Invert => current.invert_mut_with(&mut scratch),
+                // runs outside of image are clipped so binary operations do not
+                // allocate canonical copy
+                Step::SubtractOriginal => {
+                    current.clip_to_bounds();
+                    current -= rle;
+                }
+                Step::IntersectOriginal => {
+                    current.clip_to_bounds();
+                    current &= rle;
+                }
+                Step::UnionOriginal => current |= rle,
+            }
+        }
+        current
+    }
+
+    /// Fuse compatible consecutive steps.
+    fn plan(&self) -> Vec<Step> {
+        let mut steps: Vec<Step> = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let fused = match (steps.last(), step) {
+                (Some(Step::Dilate(a)), Step::Dilate(b)) => combine(a, b).map(Step::Dilate),
+                (Some(Step::Invert), Step::Invert) => {
+                    steps.pop();
+                    continue;
+                }
+                _ => None,
+            };
+            match fused {
+                Some(fused) => *steps.last_mut().unwrap() = fused,
+                None => steps.push(step.clone()),
+            }
+        }
+        steps
+    }
+}
+
+/// Create structuring element c such that dilating with a and then with b is same
+/// as dilating with c, that is Minkowski sum of a and b.
+/// If both a and b have even width (or height) center of c can not be placed so that
+/// this holds and None is returned.
+fn combine(a: &RLE, b: &RLE) -> Option<RLE> {
+    if (a.width | b.width) & 1 == 0 || (a.height | b.height) & 1 == 0 {
+        return None;
+    }
+    if a.width == 0 || a.height == 0 || b.width == 0 || b.height == 0 {
+        return None;
+    }
+    let (width, height) = (a.width + b.width - 1, a.height + b.height - 1);
+    // offsets of pixels relative to center are added
+    let dx = (width / 2) as i32 - (a.width / 2) as i32 - (b.width / 2) as i32;
+    let dy = (height / 2) as i32 - (a.height / 2) as i32 - (b.height / 2) as i32;
+    let (a_runs, b_runs) = (a.canonical_runs(), b.canonical_runs());
+    let mut runs = Vec::with_capacity(a_runs.len() * b_runs.len());
+    for ra in a_runs.iter() {
+        for rb in b_runs.iter() {
+            runs.push(Run::new(ra.x_start + rb.x_start + dx, ra.x_end + rb.x_end + dx, ra.y + rb.y + dy));
+        }
+    }
+    Some(RLE::from_runs(width, height, runs).merge_overlapping_runs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    fn sample() -> RLE {
+        RLE::from(&Image::new(8, 6, vec![
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 0, 0, 1,
+            0, 1, 1, 1, 1, 1, 0, 0,
+            0, 1, 1, 1, 1, 1, 1, 0,
+            1, 1, 1, 0, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 1,
+        ]))
+    }
+
+    #[test]
+    fn fused_steps_test() {
+        let rle = sample();
+        let a = RLE::from(&Image::new(2, 1, vec![1, 1]));
+        let b = RLE::from(&Image::new(3, 2, vec![
+            0, 1, 0,
+            1, 0, 1,
+        ]));
+        let pipeline = Pipeline::new().dilate(a.clone()).dilate(b.clone()).dilate(RLE::l1_structuring(1));
+        assert_eq!(pipeline.planned_len(), 1);
+        assert_eq!(pipeline.run(&rle), rle.dilate(&a).dilate(&b).dilate(&RLE::l1_structuring(1)));
+
+        // erosions are not fused, pixels outside of image are 1 for every erosion
+        let x = RLE::from_raw_data(3, 1, &[0, 1, 1]);
+        let (a_right, b_left) = (RLE::from_raw_data(3, 1, &[0, 0, 1]), RLE::from_raw_data(3, 1, &[1, 0, 0]));
+        let pipeline = Pipeline::new().erode(a_right.clone()).erode(b_left.clone());
+        assert_eq!(pipeline.planned_len(), 2);
+        assert_eq!(pipeline.run(&x), x.erode(&a_right).erode(&b_left));
+        assert_eq!(pipeline.run(&x), RLE::ones(3, 1));
+
+        // both even width, can not be fused
+        let pipeline = Pipeline::new().dilate(a.clone()).dilate(a.clone());
+        assert_eq!(pipeline.planned_len(), 2);
+        assert_eq!(pipeline.run(&rle), rle.dilate(&a).dilate(&a));
+
+        let pipeline = Pipeline::new().invert().invert().open(RLE::linf_structuring(1));
+        assert_eq!(pipeline.planned_len(), 2);
+        let s = RLE::linf_structuring(1);
+        assert_eq!(pipeline.run(&rle), rle.open(&s));
+    }
+
+    #[test]
+    fn original_steps_test() {
+        let rle = sample();
+        let s = RLE::linf_structuring(1);
+        assert_eq!(Pipeline::new().run(&rle), rle);
+        assert_eq!(Pipeline::new().dilate(s.clone()).subtract_original().run(&rle), &rle.dilate(&s) - &rle);
+        assert_eq!(Pipeline::new().invert().intersect_original().run(&rle), RLE::new(8, 6));
+        assert_eq!(Pipeline::new().invert().union_original().run(&rle), RLE::ones(8, 6));
+        assert_eq!(Pipeline::new().close(s.clone()).run(&rle), rle.close(&s));
+    }
+}
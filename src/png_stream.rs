@@ -0,0 +1,187 @@
+//! Decoding of PNG images directly to `RLE`, enabled with `png` feature.
+//! Rows are encoded into runs as they are decoded, so whole decoded image is
+//! never held in memory.
+use super::{RleError, Run, RLE};
+use ::png::{BitDepth, ColorType, Decoder, DecodingError, Transformations};
+use std::fmt;
+use std::io::Read;
+
+/// Errors returned by `RLE::from_png_reader`.
+#[derive(Debug)]
+pub enum PngError {
+    /// PNG data could not be decoded.
+    Decoding(DecodingError),
+    /// Color type is not grayscale, grayscale with alpha or paletted.
+    UnsupportedColorType(ColorType),
+    /// Interlaced images can not be decoded row by row.
+    Interlaced,
+    /// Image can not be represented as RLE.
+    Rle(RleError),
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngError::Decoding(err) => write!(f, "{}", err),
+            PngError::UnsupportedColorType(color) => write!(f, "unsupported color type {:?}", color),
+            PngError::Interlaced => write!(f, "interlaced images are not supported"),
+            PngError::Rle(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for PngError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PngError::Decoding(err) => Some(err),
+            PngError::Rle(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodingError> for PngError {
+    fn from(err: DecodingError) -> Self {
+        PngError::Decoding(err)
+    }
+}
+
+impl From<RleError> for PngError {
+    fn from(err: RleError) -> Self {
+        PngError::Rle(err)
+    }
+}
+
+impl RLE {
+    /// Decode grayscale (of any bit depth), grayscale with alpha or paletted PNG.
+    /// Pixel is 1 if its gray value (palette color for paletted images) is greater
+    /// than 0, alpha is ignored. Only one row of decoded image is kept in memory.
+    pub fn from_png_reader<R: Read>(reader: R) -> Result<Self, PngError> {
+        let mut decoder = Decoder::new(reader);
+        // keep rows packed, 1 bit images use 8 times less memory per row
+        decoder.set_transformations(Transformations::IDENTITY);
+        let (info, mut reader) = decoder.read_info()?;
+        if reader.info().interlaced {
+            return Err(PngError::Interlaced);
+        }
+        if info.width > i32::MAX as u32 || info.height > i32::MAX as u32 {
+            return Err(RleError::CoordinateOverflow.into());
+        }
+        let bits = info.bit_depth as usize;
+        // foreground[v] tells if sample v (for bit depths up to 8) is 1
+        let (samples, foreground): (usize, Vec<bool>) = match info.color_type {
+            ColorType::Grayscale => (1, (0..256).map(|v| v > 0).collect()),
+            ColorType::GrayscaleAlpha => (2, (0..256).map(|v| v > 0).collect()),
+            ColorType::Indexed => {
+                let palette = reader.info().palette.clone().unwrap_or_default();
+                let mut foreground: Vec<bool> = palette.chunks(3).map(|c| c.iter().any(|&v| v > 0)).collect();
+                foreground.resize(256, false);
+                (1, foreground)
+            }
+            color => return Err(PngError::UnsupportedColorType(color)),
+        };
+        let width = info.width as usize;
+        let mut runs = Vec::new();
+        let mut y = 0;
+        while let Some(row) = reader.next_row()? {
+            let is_set = |x: usize| -> bool {
+                let bit = x * samples * bits;
+                match info.bit_depth {
+                    BitDepth::Sixteen => row[bit / 8] | row[bit / 8 + 1] > 0,
+                    BitDepth::Eight => foreground[row[bit / 8] as usize],
+                    _ => {
+                        let shift = 8 - bits - bit % 8;
+                        foreground[((row[bit / 8] >> shift) & ((1 << bits) - 1) as u8) as usize]
+                    }
+                }
+            };
+            let mut start = None;
+            for x in 0..width {
+                match (is_set(x), start) {
+                    (true, None) => start = Some(x),
+                    (false, Some(s)) => {
+                        runs.push(Run::new(s as i32, x as i32 - 1, y));
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(s) = start {
+                runs.push(Run::new(s as i32, width as i32 - 1, y));
+            }
+            y += 1;
+        }
+        // rows are decoded in order so runs are canonical
+        Ok(RLE { width, height: info.height as usize, runs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+    use ::png::Encoder;
+
+    fn encode(width: u32, height: u32, color: ColorType, depth: BitDepth, palette: Option<Vec<u8>>, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut out, width, height);
+            encoder.set_color(color);
+            encoder.set_depth(depth);
+            if let Some(palette) = palette {
+                encoder.set_palette(palette);
+            }
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+        }
+        out
+    }
+
+    fn expected() -> RLE {
+        RLE::from(&Image::new(10, 3, vec![
+            1, 1, 0, 0, 0, 0, 0, 0, 1, 1,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 1, 0,
+        ]))
+    }
+
+    #[test]
+    fn grayscale_test() {
+        // 1 bit rows are padded to 2 bytes
+        let data = [0b1100_0000, 0b1100_0000, 0, 0, 0b0111_1111, 0b1000_0000];
+        let png = encode(10, 3, ColorType::Grayscale, BitDepth::One, None, &data);
+        assert_eq!(RLE::from_png_reader(&png[..]).unwrap(), expected());
+
+        let gray: Vec<u8> = expected().to_image(1).data().iter().map(|&p| p * 200).collect();
+        let png = encode(10, 3, ColorType::Grayscale, BitDepth::Eight, None, &gray);
+        assert_eq!(RLE::from_png_reader(&png[..]).unwrap(), expected());
+
+        let gray16: Vec<u8> = gray.iter().flat_map(|&p| vec![0, p / 200]).collect();
+        let png = encode(10, 3, ColorType::Grayscale, BitDepth::Sixteen, None, &gray16);
+        assert_eq!(RLE::from_png_reader(&png[..]).unwrap(), expected());
+
+        let gray_alpha: Vec<u8> = gray.iter().flat_map(|&p| vec![p, 255 - p]).collect();
+        let png = encode(10, 3, ColorType::GrayscaleAlpha, BitDepth::Eight, None, &gray_alpha);
+        assert_eq!(RLE::from_png_reader(&png[..]).unwrap(), expected());
+    }
+
+    #[test]
+    fn indexed_test() {
+        // index 0 and 2 are black, index 1 is red
+        let palette = vec![0, 0, 0, 255, 0, 0, 0, 0, 0];
+        let data = [
+            0b0101_0000, 0b0000_0000, 0b0101_0000,
+            0b1010_1010, 0b1010_1010, 0b1010_0000,
+            0b0001_0101, 0b0101_0101, 0b0100_0000,
+        ];
+        let png = encode(10, 3, ColorType::Indexed, BitDepth::Two, Some(palette), &data);
+        assert_eq!(RLE::from_png_reader(&png[..]).unwrap(), expected());
+    }
+
+    #[test]
+    fn unsupported_test() {
+        let png = encode(1, 1, ColorType::RGB, BitDepth::Eight, None, &[0, 0, 0]);
+        assert!(matches!(RLE::from_png_reader(&png[..]), Err(PngError::UnsupportedColorType(ColorType::RGB))));
+        assert!(matches!(RLE::from_png_reader(&[0u8, 1, 2][..]), Err(PngError::Decoding(_))));
+    }
+}
@@ -0,0 +1,192 @@
+//! Python module `rle_morph` enabled with `python` feature, build it with maturin
+//! (see `pyproject.toml`). Masks are converted from and to 2D numpy arrays of
+//! `uint8` or `bool` with shape (height, width).
+use super::{RleError, RLE};
+use numpy::{PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray2, PyUntypedArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Binary image stored as runs, Python wrapper of `RLE`.
+#[pyclass(name = "RLE", module = "rle_morph", eq)]
+#[derive(Clone, PartialEq)]
+pub struct PyRle {
+    inner: RLE,
+}
+
+/// Mask accepted by `RLE.from_numpy`.
+#[derive(FromPyObject)]
+enum MaskArray<'py> {
+    U8(PyReadonlyArray2<'py, u8>),
+    Bool(PyReadonlyArray2<'py, bool>),
+}
+
+/// COCO counts are either compressed string or list of numbers.
+#[derive(FromPyObject)]
+enum CocoCounts {
+    String(String),
+    List(Vec<u32>),
+}
+
+fn value_error(err: RleError) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn wrap(res: Result<RLE, RleError>) -> PyResult<PyRle> {
+    res.map(|inner| PyRle { inner }).map_err(value_error)
+}
+
+#[pymethods]
+impl PyRle {
+    /// Empty mask with given dimensions.
+    #[new]
+    fn new(width: usize, height: usize) -> Self {
+        PyRle { inner: RLE::new(width, height) }
+    }
+
+    /// Encode 2D array, nonzero (or true) pixels are 1.
+    #[staticmethod]
+    fn from_numpy(array: MaskArray<'_>) -> PyResult<Self> {
+        let (height, width, data): (usize, usize, Vec<u8>) = match array {
+            MaskArray::U8(array) => {
+                let (h, w) = (array.shape()[0], array.shape()[1]);
+                (h, w, array.as_array().iter().copied().collect())
+            }
+            MaskArray::Bool(array) => {
+                let (h, w) = (array.shape()[0], array.shape()[1]);
+                (h, w, array.as_array().iter().map(|&set| set as u8).collect())
+            }
+        };
+        wrap(RLE::try_from_raw_data(width, height, &data))
+    }
+
+    /// Decode to `uint8` array of 0s and 1s with shape (height, width).
+    fn to_numpy<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<u8>>> {
+        let mut data = vec![0; self.inner.width() * self.inner.height()];
+        self.inner.to_image_into(&mut data, 1);
+        PyArray1::from_vec(py, data).reshape([self.inner.height(), self.inner.width()])
+    }
+
+    /// Square structuring element of size 2k + 1.
+    #[staticmethod]
+    fn square(k: usize) -> Self {
+        PyRle { inner: RLE::linf_structuring(k) }
+    }
+
+    /// Diamond structuring element of size 2k + 1.
+    #[staticmethod]
+    fn diamond(k: usize) -> Self {
+        PyRle { inner: RLE::l1_structuring(k) }
+    }
+
+    /// Decode COCO RLE dict with keys `size` ([height, width]) and `counts`
+    /// (compressed string or list of numbers).
+    #[staticmethod]
+    fn from_coco(rle: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let size: [usize; 2] = rle.get_item("size")?
+            .ok_or_else(|| PyValueError::new_err("missing size"))?
+            .extract()?;
+        let counts: CocoCounts = rle.get_item("counts")?
+            .ok_or_else(|| PyValueError::new_err("missing counts"))?
+            .extract()?;
+        let [height, width] = size;
+        wrap(match counts {
+            CocoCounts::String(s) => RLE::from_coco_string(width, height, &s),
+            CocoCounts::List(counts) => RLE::from_coco_counts(width, height, &counts),
+        })
+    }
+
+    /// Encode as COCO RLE dict with compressed counts, same as pycocotools `encode`.
+    fn to_coco<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("size", [self.inner.height(), self.inner.width()])?;
+        dict.set_item("counts", self.inner.to_coco_string())?;
+        Ok(dict)
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.inner.width()
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.inner.height()
+    }
+
+    /// Number of pixels set to 1.
+    fn area(&self) -> usize {
+        self.inner.area()
+    }
+
+    fn dilate(&self, se: &PyRle) -> PyResult<Self> {
+        wrap(self.inner.try_dilate(&se.inner))
+    }
+
+    fn erode(&self, se: &PyRle) -> PyResult<Self> {
+        wrap(self.inner.try_erode(&se.inner))
+    }
+
+    fn __and__(&self, other: &PyRle) -> PyResult<Self> {
+        wrap(self.inner.try_bitand(&other.inner))
+    }
+
+    fn __or__(&self, other: &PyRle) -> PyResult<Self> {
+        wrap(self.inner.try_bitor(&other.inner))
+    }
+
+    fn __xor__(&self, other: &PyRle) -> PyResult<Self> {
+        wrap(self.inner.try_bitxor(&other.inner))
+    }
+
+    fn __sub__(&self, other: &PyRle) -> PyResult<Self> {
+        wrap(self.inner.try_sub(&other.inner))
+    }
+
+    fn __invert__(&self) -> Self {
+        PyRle { inner: !&self.inner }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RLE(width={}, height={}, area={})", self.inner.width(), self.inner.height(), self.inner.area())
+    }
+}
+
+#[pymodule]
+fn rle_morph(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyRle>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn python_test() {
+        Python::initialize();
+        Python::attach(|py| {
+            let rle = RLE::from(&Image::new(4, 3, vec![
+                0, 1, 1, 0,
+                0, 0, 0, 0,
+                1, 0, 0, 1,
+            ]));
+            let mask = Bound::new(py, PyRle { inner: rle.clone() }).unwrap();
+            let coco = mask.call_method0("to_coco").unwrap();
+            let decoded: PyRle = py.get_type::<PyRle>().call_method1("from_coco", (&coco,)).unwrap().extract().unwrap();
+            assert_eq!(decoded.inner, rle);
+            let counts = PyDict::new(py);
+            counts.set_item("size", [3, 4]).unwrap();
+            counts.set_item("counts", rle.to_coco_counts()).unwrap();
+            assert_eq!(PyRle::from_coco(&counts).unwrap().inner, rle);
+
+            let se = PyRle::square(1);
+            let dilated = mask.borrow().dilate(&se).unwrap();
+            assert_eq!(dilated.inner, rle.dilate(&RLE::linf_structuring(1)));
+            let inverted: PyRle = mask.call_method0("__invert__").unwrap().extract().unwrap();
+            assert_eq!(mask.borrow().__and__(&inverted).unwrap().area(), 0);
+            assert!(mask.borrow().__or__(&PyRle::new(2, 2)).is_err());
+            assert_eq!(mask.repr().unwrap().to_string(), "RLE(width=4, height=3, area=4)");
+        });
+    }
+}
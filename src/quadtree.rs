@@ -0,0 +1,243 @@
+use super::{Run, RLE};
+
+/// Node of quadtree covering square region of image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuadNode {
+    /// All pixels of region are 0.
+    Empty,
+    /// All pixels of region are 1.
+    Full,
+    /// Region is split in 4 quadrants: top left, top right, bottom left, bottom right.
+    Split(Box<[QuadNode; 4]>),
+}
+
+/// Representation of binary image as quadtree.
+/// Root covers square with side `size` (smallest power of two not smaller than
+/// width and height), pixels outside of image are treated as 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuadTree {
+    /// width of image
+    width: usize,
+    /// height of image
+    height: usize,
+    /// side of square covered by root
+    size: usize,
+    /// root node
+    root: QuadNode,
+}
+
+impl QuadTree {
+    /// Get image width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get image height.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get side of square covered by root.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Get root node.
+    pub fn root(&self) -> &QuadNode {
+        &self.root
+    }
+
+    /// Get number of nodes in tree.
+    pub fn node_count(&self) -> usize {
+        fn count(node: &QuadNode) -> usize {
+            match node {
+                QuadNode::Split(children) => 1 + children.iter().map(count).sum::<usize>(),
+                _ => 1,
+            }
+        }
+        count(&self.root)
+    }
+
+    /// Check if pixel (x, y) is 1.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return false;
+        }
+        let (mut x0, mut y0, mut size) = (0, 0, self.size as i32);
+        let mut node = &self.root;
+        loop {
+            match node {
+                QuadNode::Empty => return false,
+                QuadNode::Full => return true,
+                QuadNode::Split(children) => {
+                    size /= 2;
+                    let right = (x >= x0 + size) as usize;
+                    let bottom = (y >= y0 + size) as usize;
+                    x0 += right as i32 * size;
+                    y0 += bottom as i32 * size;
+                    node = &children[2 * bottom + right];
+                }
+            }
+        }
+    }
+
+    /// Get number of pixels set to 1 inside rectangle with top left corner (x, y),
+    /// width w and height h.
+    pub fn area_in_rect(&self, x: i32, y: i32, w: usize, h: usize) -> usize {
+        let rect = (x as i64, y as i64, x as i64 + w as i64, y as i64 + h as i64);
+        area_in_rect(&self.root, 0, 0, self.size as i64, rect)
+    }
+
+    /// Check if any pixel inside rectangle with top left corner (x, y),
+    /// width w and height h is 1.
+    pub fn intersects_rect(&self, x: i32, y: i32, w: usize, h: usize) -> bool {
+        self.area_in_rect(x, y, w, h) > 0
+    }
+}
+
+/// Number of set pixels of node (covering square at (x0, y0) with side size)
+/// inside rect (x_min, y_min, x_max, y_max) with exclusive max coordinates.
+fn area_in_rect(node: &QuadNode, x0: i64, y0: i64, size: i64, rect: (i64, i64, i64, i64)) -> usize {
+    let (x_min, y_min, x_max, y_max) = rect;
+    let w = std::cmp::min(x_max, x0 + size) - std::cmp::max(x_min, x0);
+    let h = std::cmp::min(y_max, y0 + size) - std::cmp::max(y_min, y0);
+    if w <= 0 || h <= 0 {
+        return 0;
+    }
+    match node {
+        QuadNode::Empty => 0,
+        QuadNode::Full => (w * h) as usize,
+        QuadNode::Split(children) => {
+            let half = size / 2;
+            children.iter().enumerate()
+                .map(|(i, child)| area_in_rect(child, x0 + (i % 2) as i64 * half, y0 + (i / 2) as i64 * half, half, rect))
+                .sum()
+        }
+    }
+}
+
+/// Build node covering square at (x0, y0) with given size from runs inside that square.
+fn build(runs: &[Run], x0: i32, y0: i32, size: i32, width: i32, height: i32) -> QuadNode {
+    if runs.is_empty() {
+        return QuadNode::Empty;
+    }
+    let area: i64 = runs.iter().map(|run| (run.x_end - run.x_start + 1) as i64).sum();
+    let inside_image = x0 + size <= width && y0 + size <= height;
+    if inside_image && area == size as i64 * size as i64 {
+        return QuadNode::Full;
+    }
+    let half = size / 2;
+    let quadrant = |qx: i32, qy: i32| {
+        let quad_runs: Vec<Run> = runs.iter()
+            .filter(|run| run.y >= qy && run.y < qy + half)
+            .filter_map(|run| run.intersect(Run::new(qx, qx + half - 1, run.y)))
+            .collect();
+        build(&quad_runs, qx, qy, half, width, height)
+    };
+    QuadNode::Split(Box::new([
+        quadrant(x0, y0),
+        quadrant(x0 + half, y0),
+        quadrant(x0, y0 + half),
+        quadrant(x0 + half, y0 + half),
+    ]))
+}
+
+/// Add runs of all full nodes to runs.
+fn collect_runs(node: &QuadNode, x0: i32, y0: i32, size: i32, width: i32, height: i32, runs: &mut Vec<Run>) {
+    match node {
+        QuadNode::Empty => {}
+        QuadNode::Full => {
+            let x_end = std::cmp::min(x0 + size, width) - 1;
+            for y in y0..std::cmp::min(y0 + size, height) {
+                runs.push(Run::new(x0, x_end, y));
+            }
+        }
+        QuadNode::Split(children) => {
+            let half = size / 2;
+            for (i, child) in children.iter().enumerate() {
+                collect_runs(child, x0 + (i % 2) as i32 * half, y0 + (i / 2) as i32 * half, half, width, height, runs);
+            }
+        }
+    }
+}
+
+impl From<&RLE> for QuadTree {
+    fn from(rle: &RLE) -> QuadTree {
+        let size = std::cmp::max(1, std::cmp::max(rle.width(), rle.height())).next_power_of_two();
+        let runs = rle.canonical_runs();
+        QuadTree {
+            width: rle.width(),
+            height: rle.height(),
+            size,
+            root: build(&runs, 0, 0, size as i32, rle.width() as i32, rle.height() as i32),
+        }
+    }
+}
+
+impl From<&QuadTree> for RLE {
+    fn from(tree: &QuadTree) -> RLE {
+        let mut runs = Vec::new();
+        collect_runs(&tree.root, 0, 0, tree.size as i32, tree.width as i32, tree.height as i32, &mut runs);
+        // neighboring blocks create touching runs so merge them
+        let rle = RLE::from_runs(tree.width, tree.height, runs);
+        let runs = rle.canonical_runs().into_owned();
+        RLE::from_runs(tree.width, tree.height, runs)
+    }
+}
+
+impl From<QuadTree> for RLE {
+    fn from(tree: QuadTree) -> RLE {
+        RLE::from(&tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    fn image() -> Image {
+        Image::new(5, 3, vec![
+            1, 1, 0, 0, 1,
+            1, 1, 0, 1, 1,
+            0, 0, 0, 1, 1,
+        ])
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let rle = RLE::from(&image());
+        let tree = QuadTree::from(&rle);
+        assert_eq!(tree.size(), 8);
+        let back: RLE = tree.into();
+        assert_eq!(back.runs(), rle.runs());
+
+        let empty = RLE::new(3, 3);
+        let tree = QuadTree::from(&empty);
+        assert_eq!(tree.root(), &QuadNode::Empty);
+        assert_eq!(RLE::from(&tree), empty);
+
+        let full = RLE::ones(4, 4);
+        let tree = QuadTree::from(&full);
+        assert_eq!(tree.root(), &QuadNode::Full);
+        assert_eq!(tree.node_count(), 1);
+        assert_eq!(RLE::from(&tree).runs(), full.runs());
+    }
+
+    #[test]
+    fn queries_test() {
+        let img = image();
+        let tree = QuadTree::from(&RLE::from(&img));
+        for y in -1..4 {
+            for x in -1..6 {
+                let expected = x >= 0 && y >= 0 && x < 5 && y < 3 && img[y as usize][x as usize] > 0;
+                assert_eq!(tree.contains(x, y), expected);
+            }
+        }
+        assert_eq!(tree.area_in_rect(0, 0, 5, 3), 9);
+        assert_eq!(tree.area_in_rect(1, 1, 3, 2), 3);
+        assert_eq!(tree.area_in_rect(-10, -10, 100, 100), 9);
+        assert!(tree.intersects_rect(2, 1, 2, 1));
+        assert!(!tree.intersects_rect(2, 0, 1, 3));
+    }
+}
@@ -0,0 +1,132 @@
+//! Random masks for benchmarks and stress tests, enabled with `rand` feature.
+//! Any generator implementing `rand::Rng` can be used, so seeded generators give
+//! reproducible masks.
+use super::{Run, RLE};
+use rand::{Rng, RngExt};
+
+/// Sample length of run or gap from geometric distribution with given mean (at least
+/// 1), which is distribution of run lengths when pixels are independent.
+fn geometric<R: Rng + ?Sized>(mean: f64, rng: &mut R) -> i64 {
+    if mean <= 1.0 {
+        return 1;
+    }
+    // 1 - u is in (0, 1] so logarithm is finite
+    let u = 1.0 - rng.random::<f64>();
+    1 + (u.ln() / (1.0 - 1.0 / mean).ln()).floor().min(i32::MAX as f64) as i64
+}
+
+impl RLE {
+    /// Random mask where every pixel is 1 with probability density, independently of
+    /// other pixels. Runs and gaps are sampled directly, so time is proportional to
+    /// number of runs and not to number of pixels.
+    pub fn random<R: Rng + ?Sized>(width: usize, height: usize, density: f64, rng: &mut R) -> RLE {
+        let mean_run_length = 1.0 / (1.0 - density.clamp(0.0, 1.0));
+        Self::random_runs(width, height, density, mean_run_length, rng)
+    }
+
+    /// Random mask with given fraction of pixels set to 1 (in expectation) where run
+    /// lengths in every row have geometric distribution with mean mean_run_length.
+    /// Mean gap length follows from density, it is at least 1 so density is lower than
+    /// requested if runs are too short to reach it.
+    pub fn random_runs<R: Rng + ?Sized>(
+        width: usize,
+        height: usize,
+        density: f64,
+        mean_run_length: f64,
+        rng: &mut R,
+    ) -> RLE {
+        if width == 0 || density <= 0.0 {
+            return RLE::new(width, height);
+        }
+        if density >= 1.0 {
+            return RLE::ones(width, height);
+        }
+        let mean_run_length = mean_run_length.max(1.0);
+        let mean_gap_length = mean_run_length * (1.0 - density) / density;
+        // probability that row starts with run, so that rows look same everywhere
+        let start_in_run = mean_run_length / (mean_run_length + mean_gap_length.max(1.0));
+        let mut runs = Vec::new();
+        for y in 0..height as i32 {
+            let mut in_run = rng.random_bool(start_in_run);
+            let mut x = 0i64;
+            while x < width as i64 {
+                if in_run {
+                    let len = geometric(mean_run_length, rng);
+                    runs.push(Run::new(x as i32, (x + len - 1).min(width as i64 - 1) as i32, y));
+                    x += len;
+                } else {
+                    x += geometric(mean_gap_length, rng);
+                }
+                in_run = !in_run;
+            }
+        }
+        // runs are sorted and separated by gaps
+        RLE { width, height, runs }
+    }
+
+    /// Random mask with count elliptical blobs with radii in min_radius..=max_radius
+    /// and centers anywhere in image, blobs can overlap and are clipped to image.
+    /// If max_radius < min_radius this function will panic.
+    pub fn random_blobs<R: Rng + ?Sized>(
+        width: usize,
+        height: usize,
+        count: usize,
+        min_radius: usize,
+        max_radius: usize,
+        rng: &mut R,
+    ) -> RLE {
+        assert!(min_radius <= max_radius);
+        let mut runs = Vec::new();
+        if width > 0 && height > 0 {
+            for _ in 0..count {
+                let cx = rng.random_range(0..width) as i32;
+                let cy = rng.random_range(0..height) as i32;
+                let rx = rng.random_range(min_radius..=max_radius);
+                let ry = rng.random_range(min_radius..=max_radius);
+                let (dx, dy) = (cx - rx as i32, cy - ry as i32);
+                runs.extend(RLE::ellipse_structuring(rx, ry).runs.iter()
+                    .map(|run| Run::new(run.x_start + dx, run.x_end + dx, run.y + dy)));
+            }
+        }
+        let mut res = RLE::from_runs(width, height, runs);
+        res.clip_to_bounds();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Connectivity;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn random_test() {
+        let mut rng = SmallRng::seed_from_u64(7);
+        let rle = RLE::random(200, 100, 0.3, &mut rng);
+        assert!(rle.is_canonical());
+        let density = rle.area() as f64 / 20000.0;
+        assert!((density - 0.3).abs() < 0.02, "density {}", density);
+        assert_eq!(RLE::random(200, 100, 0.3, &mut SmallRng::seed_from_u64(7)), rle);
+        assert_eq!(RLE::random(5, 4, 0.0, &mut rng), RLE::new(5, 4));
+        assert_eq!(RLE::random(5, 4, 1.0, &mut rng), RLE::ones(5, 4));
+
+        let long = RLE::random_runs(1000, 50, 0.5, 20.0, &mut rng);
+        assert!(long.is_canonical());
+        let mean_run = long.area() as f64 / long.runs().len() as f64;
+        assert!((mean_run - 20.0).abs() < 3.0, "mean run {}", mean_run);
+    }
+
+    #[test]
+    fn random_blobs_test() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let rle = RLE::random_blobs(300, 200, 5, 3, 4, &mut rng);
+        assert!(rle.is_canonical());
+        let components = rle.component_stats(Connectivity::Eight);
+        assert!(!components.is_empty() && components.len() <= 5);
+        // every blob fits in 9x9 box, overlapping blobs are at most 5 times larger
+        assert!(components.iter().all(|c| c.area <= 5 * 81));
+        assert_eq!(RLE::random_blobs(0, 10, 3, 1, 2, &mut rng), RLE::new(0, 10));
+    }
+}
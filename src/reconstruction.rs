@@ -0,0 +1,226 @@
+use super::connected_components::label_runs;
+use super::{trace, Connectivity, Context, RleError, RLE};
+
+impl RLE {
+    /// Morphological reconstruction by dilation of marker under mask: marker is
+    /// repeatedly dilated with 3x3 square and intersected with mask until it does
+    /// not change. Result are all 8-connected components of mask which intersect
+    /// marker, so they are found directly from runs instead of iterating.
+    /// If dimensions of marker and mask are not same this method will panic.
+    pub fn reconstruct_by_dilation(marker: &RLE, mask: &RLE) -> RLE {
+        Self::reconstruct_by_dilation_with_progress(marker, mask, &mut Context::new()).unwrap()
+    }
+
+    /// Same as `reconstruct_by_dilation` but reports progress of labeling of mask to
+    /// ctx and returns `RleError::Cancelled` if ctx is cancelled.
+    /// If dimensions of marker and mask are not same this method will panic.
+    pub fn reconstruct_by_dilation_with_progress(marker: &RLE, mask: &RLE, ctx: &mut Context) -> Result<RLE, RleError> {
+        assert!(marker.width == mask.width && marker.height == mask.height);
+        trace::instrument("reconstruct_by_dilation", mask.runs().len(), || {
+            let runs = mask.canonical_runs();
+            let marker_runs = marker.canonical_runs();
+            let (labels, count) = label_runs(&runs, Connectivity::Eight, ctx)?;
+            let mut reached = vec![false; count];
+            // two pointer walk over runs of mask and marker, both are sorted
+            let (mut i, mut j) = (0, 0);
+            while i < runs.len() && j < marker_runs.len() {
+                let (a, b) = (runs[i], marker_runs[j]);
+                if a.y == b.y && a.x_start <= b.x_end && b.x_start <= a.x_end {
+                    reached[labels[i]] = true;
+                }
+                if (a.y, a.x_end) < (b.y, b.x_end) {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+            let runs = runs.iter().zip(&labels).filter(|&(_, &label)| reached[label]).map(|(&run, _)| run).collect();
+            Ok(RLE::from_runs(mask.width, mask.height, runs))
+        })
+    }
+
+    /// Morphological reconstruction by erosion of marker above mask: marker is
+    /// repeatedly eroded with 3x3 square and united with mask until it does not change.
+    /// This is dual of `reconstruct_by_dilation`, holes of mask which are not
+    /// 8-connected to holes of marker are filled.
+    /// If dimensions of marker and mask are not same this method will panic.
+    pub fn reconstruct_by_erosion(marker: &RLE, mask: &RLE) -> RLE {
+        !&Self::reconstruct_by_dilation(&!marker, &!mask)
+    }
+
+    /// Ultimate erosion: union over n of regional maxima of n-th erosion with 3x3
+    /// square, that is pixels of n-th erosion whose 8-connected component disappears
+    /// in erosion n + 1. Every object gets at least one marker, touching convex
+    /// objects get one marker each. Pixels outside of image are treated as 0.
+    pub fn ultimate_erosion(&self) -> RLE {
+        let s = RLE::linf_structuring(1);
+        let mut res = RLE::new(self.width, self.height);
+        let mut current = RLE::from_runs(self.width, self.height, self.canonical_runs().into_owned());
+        while !current.runs.is_empty() {
+            let next = current.erode_zero_border(&s);
+            res |= &(&current - &Self::reconstruct_by_dilation(&next, &current));
+            current = next;
+        }
+        res
+    }
+
+    /// Conditional (geodesic) erosion of self above mask: erosion with 3x3 square
+    /// united with mask, so pixels of mask are never removed. Pixels outside of image
+    /// are treated as 0. Iterating it shrinks objects to markers given by mask without
+    /// losing them.
+    /// If dimensions of self and mask are not same this method will panic.
+    pub fn conditional_erode(&self, mask: &RLE) -> RLE {
+        assert!(self.width == mask.width && self.height == mask.height);
+        &self.erode_zero_border(&RLE::linf_structuring(1)) | mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Image, Run};
+
+    fn sample() -> RLE {
+        RLE::from(&Image::new(8, 6, vec![
+            1, 1, 0, 0, 0, 1, 1, 0,
+            0, 1, 0, 0, 1, 0, 1, 0,
+            0, 0, 0, 1, 0, 0, 1, 0,
+            1, 1, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 1, 1, 1, 0, 1,
+            1, 0, 0, 1, 0, 1, 0, 1,
+        ]))
+    }
+
+    /// Reconstruction with iterated geodesic dilations.
+    fn naive_reconstruct(marker: &RLE, mask: &RLE) -> RLE {
+        let s = RLE::linf_structuring(1);
+        let mut current = marker & mask;
+        loop {
+            let next = &current.dilate(&s) & mask;
+            if next == current {
+                return current;
+            }
+            current = next;
+        }
+    }
+
+    #[test]
+    fn reconstruct_by_dilation_test() {
+        let mask = sample();
+        let mut marker = RLE::new(8, 6);
+        crate::draw::filled_rect(&mut marker, 6, 0, 1, 1);
+        crate::draw::filled_rect(&mut marker, 0, 5, 1, 1);
+        let reconstructed = RLE::reconstruct_by_dilation(&marker, &mask);
+        assert_eq!(reconstructed.to_image(1), Image::new(8, 6, vec![
+            0, 0, 0, 0, 0, 1, 1, 0,
+            0, 0, 0, 0, 1, 0, 1, 0,
+            0, 0, 0, 1, 0, 0, 1, 0,
+            1, 1, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(reconstructed, naive_reconstruct(&marker, &mask));
+        // opening by reconstruction keeps whole objects which survive erosion
+        let eroded = mask.erode_zero_border(&RLE::ones(1, 3));
+        let opened = RLE::reconstruct_by_dilation(&eroded, &mask);
+        assert_eq!(opened, naive_reconstruct(&eroded, &mask));
+        assert_eq!(opened.to_image(1), Image::new(8, 6, vec![
+            0, 0, 0, 0, 0, 1, 1, 0,
+            0, 0, 0, 0, 1, 0, 1, 0,
+            0, 0, 0, 1, 0, 0, 1, 0,
+            1, 1, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(RLE::reconstruct_by_dilation(&RLE::new(8, 6), &mask), RLE::new(8, 6));
+
+        let mut reported = Vec::new();
+        let mut ctx = Context::new().with_progress(|p| reported.push(p));
+        assert_eq!(RLE::reconstruct_by_dilation_with_progress(&marker, &mask, &mut ctx), Ok(reconstructed));
+        drop(ctx);
+        assert_eq!(reported.last(), Some(&1.0));
+        let token = crate::CancellationToken::new();
+        token.cancel();
+        let mut ctx = Context::new().with_cancellation(token);
+        assert_eq!(RLE::reconstruct_by_dilation_with_progress(&marker, &mask, &mut ctx), Err(RleError::Cancelled));
+    }
+
+    #[test]
+    fn reconstruct_by_erosion_test() {
+        let mask = sample();
+        let marker = RLE::ones(8, 6);
+        // without background marker all holes are filled
+        assert_eq!(RLE::reconstruct_by_erosion(&marker, &mask), RLE::ones(8, 6));
+        let mut marker = RLE::ones(8, 6);
+        marker = &marker - &RLE::from(&Image::new(8, 6, vec![
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 0, 0, 0,
+        ]));
+        assert_eq!(
+            RLE::reconstruct_by_erosion(&marker, &mask),
+            !&naive_reconstruct(&!&marker, &!&mask)
+        );
+        assert_eq!(RLE::reconstruct_by_erosion(&marker, &mask).to_image(1), Image::new(8, 6, vec![
+            1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 0, 1, 1, 1,
+        ]));
+    }
+
+    #[test]
+    fn ultimate_erosion_test() {
+        // object made of square and two rectangles, and single pixel
+        let rle = RLE::from(&Image::new(12, 7, vec![
+            1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 1,
+            0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0,
+        ]));
+        assert_eq!(rle.ultimate_erosion().to_image(1), Image::new(12, 7, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 1,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+        // squares joined by bridge get one marker each
+        let dumbbell = RLE::from(&Image::new(7, 3, vec![
+            1, 1, 1, 0, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 0, 1, 1, 1,
+        ]));
+        assert_eq!(dumbbell.ultimate_erosion().runs(), &[Run::new(1, 1, 1), Run::new(5, 5, 1)]);
+        assert_eq!(RLE::new(3, 3).ultimate_erosion(), RLE::new(3, 3));
+    }
+
+    #[test]
+    fn conditional_erode_test() {
+        let rle = RLE::ones(5, 4);
+        let mut mask = RLE::new(5, 4);
+        crate::draw::filled_rect(&mut mask, 0, 0, 1, 1);
+        assert_eq!(rle.conditional_erode(&mask).to_image(1), Image::new(5, 4, vec![
+            1, 0, 0, 0, 0,
+            0, 1, 1, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        // iterating converges to mask
+        let mut current = rle;
+        for _ in 0..3 {
+            current = current.conditional_erode(&mask);
+        }
+        assert_eq!(current, mask);
+    }
+}
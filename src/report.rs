@@ -0,0 +1,185 @@
+use super::{Connectivity, Run, RLE};
+use std::fmt;
+
+/// Summary of mask, see `RLE::report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskReport {
+    /// width of image
+    pub width: usize,
+    /// height of image
+    pub height: usize,
+    /// number of pixels set to 1
+    pub area: usize,
+    /// area divided by number of pixels in image, 0 for image without pixels
+    pub density: f64,
+    /// number of runs
+    pub run_count: usize,
+    /// number of 8-connected components
+    pub component_count: usize,
+    /// area of largest component, 0 if mask is empty
+    pub largest_component_area: usize,
+    /// bounding box (x_min, y_min, x_max, y_max) of set pixels, None if mask is empty
+    pub bbox: Option<(i32, i32, i32, i32)>,
+    /// number of holes, 4-connected components of 0 pixels which do not touch image border
+    pub holes: usize,
+}
+
+/// Statistics of runs of mask, see `RLE::stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStats {
+    /// number of runs
+    pub run_count: usize,
+    /// number of pixels set to 1
+    pub area: usize,
+    /// mean length of runs, 0 if mask is empty
+    pub mean_run_length: f64,
+    /// length of longest run, 0 if mask is empty
+    pub max_run_length: usize,
+    /// histogram[i] is number of runs with length in [2^i, 2^(i+1)),
+    /// histogram has no trailing zeros
+    pub histogram: Vec<usize>,
+    /// estimated heap memory used by mask in bytes (allocated capacity of runs)
+    pub heap_bytes: usize,
+}
+
+impl RLE {
+    /// Statistics of runs computed in one pass over runs, cheap enough to be called
+    /// often (for example to decide representation or to monitor memory).
+    /// Parts of runs outside of image are not counted, heap_bytes includes them.
+    pub fn stats(&self) -> RunStats {
+        let runs = self.canonical_runs();
+        let mut histogram = Vec::new();
+        let (mut area, mut max_run_length) = (0, 0);
+        for run in runs.iter() {
+            let len = (run.x_end - run.x_start + 1) as usize;
+            area += len;
+            max_run_length = std::cmp::max(max_run_length, len);
+            let bucket = (usize::BITS - 1 - len.leading_zeros()) as usize;
+            if histogram.len() <= bucket {
+                histogram.resize(bucket + 1, 0);
+            }
+            histogram[bucket] += 1;
+        }
+        RunStats {
+            run_count: runs.len(),
+            area,
+            mean_run_length: if runs.is_empty() { 0.0 } else { area as f64 / runs.len() as f64 },
+            max_run_length,
+            histogram,
+            heap_bytes: self.runs.capacity() * std::mem::size_of::<Run>(),
+        }
+    }
+
+    /// Create summary of mask, useful for logging or validation of masks.
+    pub fn report(&self) -> MaskReport {
+        let canonical = RLE::from_runs(self.width, self.height, self.canonical_runs().into_owned());
+        let runs = canonical.runs();
+        let components = canonical.components(Connectivity::Eight);
+        let bbox = match (runs.first(), runs.last()) {
+            (Some(first), Some(last)) => Some((
+                runs.iter().map(|run| run.x_start).min().unwrap(),
+                first.y,
+                runs.iter().map(|run| run.x_end).max().unwrap(),
+                last.y,
+            )),
+            _ => None,
+        };
+        let (w, h) = (self.width as i32, self.height as i32);
+        let holes = (!&canonical).components(Connectivity::Four).iter()
+            .filter(|background| background.runs().iter().all(|run| {
+                run.y > 0 && run.y < h - 1 && run.x_start > 0 && run.x_end < w - 1
+            }))
+            .count();
+        let mask_area = canonical.area();
+        let pixels = self.width * self.height;
+        MaskReport {
+            width: self.width,
+            height: self.height,
+            area: mask_area,
+            density: if pixels == 0 { 0.0 } else { mask_area as f64 / pixels as f64 },
+            run_count: runs.len(),
+            component_count: components.len(),
+            largest_component_area: components.iter().map(RLE::area).max().unwrap_or(0),
+            bbox,
+            holes,
+        }
+    }
+}
+
+impl fmt::Display for MaskReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "dimensions: {}x{}", self.width, self.height)?;
+        writeln!(f, "area: {} ({:.2}%)", self.area, self.density * 100.0)?;
+        writeln!(f, "runs: {}", self.run_count)?;
+        writeln!(f, "components: {}", self.component_count)?;
+        writeln!(f, "largest component: {}", self.largest_component_area)?;
+        match self.bbox {
+            Some((x_min, y_min, x_max, y_max)) => {
+                writeln!(f, "bbox: ({}, {}) - ({}, {})", x_min, y_min, x_max, y_max)?
+            }
+            None => writeln!(f, "bbox: none")?,
+        }
+        write!(f, "holes: {}", self.holes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn report_test() {
+        let rle = RLE::from(&Image::new(6, 5, vec![
+            0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 0, 0,
+            0, 1, 0, 1, 0, 1,
+            0, 1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]));
+        let report = rle.report();
+        assert_eq!(report, MaskReport {
+            width: 6,
+            height: 5,
+            area: 9,
+            density: 9.0 / 30.0,
+            run_count: 5,
+            component_count: 2,
+            largest_component_area: 8,
+            bbox: Some((1, 1, 5, 3)),
+            holes: 1,
+        });
+        assert_eq!(
+            report.to_string(),
+            "dimensions: 6x5\narea: 9 (30.00%)\nruns: 5\ncomponents: 2\nlargest component: 8\nbbox: (1, 1) - (5, 3)\nholes: 1"
+        );
+        let empty = RLE::new(0, 0).report();
+        assert_eq!(empty.density, 0.0);
+        assert_eq!(empty.bbox, None);
+        assert_eq!(empty.holes, 0);
+    }
+
+    #[test]
+    fn stats_test() {
+        let mut rle = RLE::from_runs(20, 3, vec![
+            Run::new(0, 0, 0),
+            Run::new(2, 4, 0),
+            Run::new(-5, 10, 1),
+            Run::new(3, 6, 2),
+            Run::new(8, 9, 2),
+        ]);
+        rle.runs.shrink_to_fit();
+        let stats = rle.stats();
+        assert_eq!(stats, RunStats {
+            run_count: 5,
+            area: 1 + 3 + 11 + 4 + 2,
+            mean_run_length: 21.0 / 5.0,
+            max_run_length: 11,
+            histogram: vec![1, 2, 1, 1],
+            heap_bytes: 5 * std::mem::size_of::<Run>(),
+        });
+        let empty = RLE::new(4, 4).stats();
+        assert_eq!((empty.run_count, empty.mean_run_length, empty.max_run_length), (0, 0.0, 0));
+        assert!(empty.histogram.is_empty());
+    }
+}
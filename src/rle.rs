@@ -1,8 +1,15 @@
-use super::{Image, Run, FlipBitsIter};
+use super::{coord, decompose, parallel, trace, union_iter, Image, Kernel, Run, FlipBitsIter, RleError, StructuringElement};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
 
 
 /// Representation of a binary image using a combinations of runs.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Runs are always sorted (by row and then by column), disjoint and not adjacent, all
+/// constructors and operations keep this invariant. Runs can still be outside of image
+/// (for example after `dilate`), those parts are not pixels of image.
+/// Two RLEs are equal if they have same dimensions and same pixels inside image.
+#[derive(Clone)]
 pub struct RLE {
     /// width of image
     pub(crate) width: usize,
@@ -23,17 +30,57 @@ impl RLE {
         }
     }
 
+    /// Create empty RLE with space for at least n_runs runs.
+    pub fn with_capacity(width: usize, height: usize, n_runs: usize) -> Self {
+        Self {
+            width,
+            height,
+            runs: Vec::with_capacity(n_runs),
+        }
+    }
+
+    /// Number of runs which can be stored without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.runs.capacity()
+    }
+
+    /// Reserve space for at least additional more runs.
+    pub fn reserve(&mut self, additional: usize) {
+        self.runs.reserve(additional);
+    }
+
+    /// Free unused space of runs, useful before storing many masks for long time.
+    pub fn shrink_to_fit(&mut self) {
+        self.runs.shrink_to_fit();
+    }
+
+    /// Create RLE binary image from runs in any order, runs are sorted and
+    /// overlapping or touching runs are merged.
     pub fn from_runs(width: usize, height: usize, runs: Vec<Run>) -> Self {
         Self {
             width,
             height,
             runs
+        }.merge_overlapping_runs()
+    }
+
+    /// Same as `from_runs` but returns `RleError::InvalidRun` if some run is empty
+    /// (x_end < x_start) or is not inside of image, instead of accepting it.
+    pub fn try_from_runs(width: usize, height: usize, runs: Vec<Run>) -> Result<Self, RleError> {
+        if let Some(&run) = runs.iter().find(|run| !Self::run_in_bounds(width, height, run)) {
+            return Err(RleError::InvalidRun(run));
         }
+        Ok(Self::from_runs(width, height, runs))
     }
-    /// Create RLE binary image with all pixels 1
+
+    /// Create RLE binary image with all pixels 1.
+    /// If width or height is 0 there are no pixels so there are no runs.
     pub fn ones(width: usize, height: usize) -> Self {
+        if width == 0 {
+            return Self::new(width, height);
+        }
         Self {
-            runs: (0..height).map(|y| Run {x_start: 0, x_end: width as _, y: y as _}).collect(),
+            runs: (0..height).map(|y| Run {x_start: 0, x_end: width as i32 - 1, y: y as _}).collect(),
             width,
             height,
         }
@@ -43,51 +90,126 @@ impl RLE {
     /// If w * h != data.len() then this will panic.
     /// All pixel values greater than 0 will be treated as binary value 1 else 0.
     pub fn from_raw_data(w: usize, h: usize, data: &[u8]) -> Self {
-        assert_eq!(w * h, data.len());
+        match Self::try_from_raw_data(w, h, data) {
+            Ok(rle) => rle,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Create RLE binary image from raw pixels.
+    /// Returns error if w * h != data.len() (or w * h overflows, then expected
+    /// length is `usize::MAX`).
+    /// All pixel values greater than 0 will be treated as binary value 1 else 0.
+    pub fn try_from_raw_data(w: usize, h: usize, data: &[u8]) -> Result<Self, RleError> {
+        match w.checked_mul(h) {
+            Some(len) if len == data.len() => {}
+            len => return Err(RleError::DataLength { expected: len.unwrap_or(usize::MAX), actual: data.len() }),
+        }
+        Self::try_from_raw_data_with_stride(w, h, w, data)
+    }
+
+    /// Create RLE binary image from raw pixels where each row starts `stride` bytes
+    /// after previous one (padded buffers).
+    /// Bytes between `w` and `stride` in each row are ignored and last row does not
+    /// need to be padded.
+    /// Returns error if stride < w or if data is too short (buffer size which
+    /// overflows usize is reported as expected length `usize::MAX`).
+    pub fn try_from_raw_data_with_stride(w: usize, h: usize, stride: usize, data: &[u8]) -> Result<Self, RleError> {
+        if stride < w {
+            return Err(RleError::InvalidStride { width: w, stride });
+        }
+        let expected = if h == 0 {
+            Some(0)
+        } else {
+            stride.checked_mul(h - 1).and_then(|len| len.checked_add(w))
+        };
+        let expected = expected.unwrap_or(usize::MAX);
+        if data.len() < expected {
+            return Err(RleError::DataLength { expected, actual: data.len() });
+        }
         let mut runs = Vec::new();
         for y in 0..h {
-            let mut state = EncodeState::NotRunning;
-            let mut run = Run::default();
-            run.y = y as _;
-            for current in y * w..(y+1) * w {
-                if data[current] > 0 {
-                    state = match state {
-                        // if we were not in run create run
-                        EncodeState::NotRunning => {
-                            run.x_start = (current - y * w) as _;
-                            run.x_end = run.x_start;
-                            EncodeState::Running
-                        }
-                        // if we were in run then just increment interval
-                        EncodeState::Running => {
-                            run.x_end += 1;
-                            EncodeState::Running
-                        },
-                    };
-                } else {
-                    state = match state {
-                        // if we were in run now stop and add in collection
-                        EncodeState::Running => {
-                            runs.push(run);
-                            EncodeState::NotRunning
-                        },
-                        // otherwise continue
-                        EncodeState::NotRunning => state,
-                    };
-                }
-            }
-            // in the end if we were in run add that run into collection
-            if let EncodeState::Running = state {
-                runs.push(run);
-            }
+            let row = &data[y * stride..y * stride + w];
+            encode_nonzero_row(row, y as _, &mut runs);
+        }
+        Ok(Self {
+            width: w,
+            height: h,
+            runs
+        })
+    }
 
+    /// Create RLE binary image from raw pixels where pixels with value at least min
+    /// are 1, grayscale image is binarized and encoded in single pass.
+    /// If w * h != data.len() then this will panic.
+    pub fn from_raw_data_threshold(w: usize, h: usize, data: &[u8], min: u8) -> Self {
+        Self::from_pixels_with(w, h, data, |&p| p >= min)
+    }
+
+    /// Create RLE binary image from pixels of any type, pixel is 1 if is_set returns true
+    /// for it. Pixels are stored row after row.
+    /// If w * h != data.len() then this will panic.
+    pub fn from_pixels_with<T, F: Fn(&T) -> bool>(w: usize, h: usize, data: &[T], is_set: F) -> Self {
+        if w * h != data.len() {
+            panic!("{}", RleError::DataLength { expected: w * h, actual: data.len() });
+        }
+        let mut runs = Vec::new();
+        for y in 0..h {
+            encode_row(&data[y * w..(y + 1) * w], y as _, &mut runs, &is_set);
         }
         Self {
             width: w,
             height: h,
             runs
         }
+    }
 
+    /// Create RLE binary image from image with any pixel type, pixel is 1 if is_set
+    /// returns true for it (see `from_pixels_with`).
+    pub fn from_image_with<T, F: Fn(&T) -> bool>(img: &Image<T>, is_set: F) -> Self {
+        Self::from_pixels_with(img.w(), img.h(), img.data(), is_set)
+    }
+
+    /// Create one RLE binary image per threshold from probability map in single pass.
+    /// Pixel is 1 in i-th image if its probability is greater or equal to thresholds[i],
+    /// so pixels with NaN probability are 0 and image for NaN threshold is empty.
+    /// If w * h != probs.len() then this will panic.
+    pub fn from_probability_map(w: usize, h: usize, probs: &[f32], thresholds: &[f32]) -> Vec<Self> {
+        assert_eq!(w * h, probs.len());
+        // indices of thresholds sorted by value with NaN last, pixel is 1 for prefix of
+        // sorted thresholds
+        let mut order: Vec<usize> = (0..thresholds.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (thresholds[a], thresholds[b]);
+            a.is_nan().cmp(&b.is_nan()).then(a.total_cmp(&b))
+        });
+        let sorted: Vec<f32> = order.iter().map(|&i| thresholds[i]).collect();
+        let mut runs: Vec<Vec<Run>> = vec![Vec::new(); thresholds.len()];
+        for y in 0..h {
+            // number of (sorted) thresholds for which previous pixel was 1
+            let mut active = 0;
+            for x in 0..=w {
+                let count = if x < w {
+                    let p = probs[y * w + x];
+                    sorted.partition_point(|&t| t <= p)
+                } else {
+                    0
+                };
+                // thresholds in [active, count) start run, in [count, active) end run
+                for runs in &mut runs[active..std::cmp::max(active, count)] {
+                    runs.push(Run::new(x as _, x as _, y as _));
+                }
+                for runs in &mut runs[count..std::cmp::max(active, count)] {
+                    runs.last_mut().unwrap().x_end = x as i32 - 1;
+                }
+                active = count;
+            }
+        }
+        let mut res: Vec<Option<Self>> = vec![None; thresholds.len()];
+        for (runs, &i) in runs.into_iter().zip(&order) {
+            res[i] = Some(Self { width: w, height: h, runs });
+        }
+        res.into_iter().map(|rle| rle.unwrap()).collect()
     }
 
     /// Structuring element for dilation/erosion using l1 norm (manhattan distance)
@@ -123,9 +245,8 @@ impl RLE {
     #[inline]
     pub fn linf_structuring(k: usize) -> Self {
         let dim = 2 * k + 1;
-        let center = (dim / 2) as i32;
         // all pixels are 1
-        let mut runs = (0..dim as i32).map(|i| Run {
+        let runs = (0..dim as i32).map(|i| Run {
             x_start: 0,
             x_end: (dim - 1) as i32,
             y: i
@@ -137,25 +258,135 @@ impl RLE {
         }
     }
 
-    /// Set Run of pixels to 1.
-    /// This method will also try to merge runs
+    /// Structuring element for dilation/erosion using l2 norm (euclidean distance),
+    /// all pixels with distance to center at most k are 1.
     #[inline]
-    fn add_run(&mut self, run: Run) {
-        self.runs.push(run);
-        self.merge_overlapping_runs_mut();
+    pub fn disk_structuring(k: usize) -> Self {
+        let dim = 2 * k + 1;
+        let center = k as i64;
+        let runs = (0..dim as i64).map(|y| {
+            let dy = y - center;
+            // largest dx for which dx^2 + dy^2 <= k^2
+            let mut dx = 0;
+            while (dx + 1) * (dx + 1) + dy * dy <= center * center {
+                dx += 1;
+            }
+            Run {
+                x_start: (center - dx) as i32,
+                x_end: (center + dx) as i32,
+                y: y as i32,
+            }
+        }).collect();
+        Self {
+            width: dim,
+            height: dim,
+            runs,
+        }
+    }
+
+    /// Elliptical structuring element with 2 * rx + 1 columns and 2 * ry + 1 rows,
+    /// rasterized same as OpenCV `MORPH_ELLIPSE`: row at distance dy from center has
+    /// pixels at distance at most round(rx * sqrt(1 - dy^2 / ry^2)) from center.
+    pub fn ellipse_structuring(rx: usize, ry: usize) -> Self {
+        let (width, height) = (2 * rx + 1, 2 * ry + 1);
+        let (c, r) = (rx as f64, ry as f64);
+        let runs = (0..height as i32).map(|y| {
+            let dy = (y - ry as i32) as f64;
+            let dx = if ry == 0 { rx as i32 } else { (c * (1.0 - dy * dy / (r * r)).sqrt()).round() as i32 };
+            Run::new(rx as i32 - dx, rx as i32 + dx, y)
+        }).collect();
+        Self { width, height, runs }
+    }
+
+    /// Rectangular structuring element with w columns and h rows, all pixels are 1.
+    /// Center is (w / 2, h / 2) as for all structuring elements, so for even
+    /// dimensions it is right (or below) of geometric center.
+    #[inline]
+    pub fn rect_structuring(w: usize, h: usize) -> Self {
+        Self::ones(w, h)
+    }
+
+    /// Horizontal line structuring element with len columns and 1 row.
+    #[inline]
+    pub fn hline_structuring(len: usize) -> Self {
+        Self::rect_structuring(len, 1)
+    }
+
+    /// Vertical line structuring element with 1 column and len rows.
+    #[inline]
+    pub fn vline_structuring(len: usize) -> Self {
+        Self::rect_structuring(1, len)
+    }
+
+    /// Runs of l1 structuring element with `DIM` rows and columns (k = DIM / 2).
+    /// This can be evaluated in const context so fixed kernels can be stored in statics:
+    /// ```
+    /// use rle_morph::{Run, RLE};
+    /// static L1_2: [Run; 5] = RLE::l1_structuring_runs::<5>();
+    /// assert_eq!(RLE::from_runs(5, 5, L1_2.to_vec()), RLE::l1_structuring(2));
+    /// ```
+    /// DIM must be odd.
+    pub const fn l1_structuring_runs<const DIM: usize>() -> [Run; DIM] {
+        assert!(DIM % 2 == 1, "structuring element dimension must be odd");
+        let center = (DIM / 2) as i32;
+        let mut runs = [Run::new(0, 0, 0); DIM];
+        let mut i = 0;
+        while i < DIM as i32 {
+            // distance of current row to center row
+            let d = if i <= center { i } else { 2 * center - i };
+            runs[i as usize] = Run::new(center - d, center + d, i);
+            i += 1;
+        }
+        runs
+    }
+
+    /// Runs of linf structuring element with `DIM` rows and columns (k = DIM / 2).
+    /// This can be evaluated in const context, see `l1_structuring_runs`.
+    /// DIM must be odd.
+    pub const fn linf_structuring_runs<const DIM: usize>() -> [Run; DIM] {
+        assert!(DIM % 2 == 1, "structuring element dimension must be odd");
+        Self::rect_structuring_runs::<DIM, DIM>()
+    }
+
+    /// Runs of rectangular structuring element with `W` columns and `H` rows,
+    /// all pixels are 1.
+    /// This can be evaluated in const context, see `l1_structuring_runs`.
+    pub const fn rect_structuring_runs<const W: usize, const H: usize>() -> [Run; H] {
+        let mut runs = [Run::new(0, 0, 0); H];
+        let mut i = 0;
+        while i < H {
+            runs[i] = Run::new(0, W as i32 - 1, i as i32);
+            i += 1;
+        }
+        runs
+    }
+
+    /// Set run of pixels to 1.
+    /// Position of run is found with binary search and it is merged only with runs it
+    /// overlaps or touches, so building RLE run by run does not sort all runs every time.
+    /// Empty run (x_start > x_end) is ignored.
+    pub fn insert_run(&mut self, run: Run) {
+        debug_assert!(Run::is_normalized(&self.runs), "runs are not normalized");
+        if run.x_start > run.x_end {
+            return;
+        }
+        // i64 so that touching runs at i32 bounds do not overflow
+        let (x_start, x_end) = (run.x_start as i64, run.x_end as i64);
+        let start = self.runs.partition_point(|r| r.y < run.y || (r.y == run.y && (r.x_end as i64) + 1 < x_start));
+        let end = start + self.runs[start..].partition_point(|r| r.y == run.y && r.x_start as i64 <= x_end + 1);
+        let merged = self.runs[start..end].iter().fold(run, |acc, r| Run::new(
+            std::cmp::min(acc.x_start, r.x_start),
+            std::cmp::max(acc.x_end, r.x_end),
+            run.y,
+        ));
+        self.runs.splice(start..end, std::iter::once(merged));
     }
 
     /// Merge overlapping runs in this RLE.
     /// Mutable version
     #[inline]
     pub fn merge_overlapping_runs_mut(&mut self) {
-        loop {
-            let last_cnt = self.runs.len();
-            Run::merge_overlapping_runs_mut(&mut self.runs);
-            if last_cnt == self.runs.len() {
-                break;
-            }
-        }
+        Run::merge_overlapping_runs_mut(&mut self.runs);
     }
 
     /// Merge overlapping runs in this RLE.
@@ -167,50 +398,466 @@ impl RLE {
     }
 
     /// Decode RLE to binary image (0s and 1s).
+    /// Parts of runs outside of image are ignored.
     #[inline]
     pub fn to_image(&self, pixel_val: u8) -> Image {
         let mut output = vec![0; self.width * self.height];
-        for &run in self.runs.iter().filter(|run| run.y >= 0 && run.y < self.height as _) {
+        self.to_image_into(&mut output, pixel_val);
+        Image::new(self.width, self.height, output)
+    }
+
+    /// Decode RLE into buffer with width * height bytes (row after row), pixels set
+    /// to 1 get pixel_val and all other pixels 0. Buffer can be reused for many images
+    /// (for example frames of video) without allocating.
+    /// If buf.len() != width * height then this will panic.
+    pub fn to_image_into(&self, buf: &mut [u8], pixel_val: u8) {
+        assert_eq!(buf.len(), self.width * self.height, "buffer must have width * height bytes");
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        for pixel in buf.iter_mut() {
+            *pixel = 0;
+        }
+        let visible = |run: &&Run| {
+            run.y >= 0 && run.y < self.height as i32 && run.x_end >= 0 && run.x_start < self.width as i32
+        };
+        for &run in self.runs.iter().filter(visible) {
             let y = run.y as usize;
             let start = std::cmp::max(0, run.x_start) as usize;
-            let end = std::cmp::max(0, std::cmp::min(run.x_end as usize, self.width - 1));
-            let col = &mut output[y * self.width..(y + 1) * self.width];
-            for i in start..end + 1 {
-                col[i] = pixel_val;
+            let end = std::cmp::min(run.x_end as usize, self.width - 1);
+            let col = &mut buf[y * self.width..(y + 1) * self.width];
+            for pixel in &mut col[start..end + 1] {
+                *pixel = pixel_val;
             }
         }
-        Image::new(self.width, self.height, output)
     }
 
-    pub fn dilate(&self, s: &Self) -> Self {
-        // find primary runs
-        let mut primary_runs = Vec::with_capacity(self.runs.len() * s.runs.len());
-        let delta_x: i32 = (s.width as i32 / 2);
-        let delta_y: i32 =  (s.height as i32 / 2);
-        for &a in &self.runs {
-            for &b in &s.runs {
-                primary_runs.push(
-                    Run {
-                        x_start: a.x_start - delta_x + b.x_start,
-                        x_end: a.x_end - delta_x + b.x_end,
-                        y: a.y + delta_y - b.y
-                    }
-                );
+    /// Write pixel_val to pixels set to 1 of image placed at (offset_x, offset_y) in
+    /// larger buffer whose rows start buf_stride bytes apart, other bytes of buffer are
+    /// not changed, so many masks can be composited into same canvas.
+    /// Parts of image outside of buffer (columns at or after buf_stride, rows after
+    /// end of buffer or at negative coordinates) are skipped.
+    pub fn render_into(&self, buf: &mut [u8], buf_stride: usize, offset_x: i32, offset_y: i32, pixel_val: u8) {
+        if buf_stride == 0 {
+            return;
+        }
+        let (stride, offset_x, offset_y) = (buf_stride as i64, offset_x as i64, offset_y as i64);
+        let buf_len = buf.len() as i64;
+        for run in self.canonical_runs().iter() {
+            let y = run.y as i64 + offset_y;
+            let start = (run.x_start as i64 + offset_x).max(0);
+            let end = (run.x_end as i64 + offset_x).min(stride - 1);
+            if y < 0 || start > end {
+                continue;
+            }
+            let row = y * stride;
+            if row + start >= buf_len {
+                break;
+            }
+            buf[(row + start) as usize..=(row + end).min(buf_len - 1) as usize].fill(pixel_val);
+        }
+    }
+
+    /// Dilate image with structuring element s.
+    /// Pixel (x, y) of result is 1 if any pixel (x + sx - cx, y + sy - cy) of self is 1
+    /// where (sx, sy) is pixel of s set to 1 and (cx, cy) is anchor of s, which is
+    /// (s.width / 2, s.height / 2) for `RLE` (see `StructuringElement` for other anchors).
+    /// Offsets of s are reflected in both axes, so for example pixels of s = [1, 1, 0]
+    /// (anchor 1) grow to the right, not to the left.
+    /// Runs of result can be outside of image, see `clip_to_bounds`.
+    /// If some coordinate of result does not fit in i32 this method will panic, see
+    /// `try_dilate`.
+    pub fn dilate<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        match self.try_dilate(s) {
+            Ok(res) => res,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Same as `dilate` but returns `RleError::CoordinateOverflow` instead of panicking
+    /// if some coordinate of result does not fit in i32. Offsets of structuring element
+    /// are added in i64, so this is checked also without `strict` feature.
+    pub fn try_dilate<K: Kernel + ?Sized>(&self, s: &K) -> Result<Self, RleError> {
+        trace::instrument("dilate", self.runs.len(), || {
+            // primary runs of groups of rows are found and merged independently
+            // (in parallel with `rayon` feature) and then merged together
+            let s_runs = s.kernel_runs();
+            let anchor = s.anchor();
+            if let Some(runs) = decompose::dilate_line(&self.runs, s_runs, anchor) {
+                return Ok(Self { runs: runs?, width: self.width, height: self.height });
+            }
+            let chunks = row_chunks(&self.runs, DILATE_CHUNK_RUNS);
+            let merged = parallel::map(&chunks, |chunk| primary_runs(chunk, s_runs, anchor));
+            let mut runs = Vec::with_capacity(merged.iter().map(|res| res.as_ref().map_or(0, Vec::len)).sum());
+            for chunk_runs in merged {
+                runs.extend(chunk_runs?);
             }
+            Ok(Self {
+                runs,
+                width: self.width,
+                height: self.height,
+            }.merge_overlapping_runs())
+        })
+    }
+
+    /// Dilate image with structuring element s in place, see `dilate`.
+    pub fn dilate_mut<K: Kernel + ?Sized>(&mut self, s: &K) {
+        self.dilate_mut_with(s, &mut Vec::new());
+    }
+
+    /// Dilate image with structuring element s in place using scratch as temporary
+    /// buffer for primary runs. Merged runs are copied back to buffer of self, so
+    /// self does not keep large buffer of primary runs and when same scratch is used
+    /// for many calls (for example for all masks of video frame) buffers are reused
+    /// and nothing is allocated once they are large enough.
+    /// If some coordinate of result does not fit in i32 this method will panic.
+    pub fn dilate_mut_with<K: Kernel + ?Sized>(&mut self, s: &K, scratch: &mut Vec<Run>) {
+        scratch.clear();
+        if let Err(e) = push_primary_runs(self.runs.iter().copied(), s.kernel_runs(), s.anchor(), scratch) {
+            panic!("{}", e);
+        }
+        Run::merge_overlapping_runs_mut(scratch);
+        self.runs.clear();
+        self.runs.extend_from_slice(scratch);
+    }
+
+    /// Erode image with structuring element s in place, see `erode`.
+    pub fn erode_mut<K: Kernel + ?Sized>(&mut self, s: &K) {
+        self.erode_mut_with(s, &mut Vec::new());
+    }
+
+    /// Erode image with structuring element s in place using scratch as temporary
+    /// buffer, see `dilate_mut_with`.
+    pub fn erode_mut_with<K: Kernel + ?Sized>(&mut self, s: &K, scratch: &mut Vec<Run>) {
+        self.invert_mut_with(scratch);
+        self.dilate_mut_with(s, scratch);
+        self.invert_mut_with(scratch);
+    }
+
+    /// Flip all pixels in place, complement is written to scratch which is then
+    /// swapped with runs.
+    pub(crate) fn invert_mut_with(&mut self, scratch: &mut Vec<Run>) {
+        self.clip_to_bounds();
+        scratch.clear();
+        scratch.extend(self.flip_bits_iter());
+        std::mem::swap(&mut self.runs, scratch);
+    }
+
+    /// Truncate runs to image and drop runs which are completely outside of it, so
+    /// that all runs are in canonical form (see `is_canonical`).
+    /// Runs outside of image never change pixels, `area`, comparison and binary
+    /// operations already ignore them, so morphology does not clip its results and
+    /// runs moved outside can still be moved back with `translate`.
+    pub fn clip_to_bounds(&mut self) {
+        let len = clip_runs(&mut self.runs, self.width as i32, self.height as i32);
+        self.runs.truncate(len);
+    }
+
+    pub fn flip_bits_iter(&self) -> FlipBitsIter<'_> {
+        FlipBitsIter::new(self)
+    }
+
+    /// Erode image with structuring element s.
+    /// Pixel (x, y) of result is 1 if all pixels (x + sx - cx, y + sy - cy) of self are 1
+    /// where (sx, sy) is pixel of s set to 1 and (cx, cy) is anchor of s (see `dilate`).
+    /// Pixels outside of image are treated as 1.
+    /// With `rayon` feature complement of self is dilated in parallel (see `dilate`),
+    /// otherwise complements are never stored, see `erode_streaming`.
+    /// If some coordinate does not fit in i32 this method will panic, see `try_erode`.
+    pub fn erode<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        match self.try_erode(s) {
+            Ok(res) => res,
+            Err(e) => panic!("{}", e),
         }
+    }
+
+    /// Same as `erode` but returns `RleError::CoordinateOverflow` instead of panicking
+    /// (see `try_dilate`).
+    pub fn try_erode<K: Kernel + ?Sized>(&self, s: &K) -> Result<Self, RleError> {
+        trace::instrument("erode", self.runs.len(), || {
+            if decompose::is_line(s.kernel_runs()) {
+                // dilation of complement with line is linear, see `dilate_line`
+                return Ok(!&((!self).try_dilate(s)?));
+            }
+            #[cfg(feature = "rayon")]
+            {
+                Ok(!&((!self).try_dilate(s)?))
+            }
+            #[cfg(not(feature = "rayon"))]
+            {
+                self.erode_streaming(s)
+            }
+        })
+    }
+
+    /// Erosion which reads complement of self from `flip_bits_iter` while generating
+    /// primary runs of its dilation and writes complement of dilation directly to
+    /// result, so complement of sparse mask (which has many runs) is never stored.
+    #[cfg_attr(feature = "rayon", allow(dead_code))]
+    pub(crate) fn erode_streaming<K: Kernel + ?Sized>(&self, s: &K) -> Result<Self, RleError> {
+        // complement has runs ending at width - 1 and height - 1
+        coord::dimension(self.width)?;
+        coord::dimension(self.height)?;
+        let canonical;
+        // flip_bits_iter needs runs inside of image
+        let source = if self.is_canonical() {
+            self
+        } else {
+            canonical = Self { width: self.width, height: self.height, runs: self.canonical_runs().into_owned() };
+            &canonical
+        };
+        let mut dilated = Self::new(self.width, self.height);
+        push_primary_runs(source.flip_bits_iter(), s.kernel_runs(), s.anchor(), &mut dilated.runs)?;
+        dilated.merge_overlapping_runs_mut();
+        dilated.clip_to_bounds();
+        Ok(Self {
+            width: self.width,
+            height: self.height,
+            runs: dilated.flip_bits_iter().collect(),
+        })
+    }
+
+    /// Reflect structuring element through its center (cx, cy) = (width / 2, height / 2),
+    /// pixel at offset (dx, dy) from center moves to offset (-dx, -dy).
+    /// Same as `StructuringElement::reflect` of `StructuringElement::centered`, but
+    /// anchor of `RLE` is always its center, so reflected shape is padded to keep it
+    /// there and even dimension grows by one.
+    pub fn reflect(&self) -> Self {
+        StructuringElement::centered(self.clone()).reflect().to_centered_rle()
+    }
+
+    /// Morphological opening: erode with structuring element s and then dilate with
+    /// reflected s (see `Kernel::reflected`), which removes parts of image where s
+    /// does not fit. Any size and anchor of s can be used.
+    /// Result is always subset of self and opening it again does not change it.
+    pub fn open<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        self.erode(s).dilate(&s.reflected())
+    }
+
+    /// Morphological closing: dilate with structuring element s and then erode with
+    /// reflected s (see `Kernel::reflected`), which fills gaps where s does not fit.
+    /// Result is always superset of self and closing it again does not change it.
+    pub fn close<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        self.dilate(s).erode(&s.reflected())
+    }
+
+    /// Morphological gradient, pixels of dilation which are not in erosion.
+    pub fn gradient<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        &self.dilate(s) - &self.erode(s)
+    }
+
+    /// Internal gradient, pixels of self which are not in erosion (inner boundary).
+    pub fn internal_gradient<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        self - &self.erode(s)
+    }
+
+    /// External gradient, pixels of dilation which are not in self (outer boundary).
+    pub fn external_gradient<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        &self.dilate(s) - self
+    }
+
+    /// White top-hat, pixels of self removed by opening (small bright details).
+    pub fn top_hat<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        self - &self.open(s)
+    }
+
+    /// Black top-hat, pixels added to self by closing (small holes and gaps).
+    pub fn black_hat<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        &self.close(s) - self
+    }
+
+    /// Check if runs are in canonical form: sorted, inside image bounds and
+    /// no two runs in same row overlap or touch each other.
+    pub fn is_canonical(&self) -> bool {
+        self.runs.iter().all(|run| Self::run_in_bounds(self.width, self.height, run)) &&
+            Run::is_normalized(&self.runs)
+    }
+
+    /// Check that runs are in canonical form (see `is_canonical`), error contains
+    /// first run which is empty, outside of image or not after previous run.
+    pub fn validate(&self) -> Result<(), RleError> {
+        for (i, &run) in self.runs.iter().enumerate() {
+            // bounds are checked first, so order is only compared for runs inside of image
+            if !Self::run_in_bounds(self.width, self.height, &run) ||
+                (i > 0 && !Run::is_normalized(&self.runs[i - 1..=i])) {
+                return Err(RleError::InvalidRun(run));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if run is not empty and is inside of image with given dimensions.
+    pub(crate) fn run_in_bounds(width: usize, height: usize, run: &Run) -> bool {
+        run.x_start >= 0 && run.x_start <= run.x_end && (run.x_end as i64) < width as i64 &&
+            run.y >= 0 && (run.y as i64) < height as i64
+    }
+
+    /// Get runs in canonical form (see `is_canonical`).
+    /// Runs are clipped to image bounds and overlapping or touching runs are merged.
+    /// If runs are already canonical they are borrowed.
+    pub(crate) fn canonical_runs(&self) -> Cow<'_, [Run]> {
+        if self.is_canonical() {
+            return Cow::Borrowed(&self.runs);
+        }
+        let mut runs: Vec<Run> = self.runs.iter()
+            .filter(|run| run.y >= 0 && run.y < self.height as i32)
+            .map(|run| run
+                .with_x_start(std::cmp::max(0, run.x_start))
+                .with_x_end(std::cmp::min(self.width as i32 - 1, run.x_end))
+            )
+            .filter(|run| run.x_start <= run.x_end)
+            .collect();
+        runs.sort_unstable();
+        let mut res: Vec<Run> = Vec::with_capacity(runs.len());
+        for run in runs {
+            match res.last_mut() {
+                Some(last) if last.y == run.y && last.x_end + 1 >= run.x_start => {
+                    last.x_end = std::cmp::max(last.x_end, run.x_end);
+                }
+                _ => res.push(run),
+            }
+        }
+        Cow::Owned(res)
+    }
+
+    /// Check if self and other have same dimensions and same pixels,
+    /// no matter how pixels are split into runs.
+    /// This is the same as `==`.
+    pub fn eq_pixels(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height &&
+            self.canonical_runs() == other.canonical_runs()
+    }
+
+    /// Get pixels which are set in at least k of n given masks (k-of-n voting), used for
+    /// example to fuse outputs of ensemble of segmentation models.
+    /// Done with single sweep over runs of all masks counting how many masks cover each
+    /// interval of a row.
+    /// If masks is empty or dimensions of masks are not same this method will panic.
+    pub fn vote(masks: &[&RLE], k: usize) -> Self {
+        assert!(!masks.is_empty(), "at least one mask is needed");
+        let (width, height) = (masks[0].width, masks[0].height);
+        // (y, x, +1 where mask starts covering x, -1 where it stops covering x)
+        let mut events: Vec<(i32, i32, i32)> = Vec::new();
+        for mask in masks {
+            assert!(mask.width == width && mask.height == height);
+            for run in mask.canonical_runs().iter() {
+                events.push((run.y, run.x_start, 1));
+                events.push((run.y, run.x_end + 1, -1));
+            }
+        }
+        if k == 0 {
+            return Self::ones(width, height);
+        }
+        events.sort_unstable();
+        let mut runs: Vec<Run> = Vec::new();
+        let mut count = 0;
+        let mut i = 0;
+        while i < events.len() {
+            let (y, x, _) = events[i];
+            while i < events.len() && events[i].0 == y && events[i].1 == x {
+                count += events[i].2;
+                i += 1;
+            }
+            // count is valid until next event in same row
+            if count as usize >= k && i < events.len() && events[i].0 == y {
+                let run = Run::new(x, events[i].1 - 1, y);
+                match runs.last_mut() {
+                    Some(last) if last.y == y && last.x_end + 1 == x => last.x_end = run.x_end,
+                    _ => runs.push(run),
+                }
+            }
+        }
+        Self { width, height, runs }
+    }
+
+    /// Erode image with structuring element s treating pixels outside of image as 0
+    /// (see `erode`).
+    pub(crate) fn erode_zero_border(&self, s: &Self) -> Self {
+        // pad image with enough 0 pixels so that structuring element never reaches outside
+        let (mx, my) = (s.width as i32, s.height as i32);
+        let padded = Self {
+            width: self.width + 2 * s.width,
+            height: self.height + 2 * s.height,
+            runs: self.canonical_runs().iter()
+                .map(|run| Run::new(run.x_start + mx, run.x_end + mx, run.y + my))
+                .collect(),
+        };
+        let runs = padded.erode(s).runs.iter()
+            .map(|run| Run::new(
+                std::cmp::max(0, run.x_start - mx),
+                std::cmp::min(self.width as i32 - 1, run.x_end - mx),
+                run.y - my
+            ))
+            .filter(|run| run.x_start <= run.x_end && run.y >= 0 && run.y < self.height as i32)
+            .collect();
         Self {
-            runs: primary_runs,
             width: self.width,
             height: self.height,
-        }.merge_overlapping_runs()
+            runs,
+        }
     }
 
-    pub fn flip_bits_iter(&self) -> FlipBitsIter<'_> {
-        FlipBitsIter::new(&self)
+    /// Hit-or-miss transform.
+    /// Pixel (x, y) of result is 1 if all pixels of self at offsets of `hit` pixels are 1
+    /// and all pixels of self at offsets of `miss` pixels are 0 (see `erode` for offsets).
+    /// Pixels outside of image are treated as 0.
+    /// `hit` and `miss` should have same dimensions so they have same center.
+    pub fn hit_or_miss(&self, hit: &Self, miss: &Self) -> Self {
+        let hits = self.erode_zero_border(hit);
+        if hits.runs.is_empty() {
+            return hits;
+        }
+        // erode treats pixels outside of image as 1 which means 0 in self
+        &hits & &(!self).erode(miss)
+    }
+
+    /// Number of pixels set to 1, computed from lengths of runs without decoding image.
+    pub fn area(&self) -> usize {
+        self.canonical_runs().iter().map(|run| (run.x_end - run.x_start + 1) as usize).sum()
+    }
+
+    /// Check if no pixel is set to 1.
+    pub fn is_empty(&self) -> bool {
+        self.area() == 0
+    }
+
+    /// Check if all pixels are set to 1, true for image without pixels.
+    pub fn is_full(&self) -> bool {
+        self.area() == self.width * self.height
     }
 
-    pub fn erode(&self, s: &Self) -> Self {
-        !&((!self).dilate(s))
+    /// Check if pixel (x, y) is set to 1, false for pixels outside of image.
+    /// Run is found with binary search.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+        let row = self.row_runs(y);
+        let i = row.partition_point(|run| run.x_start <= x);
+        i > 0 && row[i - 1].x_end >= x
+    }
+
+    /// Get runs of row y, found with binary search.
+    /// Runs are returned as they are stored, for empty row (or y outside of image)
+    /// slice is empty.
+    pub fn row_runs(&self, y: i32) -> &[Run] {
+        debug_assert!(Run::is_normalized(&self.runs), "runs are not normalized");
+        let start = self.runs.partition_point(|run| run.y < y);
+        let row = &self.runs[start..];
+        &row[..row.partition_point(|run| run.y == y)]
+    }
+
+    /// Iterate over rows which have at least one run, yielding y and runs of row
+    /// in increasing order of y.
+    pub fn rows(&self) -> impl Iterator<Item = (i32, &[Run])> + '_ {
+        let mut rest = &self.runs[..];
+        std::iter::from_fn(move || {
+            let y = rest.first()?.y;
+            let len = rest.partition_point(|run| run.y == y);
+            let (row, tail) = rest.split_at(len);
+            rest = tail;
+            Some((y, row))
+        })
     }
 
     #[inline]
@@ -230,38 +877,339 @@ impl RLE {
 
 }
 
+impl PartialEq for RLE {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_pixels(other)
+    }
+}
+
+impl Eq for RLE {}
+
+/// Hash of dimensions and canonical runs, so RLEs which are equal (same pixels
+/// no matter how they are split into runs) have same hash.
+impl Hash for RLE {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+        self.canonical_runs().hash(state);
+    }
+}
+
+/// Number of runs of image dilated together, see `row_chunks`.
+const DILATE_CHUNK_RUNS: usize = 1024;
+
+/// Split sorted runs into chunks of whole rows with at least chunk_runs runs
+/// (except last chunk).
+pub(crate) fn row_chunks(runs: &[Run], chunk_runs: usize) -> Vec<&[Run]> {
+    let mut chunks = Vec::new();
+    let mut rest = runs;
+    while !rest.is_empty() {
+        let mut end = std::cmp::min(chunk_runs, rest.len());
+        while end < rest.len() && rest[end].y == rest[end - 1].y {
+            end += 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/// Merged primary runs of dilation of runs with structuring element with given runs
+/// and anchor (see `RLE::dilate`).
+/// Primary runs of one run of structuring element are already sorted, so they are
+/// merged pairwise (as in bottom up merge sort) instead of collecting all of them and
+/// sorting. Buffers only hold merged runs, which for large structuring elements are
+/// much fewer than runs.len() * s_runs.len().
+fn primary_runs(runs: &[Run], s_runs: &[Run], anchor: (i32, i32)) -> Result<Vec<Run>, RleError> {
+    // merged runs and number of runs of structuring element in them, counts
+    // decrease from bottom of stack so merged buffers have similar sizes
+    let mut stack: Vec<(Vec<Run>, usize)> = Vec::new();
+    for &b in s_runs {
+        let (mut merged, mut count) = (shifted_runs(runs, b, anchor)?, 1);
+        while stack.last().map(|&(_, top_count)| top_count) == Some(count) {
+            let (top, top_count) = stack.pop().unwrap();
+            merged = union_iter(top, merged).collect();
+            count += top_count;
+        }
+        stack.push((merged, count));
+    }
+    let mut res = Vec::new();
+    while let Some((top, _)) = stack.pop() {
+        res = union_iter(top, res).collect();
+    }
+    Ok(res)
+}
+
+/// Primary runs of dilation of sorted runs with one run b of structuring element,
+/// all runs are moved and widened by same amount so result is sorted and only
+/// neighbouring runs can overlap, which are merged.
+pub(crate) fn shifted_runs(runs: &[Run], b: Run, anchor: (i32, i32)) -> Result<Vec<Run>, RleError> {
+    let mut res = Vec::with_capacity(runs.len());
+    push_shifted_runs(runs, b, anchor, &mut res)?;
+    Ok(res)
+}
+
+/// Same as `shifted_runs` but runs are pushed to any buffer, for example buffer
+/// allocated in arena.
+pub(crate) fn push_shifted_runs<O: Extend<Run>>(
+    runs: &[Run], b: Run, anchor: (i32, i32), out: &mut O
+) -> Result<(), RleError> {
+    // last run is kept until next run can not be merged with it
+    let mut last: Option<Run> = None;
+    for &a in runs {
+        let run = primary_run(a, b, anchor)?;
+        match last.as_mut() {
+            Some(last) if last.y == run.y && run.x_start as i64 <= last.x_end as i64 + 1 => last.x_end = run.x_end,
+            _ => out.extend(last.replace(run)),
+        }
+    }
+    out.extend(last);
+    Ok(())
+}
+
+/// Truncate sorted runs to image of given dimensions and move runs which are not
+/// completely outside of it to start of runs, returns number of these runs (see
+/// `RLE::clip_to_bounds`). Works on slice so runs can be in any buffer.
+pub(crate) fn clip_runs(runs: &mut [Run], width: i32, height: i32) -> usize {
+    let mut len = 0;
+    for i in 0..runs.len() {
+        let run = runs[i];
+        if run.y >= 0 && run.y < height && run.x_end >= 0 && run.x_start < width {
+            runs[len] = Run::new(std::cmp::max(0, run.x_start), std::cmp::min(width - 1, run.x_end), run.y);
+            len += 1;
+        }
+    }
+    len
+}
+
+/// Push primary runs (not merged) of dilation of runs to out.
+fn push_primary_runs<I: IntoIterator<Item = Run>, O: Extend<Run>>(
+    runs: I, s_runs: &[Run], anchor: (i32, i32), out: &mut O
+) -> Result<(), RleError> {
+    for a in runs {
+        for &b in s_runs {
+            out.extend(Some(primary_run(a, b, anchor)?));
+        }
+    }
+    Ok(())
+}
+
+/// Primary run of dilation of run a with run b of structuring element with anchor
+/// (delta_x, delta_y). Coordinates are computed in i64 and checked (see `coord::narrow`).
+#[inline]
+pub(crate) fn primary_run(a: Run, b: Run, (delta_x, delta_y): (i32, i32)) -> Result<Run, RleError> {
+    Ok(Run {
+        x_start: coord::narrow(a.x_start as i64 + delta_x as i64 - b.x_end as i64)?,
+        x_end: coord::narrow(a.x_end as i64 + delta_x as i64 - b.x_start as i64)?,
+        y: coord::narrow(a.y as i64 + delta_y as i64 - b.y as i64)?,
+    })
+}
+
 #[derive(Debug, Clone, Copy)]
 enum EncodeState {
     NotRunning,
     Running,
 }
+
+/// Bytes of u64 word with lowest bit set, see `first_zero_byte`.
+const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+/// Bytes of u64 word with highest bit set, see `first_zero_byte`.
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Read 8 bytes starting at i as little endian word, so byte i is lowest byte.
+#[inline]
+fn word_at(row: &[u8], i: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&row[i..i + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Index of first nonzero byte of row at or after start, row.len() if there is none.
+/// Zero bytes are skipped 32 at a time.
+fn first_nonzero_byte(row: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i + 32 <= row.len() && word_at(row, i) | word_at(row, i + 8) | word_at(row, i + 16) | word_at(row, i + 24) == 0 {
+        i += 32;
+    }
+    while i + 8 <= row.len() {
+        let word = word_at(row, i);
+        if word != 0 {
+            return i + word.trailing_zeros() as usize / 8;
+        }
+        i += 8;
+    }
+    i + row[i..].iter().take_while(|&&p| p == 0).count()
+}
+
+/// Index of first zero byte of row at or after start, row.len() if there is none.
+/// In `(word - LOW_BITS) & !word & HIGH_BITS` highest bit of lowest zero byte of word
+/// is lowest set bit (bits of higher bytes may be wrong because of borrow).
+fn first_zero_byte(row: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i + 8 <= row.len() {
+        let word = word_at(row, i);
+        let zeros = word.wrapping_sub(LOW_BITS) & !word & HIGH_BITS;
+        if zeros != 0 {
+            return i + zeros.trailing_zeros() as usize / 8;
+        }
+        i += 8;
+    }
+    i + row[i..].iter().take_while(|&&p| p > 0).count()
+}
+
+/// Encode one row of bytes into runs of nonzero bytes, same as `encode_row` with
+/// `p > 0` but run boundaries are found in 8 bytes at once.
+fn encode_nonzero_row(row: &[u8], y: i32, runs: &mut Vec<Run>) {
+    let mut x = first_nonzero_byte(row, 0);
+    while x < row.len() {
+        let end = first_zero_byte(row, x);
+        runs.push(Run::new(x as i32, end as i32 - 1, y));
+        x = first_nonzero_byte(row, end);
+    }
+}
+
+/// Encode one row of raw pixels into runs.
+/// Pixels for which is_set returns true will be treated as binary value 1 else 0.
+pub(crate) fn encode_row<T, F: Fn(&T) -> bool>(row: &[T], y: i32, runs: &mut Vec<Run>, is_set: &F) {
+    let mut state = EncodeState::NotRunning;
+    let mut run = Run { x_start: 0, x_end: 0, y };
+    for (x, pixel) in row.iter().enumerate() {
+        if is_set(pixel) {
+            state = match state {
+                // if we were not in run create run
+                EncodeState::NotRunning => {
+                    run.x_start = x as _;
+                    run.x_end = run.x_start;
+                    EncodeState::Running
+                }
+                // if we were in run then just increment interval
+                EncodeState::Running => {
+                    run.x_end += 1;
+                    EncodeState::Running
+                },
+            };
+        } else {
+            state = match state {
+                // if we were in run now stop and add in collection
+                EncodeState::Running => {
+                    runs.push(run);
+                    EncodeState::NotRunning
+                },
+                // otherwise continue
+                EncodeState::NotRunning => state,
+            };
+        }
+    }
+    // in the end if we were in run add that run into collection
+    if let EncodeState::Running = state {
+        runs.push(run);
+    }
+}
+/// Nonzero pixels are 1.
+/// If length of image data is not w * h this will panic, see `TryFrom<Image>`
+/// (`TryFrom<&Image>` is taken by this impl through blanket impl of std).
 impl From<&Image> for RLE {
     fn from(img: &Image) -> RLE {
         let w = img.w();
         let h = img.h();
         let data = img.data();
-        RLE::from_raw_data(w, h, &data)
+        RLE::from_raw_data(w, h, data)
     }
 }
 
+/// Nonzero pixels are 1. Returns `RleError::DataLength` if length of image data is
+/// not w * h.
+impl TryFrom<Image> for RLE {
+    type Error = RleError;
+
+    fn try_from(img: Image) -> Result<Self, Self::Error> {
+        RLE::try_from_raw_data(img.w(), img.h(), img.data())
+    }
+}
+
+/// Raw pixels (w, h, data), see `RLE::try_from_raw_data`.
+impl TryFrom<(usize, usize, &[u8])> for RLE {
+    type Error = RleError;
+
+    fn try_from((w, h, data): (usize, usize, &[u8])) -> Result<Self, Self::Error> {
+        RLE::try_from_raw_data(w, h, data)
+    }
+}
+
+impl From<&Image<bool>> for RLE {
+    fn from(img: &Image<bool>) -> RLE {
+        RLE::from_image_with(img, |&p| p)
+    }
+}
+
+/// Add runs in any order, overlapping or touching runs are merged (see `from_runs`).
+impl Extend<Run> for RLE {
+    fn extend<I: IntoIterator<Item = Run>>(&mut self, iter: I) {
+        self.runs.extend(iter);
+        self.merge_overlapping_runs_mut();
+    }
+}
+
+/// Owned runs, same as `runs`.
+impl IntoIterator for RLE {
+    type Item = Run;
+    type IntoIter = std::vec::IntoIter<Run>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.runs.into_iter()
+    }
+}
+
+/// Copies of runs, same as `runs`.
+impl<'a> IntoIterator for &'a RLE {
+    type Item = Run;
+    type IntoIter = std::iter::Copied<std::slice::Iter<'a, Run>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.runs.iter().copied()
+    }
+}
+
+/// Collect iterator of runs into RLE, `FromIterator` can not be used because
+/// dimensions of image are needed.
+///
+/// ```
+/// use rle_morph::{CollectRle, RLE};
+///
+/// let rle = RLE::ones(4, 3);
+/// // all rows except middle one
+/// let rows = rle.runs().iter().copied().filter(|run| run.y != 1).collect_into_rle(4, 3);
+/// assert_eq!(rows.area(), 8);
+/// ```
+pub trait CollectRle: Iterator<Item = Run> + Sized {
+    /// Create RLE with given dimensions from runs in any order, see `RLE::from_runs`.
+    fn collect_into_rle(self, width: usize, height: usize) -> RLE {
+        let mut rle = RLE::new(width, height);
+        rle.extend(self);
+        rle
+    }
+}
+
+impl<I: Iterator<Item = Run>> CollectRle for I {}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::XorShift;
 
     #[test]
     fn merge_overlapping_test() {
         let mut rle = RLE::new(16, 16);
-        rle.add_run(Run {
+        rle.insert_run(Run {
             x_start: 0,
             x_end: 10,
             y: 0,
         });
-        rle.add_run(Run {
+        rle.insert_run(Run {
             x_start: 5,
             x_end: 11,
             y: 0,
         });
-        rle.add_run(Run {
+        rle.insert_run(Run {
             x_start: 5,
             x_end: 11,
             y: 1,
@@ -282,10 +1230,157 @@ mod test {
         ]);
     }
 
+    #[test]
+    fn dilate_erode_mut_test() {
+        let img = Image::new(9, 6, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0, 0, 0,
+            0, 1, 1, 0, 1, 1, 1, 1, 0,
+            1, 1, 1, 1, 1, 1, 1, 1, 0,
+            1, 1, 1, 0, 0, 0, 0, 0, 1,
+        ]);
+        let rle = RLE::from(&img);
+        let se = crate::StructuringElement::new(RLE::from_raw_data(2, 2, &[1, 1, 0, 1]), 0, 1);
+        let mut scratch = Vec::new();
+        for s in &[RLE::l1_structuring(1), RLE::linf_structuring(2), RLE::hline_structuring(2)] {
+            let mut dilated = rle.clone();
+            dilated.dilate_mut_with(s, &mut scratch);
+            assert_eq!(dilated.runs, rle.dilate(s).runs);
+            let mut eroded = rle.clone();
+            eroded.erode_mut_with(s, &mut scratch);
+            assert_eq!(eroded.runs, rle.erode(s).runs);
+        }
+        let mut dilated = rle.clone();
+        dilated.dilate_mut(&se);
+        assert_eq!(dilated, rle.dilate(&se));
+        let mut eroded = rle.clone();
+        eroded.erode_mut(&se);
+        assert_eq!(eroded, rle.erode(&se));
+        // runs outside of image after dilation
+        dilated.erode_mut(&RLE::l1_structuring(1));
+        assert_eq!(dilated, rle.dilate(&se).erode(&RLE::l1_structuring(1)));
+    }
+
+    #[test]
+    fn capacity_test() {
+        let mut rle = RLE::with_capacity(10, 10, 8);
+        assert!(rle.capacity() >= 8);
+        rle.reserve(100);
+        assert!(rle.capacity() >= 100);
+        rle.insert_run(Run::new(1, 3, 2));
+        rle.shrink_to_fit();
+        assert_eq!(rle.capacity(), 1);
+        // primary runs stay in scratch, dilated mask keeps small buffer
+        let mut scratch = Vec::new();
+        rle.dilate_mut_with(&RLE::disk_structuring(5), &mut scratch);
+        assert!(scratch.capacity() >= 11);
+        assert!(rle.capacity() < scratch.capacity());
+        assert_eq!(rle, RLE::from_runs(10, 10, vec![Run::new(1, 3, 2)]).dilate(&RLE::disk_structuring(5)));
+    }
+
+    #[test]
+    fn erode_streaming_test() {
+        let img = Image::new(9, 6, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0, 0, 0,
+            0, 1, 1, 0, 1, 1, 1, 1, 0,
+            1, 1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 0, 0, 0, 0, 1, 1,
+        ]);
+        let rle = RLE::from(&img);
+        // not canonical, parts of runs outside of image
+        let dilated = rle.dilate(&RLE::hline_structuring(3));
+        for s in &[RLE::l1_structuring(1), RLE::linf_structuring(2), RLE::from_raw_data(2, 1, &[1, 1]), RLE::new(3, 3)] {
+            for mask in &[&rle, &dilated, &RLE::new(9, 6), &RLE::ones(9, 6)] {
+                assert_eq!(mask.erode_streaming(s).unwrap().runs, (!&(!*mask).dilate(s)).runs);
+            }
+        }
+    }
+
+    #[test]
+    fn encode_nonzero_row_test() {
+        let mut rng = XorShift::new(7);
+        for len in 0..100 {
+            for density in 1..4 {
+                // runs of random bytes including bytes with highest bit set
+                let row: Vec<u8> = (0..len)
+                    .map(|_| {
+                        let state = rng.next().unwrap();
+                        if state % 4 < density { (state >> 8) as u8 | 1 } else { 0 }
+                    })
+                    .collect();
+                let (mut fast, mut slow) = (Vec::new(), Vec::new());
+                encode_nonzero_row(&row, 3, &mut fast);
+                encode_row(&row, 3, &mut slow, &|&p: &u8| p > 0);
+                assert_eq!(fast, slow, "{:?}", row);
+            }
+        }
+        let mut runs = Vec::new();
+        let mut row = vec![0; 70];
+        row[65] = 0x80;
+        encode_nonzero_row(&row, 0, &mut runs);
+        assert_eq!(runs, vec![Run::new(65, 65, 0)]);
+    }
+
+    #[test]
+    fn row_chunks_test() {
+        let runs = [
+            Run::new(0, 0, 0),
+            Run::new(2, 2, 0),
+            Run::new(4, 4, 0),
+            Run::new(0, 0, 1),
+            Run::new(0, 0, 2),
+            Run::new(2, 2, 2),
+        ];
+        let chunks = row_chunks(&runs, 2);
+        assert_eq!(chunks, vec![&runs[..3], &runs[3..]]);
+        assert_eq!(row_chunks(&runs, 1), vec![&runs[..3], &runs[3..4], &runs[4..]]);
+        assert!(row_chunks(&[], 2).is_empty());
+        // dilation of chunks is same as of whole image
+        let img = Image::new(5, 3, vec![1, 0, 1, 0, 1, 1, 0, 0, 0, 0, 1, 0, 1, 0, 0]);
+        let s = RLE::l1_structuring(1);
+        let whole = primary_runs(&RLE::from(&img).runs, &s.runs, (1, 1)).unwrap();
+        assert_eq!(RLE::from(&img).dilate(&s).runs, whole);
+    }
+
+    #[test]
+    fn insert_run_test() {
+        let mut rle = RLE::new(16, 4);
+        for &run in &[
+            Run::new(4, 6, 1),
+            Run::new(10, 12, 1),
+            Run::new(0, 1, 1),
+            Run::new(2, 2, 3),
+            Run::new(0, 0, 0),
+            // touches first and overlaps second run
+            Run::new(7, 10, 1),
+            Run::new(5, 4, 2),
+            Run::new(14, 15, 1),
+        ] {
+            rle.insert_run(run);
+            assert!(Run::is_normalized(&rle.runs));
+        }
+        assert_eq!(rle.runs, vec![
+            Run::new(0, 0, 0),
+            Run::new(0, 1, 1),
+            Run::new(4, 12, 1),
+            Run::new(14, 15, 1),
+            Run::new(2, 2, 3),
+        ]);
+        rle.insert_run(Run::new(1, 14, 1));
+        assert_eq!(rle.row_runs(1), &[Run::new(0, 15, 1)]);
+        let mut edge = RLE::new(4, 1);
+        edge.insert_run(Run::new(i32::MAX, i32::MAX, 0));
+        edge.insert_run(Run::new(i32::MIN, i32::MAX - 1, 0));
+        assert_eq!(edge.runs, vec![Run::new(i32::MIN, i32::MAX, 0)]);
+    }
+
     #[test]
     fn encode_to_image_test() {
         let mut a = RLE::new(3, 3);
-        a.add_run(Run {
+        a.insert_run(Run {
             x_start: 0,
             x_end: 0,
             y: 0,
@@ -299,7 +1394,7 @@ mod test {
             0, 0, 0
         ]);
 
-        a.add_run(Run {
+        a.insert_run(Run {
             x_start: 0,
             x_end: 2,
             y: 2
@@ -313,6 +1408,85 @@ mod test {
             
     }
 
+    #[test]
+    fn to_image_into_test() {
+        let rle = RLE::from_runs(4, 3, vec![Run::new(1, 2, 0), Run::new(-1, 0, 2), Run::new(3, 5, 2)]);
+        let mut buf = vec![7; 12];
+        rle.to_image_into(&mut buf, 255);
+        assert_eq!(buf, rle.to_image(255).into_raw());
+        // image is resized and its buffer reused
+        let mut img = Image::new(2, 2, vec![1, 1, 1, 1]);
+        img.fill_from_rle(&rle, 1);
+        assert_eq!(img, rle.to_image(1));
+        img.fill_from_rle(&RLE::ones(1, 2), 1);
+        assert_eq!(img, Image::new(1, 2, vec![1, 1]));
+        // image without pixels with run crossing x = 0
+        let empty = RLE::from_runs(0, 2, vec![Run::new(-1, 1, 0)]);
+        empty.to_image_into(&mut [], 1);
+        assert_eq!(empty.to_image(1), Image::new(0, 2, vec![]));
+    }
+
+    #[test]
+    fn render_into_test() {
+        let rle = RLE::from_runs(3, 2, vec![Run::new(0, 1, 0), Run::new(-1, 5, 1)]);
+        // canvas of 5x3 pixels with 2 bytes of padding in every row
+        let mut canvas = vec![0; 7 * 3];
+        rle.render_into(&mut canvas, 7, 1, 1, 9);
+        RLE::ones(2, 2).render_into(&mut canvas, 7, 4, -1, 5);
+        assert_eq!(canvas, vec![
+            0, 0, 0, 0, 5, 5, 0,
+            0, 9, 9, 0, 0, 0, 0,
+            0, 9, 9, 9, 0, 0, 0,
+        ]);
+        // clipped to stride and to end of buffer
+        let mut canvas = vec![0; 4 * 2 - 1];
+        RLE::ones(3, 3).render_into(&mut canvas, 4, 2, 0, 1);
+        assert_eq!(canvas, vec![0, 0, 1, 1, 0, 0, 1]);
+        rle.render_into(&mut [], 0, 0, 0, 1);
+    }
+
+    #[test]
+    fn from_pixels_with_test() {
+        let data = [0u8, 10, 200, 255, 127, 128, 3, 0];
+        assert_eq!(RLE::from_raw_data_threshold(4, 2, &data, 128).to_image(1), Image::new(4, 2, vec![
+            0, 0, 1, 1,
+            0, 1, 0, 0,
+        ]));
+        assert_eq!(RLE::from_raw_data_threshold(4, 2, &data, 0), RLE::ones(4, 2));
+        assert_eq!(RLE::from_raw_data_threshold(4, 2, &data, 1), RLE::from_raw_data(4, 2, &data));
+        let depth = [0.5f32, 1.5, 2.5, f32::NAN, 1.0, 0.0];
+        let rle = RLE::from_pixels_with(3, 2, &depth, |&d| d > 0.75 && d < 2.0);
+        assert_eq!(rle.to_image(1), Image::new(3, 2, vec![
+            0, 1, 0,
+            0, 1, 0,
+        ]));
+    }
+
+    #[test]
+    fn generic_image_test() {
+        let bools = Image::new(3, 2, vec![true, false, true, false, true, true]);
+        let expected = RLE::from(&Image::new(3, 2, vec![1, 0, 1, 0, 1, 1]));
+        assert_eq!(RLE::from(&bools), expected);
+        let mut wide: Image<u16> = Image::empty(3, 2);
+        wide[0][0] = 256;
+        wide[0][2] = 1000;
+        wide[1][1] = 1;
+        wide[1][2] = 65535;
+        assert_eq!(RLE::from_image_with(&wide, |&p| p > 0), expected);
+        assert_eq!(RLE::from_image_with(&wide, |&p| p > 255), RLE::from_runs(3, 2, vec![
+            Run::new(0, 0, 0),
+            Run::new(2, 2, 0),
+            Run::new(2, 2, 1),
+        ]));
+        assert_eq!(Image::<bool>::empty(2, 1).data(), &[false, false]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_pixels_with_length_test() {
+        RLE::from_pixels_with(3, 2, &[1u16; 5], |&p| p > 0);
+    }
+
     #[test]
     fn decode_from_image_test() {
         let img = Image::new(3, 3, vec![
@@ -370,6 +1544,280 @@ mod test {
         
     }
 
+    #[test]
+    fn try_from_raw_data_test() {
+        assert_eq!(
+            RLE::try_from_raw_data(3, 3, &[0, 1, 0, 0]),
+            Err(RleError::DataLength { expected: 9, actual: 4 })
+        );
+        // w * h overflows, so it must not wrap to length of data
+        assert_eq!(
+            RLE::try_from_raw_data(usize::MAX / 2 + 1, 2, &[]),
+            Err(RleError::DataLength { expected: usize::MAX, actual: 0 })
+        );
+        let rle = RLE::try_from_raw_data(2, 2, &[0, 1, 1, 0]).unwrap();
+        assert_eq!(rle.runs(), &[
+            Run { x_start: 1, x_end: 1, y: 0 },
+            Run { x_start: 0, x_end: 0, y: 1 },
+        ]);
+    }
+
+    #[test]
+    fn try_from_test() {
+        let img = Image::new(2, 2, vec![0, 1, 1, 0]);
+        assert_eq!(RLE::try_from((2, 2, img.data())), Ok(RLE::from(&img)));
+        assert_eq!(RLE::try_from(img.clone()), Ok(RLE::from(&img)));
+        assert_eq!(
+            RLE::try_from(Image::new(3, 3, vec![0, 1, 0, 0])),
+            Err(RleError::DataLength { expected: 9, actual: 4 })
+        );
+        assert_eq!(
+            RLE::try_from((3, 1, &[1u8, 1][..])),
+            Err(RleError::DataLength { expected: 3, actual: 2 })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_raw_data_size_mismatch_test() {
+        RLE::from_raw_data(3, 3, &[0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn from_raw_data_with_stride_test() {
+        // 3x3 image padded to stride of 4, padding bytes are ignored
+        let data = [
+            0, 1, 1, 9,
+            1, 0, 0, 9,
+            0, 0, 1,
+        ];
+        let rle = RLE::try_from_raw_data_with_stride(3, 3, 4, &data).unwrap();
+        let expected = Image::new(3, 3, vec![
+            0, 1, 1,
+            1, 0, 0,
+            0, 0, 1,
+        ]);
+        assert_eq!(rle, RLE::from(&expected));
+
+        assert_eq!(
+            RLE::try_from_raw_data_with_stride(3, 3, 2, &data),
+            Err(RleError::InvalidStride { width: 3, stride: 2 })
+        );
+        assert_eq!(
+            RLE::try_from_raw_data_with_stride(3, 3, 4, &data[..10]),
+            Err(RleError::DataLength { expected: 11, actual: 10 })
+        );
+        assert_eq!(
+            RLE::try_from_raw_data_with_stride(1, 3, usize::MAX / 2 + 1, &data),
+            Err(RleError::DataLength { expected: usize::MAX, actual: 11 })
+        );
+        assert_eq!(
+            RLE::try_from_raw_data_with_stride(usize::MAX, 2, usize::MAX, &data),
+            Err(RleError::DataLength { expected: usize::MAX, actual: 11 })
+        );
+    }
+
+    #[test]
+    fn from_probability_map_test() {
+        let probs = [
+            0.1, 0.5, 0.9,
+            0.7, 0.0, 0.6,
+        ];
+        let thresholds = [0.5, 0.8, 0.05];
+        let masks = RLE::from_probability_map(3, 2, &probs, &thresholds);
+        assert_eq!(masks.len(), 3);
+        for (mask, &t) in masks.iter().zip(&thresholds) {
+            let data: Vec<u8> = probs.iter().map(|&p| (p >= t) as u8).collect();
+            assert_eq!(mask.runs(), RLE::from_raw_data(3, 2, &data).runs());
+        }
+        assert!(RLE::from_probability_map(3, 2, &probs, &[]).is_empty());
+
+        let probs = [0.3, f32::NAN, 0.9, 0.6];
+        let thresholds = [f32::NAN, 0.5, -f32::NAN, 0.2, f32::NEG_INFINITY];
+        let masks = RLE::from_probability_map(4, 1, &probs, &thresholds);
+        for (mask, &t) in masks.iter().zip(&thresholds) {
+            let data: Vec<u8> = probs.iter().map(|&p| (p >= t) as u8).collect();
+            assert_eq!(mask.runs(), RLE::from_raw_data(4, 1, &data).runs());
+        }
+        assert!(masks[0].is_empty() && masks[2].is_empty());
+    }
+
+    #[test]
+    fn try_dilate_test() {
+        let rle = RLE::from_raw_data(3, 1, &[0, 1, 0]);
+        let s = RLE::linf_structuring(1);
+        assert_eq!(rle.try_dilate(&s), Ok(rle.dilate(&s)));
+    }
+
+    #[test]
+    fn dilate_asymmetric_test() {
+        let rle = RLE::from_runs(5, 3, vec![Run::new(2, 2, 1)]);
+        // anchor (1, 0), pixel grows away from set pixel left of anchor
+        let s = RLE::from_raw_data(3, 1, &[1, 1, 0]);
+        assert_eq!(rle.dilate(&s).runs(), &[Run::new(2, 3, 1)]);
+        // anchor (0, 1), same for y
+        let s = RLE::from_raw_data(1, 3, &[1, 1, 0]);
+        assert_eq!(rle.dilate(&s).runs(), &[Run::new(2, 2, 1), Run::new(2, 2, 2)]);
+    }
+
+    #[test]
+    fn dilate_overflow_test() {
+        let s = RLE::linf_structuring(1);
+        for &run in &[
+            Run::new(i32::MAX, i32::MAX, 0),
+            Run::new(0, 0, i32::MAX),
+            Run::new(i32::MIN, i32::MIN, 0),
+            Run::new(0, 0, i32::MIN),
+        ] {
+            let rle = RLE::from_runs(4, 4, vec![run]);
+            assert_eq!(rle.try_dilate(&s), Err(RleError::CoordinateOverflow), "{:?}", run);
+        }
+        // largest coordinates which do not overflow
+        let rle = RLE::from_runs(4, 4, vec![Run::new(i32::MIN + 1, i32::MAX - 1, i32::MIN + 1)]);
+        assert!(rle.try_dilate(&s).is_ok());
+        // structuring element too wide for i32 coordinates
+        let wide = RLE::from_runs(i32::MAX as usize + 1, 1, vec![Run::new(0, 0, 0)]);
+        assert_eq!(rle.try_dilate(&wide), Err(RleError::CoordinateOverflow));
+    }
+
+    #[test]
+    fn reflect_test() {
+        let s = RLE::from(&Image::new(3, 2, vec![
+            1, 1, 0,
+            0, 0, 1,
+        ]));
+        // center (1, 1), height grows to 3
+        assert_eq!(s.reflect().to_image(1), Image::new(3, 3, vec![
+            0, 0, 0,
+            1, 0, 0,
+            0, 1, 1,
+        ]));
+        assert_eq!(RLE::l1_structuring(2).reflect(), RLE::l1_structuring(2));
+    }
+
+    #[test]
+    fn open_close_test() {
+        let img = Image::new(8, 6, vec![
+            0, 0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 1, 0, 0, 1,
+            0, 1, 1, 1, 1, 1, 0, 0,
+            0, 1, 0, 1, 1, 1, 1, 0,
+            1, 1, 1, 0, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 1,
+        ]);
+        let rle = RLE::from(&img);
+        let asymmetric = RLE::from(&Image::new(2, 2, vec![
+            1, 1,
+            0, 1,
+        ]));
+        for s in &[RLE::linf_structuring(1), asymmetric] {
+            let opened = rle.open(s);
+            let closed = rle.close(s);
+            assert_eq!(&opened - &rle, RLE::new(8, 6));
+            assert_eq!(&rle - &closed, RLE::new(8, 6));
+            assert_eq!(opened.open(s), opened);
+            assert_eq!(closed.close(s), closed);
+            assert_eq!(opened, rle.erode(s).dilate(&s.reflect()));
+        }
+        assert_eq!(
+            rle.open(&RLE::ones(2, 2)).to_image(1),
+            Image::new(8, 6, vec![
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 1, 1, 1, 1, 0, 0, 0,
+                0, 1, 1, 1, 1, 1, 0, 0,
+                0, 0, 0, 1, 1, 1, 0, 0,
+                0, 0, 0, 0, 1, 1, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ])
+        );
+    }
+
+    #[test]
+    fn gradient_top_hat_test() {
+        let img = Image::new(7, 6, vec![
+            0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 1, 0, 0,
+            0, 1, 1, 1, 0, 1, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]);
+        let rle = RLE::from(&img);
+        let s = RLE::linf_structuring(1);
+        assert_eq!(rle.gradient(&s), &rle.external_gradient(&s) | &rle.internal_gradient(&s));
+        assert_eq!(
+            rle.internal_gradient(&s).to_image(1),
+            Image::new(7, 6, vec![
+                0, 0, 0, 0, 0, 0, 1,
+                0, 1, 1, 1, 1, 0, 0,
+                0, 1, 0, 1, 0, 1, 0,
+                0, 1, 0, 1, 1, 1, 0,
+                0, 1, 1, 1, 1, 0, 0,
+                0, 0, 0, 0, 0, 0, 0,
+            ])
+        );
+        // only 3x3 block in columns 1-3 survives opening
+        assert_eq!(
+            rle.top_hat(&s).to_image(1),
+            Image::new(7, 6, vec![
+                0, 0, 0, 0, 0, 0, 1,
+                0, 0, 0, 0, 1, 0, 0,
+                0, 0, 0, 0, 0, 1, 0,
+                0, 0, 0, 0, 1, 1, 0,
+                0, 0, 0, 0, 1, 0, 0,
+                0, 0, 0, 0, 0, 0, 0,
+            ])
+        );
+        // erosion treats pixels outside of image as 1 so closing also fills border
+        assert_eq!(
+            rle.black_hat(&s).to_image(1),
+            Image::new(7, 6, vec![
+                1, 1, 1, 1, 1, 1, 0,
+                1, 0, 0, 0, 0, 1, 1,
+                1, 0, 0, 0, 1, 0, 1,
+                1, 0, 0, 0, 0, 0, 1,
+                1, 0, 0, 0, 0, 0, 0,
+                1, 1, 1, 1, 1, 0, 0,
+            ])
+        );
+    }
+
+    #[test]
+    fn gradient_imageproc_test() {
+        use ::image::GrayImage;
+        use imageproc::distance_transform::Norm;
+        use imageproc::morphology::{dilate, erode};
+        let img = Image::new(9, 7, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0, 1, 1, 1, 1, 1, 0, 0, 0,
+            0, 1, 1, 1, 0, 1, 1, 0, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 0,
+            1, 1, 0, 1, 1, 1, 1, 0, 0,
+            0, 1, 1, 1, 1, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        let rle = RLE::from(&img);
+        let gray = GrayImage::from_raw(9, 7, rle.to_image(255).into_raw()).unwrap();
+        let minus = |a: &GrayImage, b: &GrayImage| {
+            let data: Vec<u8> = a.as_raw().iter().zip(b.as_raw())
+                .map(|(&a, &b)| (a > 0 && b == 0) as u8)
+                .collect();
+            Image::new(a.width() as _, a.height() as _, data)
+        };
+        for (norm, s) in [(Norm::LInf, RLE::linf_structuring(1)), (Norm::L1, RLE::l1_structuring(2))] {
+            let k = s.width() as u8 / 2;
+            let dilated = dilate(&gray, norm, k);
+            let eroded = erode(&gray, norm, k);
+            let opened = dilate(&eroded, norm, k);
+            let closed = erode(&dilated, norm, k);
+            assert_eq!(rle.gradient(&s).to_image(1), minus(&dilated, &eroded));
+            assert_eq!(rle.internal_gradient(&s).to_image(1), minus(&gray, &eroded));
+            assert_eq!(rle.external_gradient(&s).to_image(1), minus(&dilated, &gray));
+            assert_eq!(rle.top_hat(&s).to_image(1), minus(&gray, &opened));
+            assert_eq!(rle.black_hat(&s).to_image(1), minus(&closed, &gray));
+        }
+    }
+
     #[test]
     fn encode_decode_test() {
         let img = Image::new(3, 3, vec![
@@ -381,6 +1829,269 @@ mod test {
         assert_eq!(img, rle.to_image(1));
     }
 
+    #[test]
+    fn eq_pixels_test() {
+        let a = RLE::from_runs(6, 2, vec![Run::new(0, 5, 0)]);
+        let b = RLE::from_runs(6, 2, vec![Run::new(3, 5, 0), Run::new(0, 2, 0)]);
+        let c = RLE::from_runs(6, 2, vec![Run::new(0, 3, 0), Run::new(2, 5, 0)]);
+        assert!(a.eq_pixels(&b));
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        assert_eq!(b, c);
+        // runs outside of image are not pixels
+        let d = RLE::from_runs(6, 2, vec![Run::new(-3, 8, 0), Run::new(0, 1, 2)]);
+        assert_eq!(a, d);
+
+        assert_ne!(a, RLE::from_runs(6, 2, vec![Run::new(0, 4, 0)]));
+        assert_ne!(a, RLE::from_runs(6, 2, vec![Run::new(0, 5, 1)]));
+        assert_ne!(a, RLE::from_runs(7, 2, vec![Run::new(0, 5, 0)]));
+    }
+
+    #[test]
+    fn area_test() {
+        // overlapping runs and runs outside of image are not counted twice
+        let rle = RLE::from_runs(6, 2, vec![Run::new(0, 3, 0), Run::new(2, 5, 0), Run::new(-3, 1, 1), Run::new(0, 1, 2)]);
+        assert_eq!(rle.area(), 8);
+        assert!(!rle.is_empty());
+        assert!(!rle.is_full());
+        assert!(RLE::new(6, 2).is_empty());
+        assert!(RLE::ones(6, 2).is_full());
+        assert_eq!(RLE::ones(6, 2).area(), 12);
+        assert!(RLE::new(0, 3).is_empty() && RLE::new(0, 3).is_full());
+    }
+
+    #[test]
+    fn contains_test() {
+        let img = Image::new(6, 4, vec![
+            0, 1, 1, 0, 1, 0,
+            0, 0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1,
+            1, 0, 0, 1, 0, 1,
+        ]);
+        let rle = RLE::from(&img);
+        for y in 0..4 {
+            for x in 0..6 {
+                assert_eq!(rle.contains(x, y), img[y as usize][x as usize] > 0, "({}, {})", x, y);
+            }
+        }
+        for &(x, y) in &[(-1, 2), (6, 2), (0, -1), (0, 4)] {
+            assert!(!rle.contains(x, y));
+        }
+        // parts of runs outside of image
+        let rle = RLE::from_runs(3, 1, vec![Run::new(-2, 5, 0)]);
+        assert!(rle.contains(0, 0) && rle.contains(2, 0) && !rle.contains(3, 0));
+    }
+
+    #[test]
+    fn rows_test() {
+        let rle = RLE::from(&Image::new(5, 4, vec![
+            1, 1, 0, 1, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1,
+        ]));
+        assert_eq!(rle.row_runs(0), &[Run::new(0, 1, 0), Run::new(3, 3, 0)]);
+        assert!(rle.row_runs(1).is_empty());
+        assert_eq!(rle.row_runs(3), &[Run::new(1, 4, 3)]);
+        assert!(rle.row_runs(-1).is_empty() && rle.row_runs(4).is_empty());
+        let rows: Vec<(i32, &[Run])> = rle.rows().collect();
+        assert_eq!(rows, vec![(0, rle.row_runs(0)), (3, rle.row_runs(3))]);
+        assert_eq!(RLE::new(5, 4).rows().count(), 0);
+    }
+
+    #[test]
+    fn normalized_invariant_test() {
+        let img = Image::new(7, 5, vec![
+            0, 1, 1, 0, 1, 0, 1,
+            1, 1, 0, 0, 1, 1, 1,
+            0, 0, 0, 0, 0, 0, 0,
+            1, 0, 1, 0, 1, 0, 1,
+            1, 1, 1, 1, 1, 1, 0,
+        ]);
+        let rle = RLE::from(&img);
+        let other = RLE::from_runs(7, 5, vec![Run::new(3, 3, 4), Run::new(2, 3, 0), Run::new(0, 8, 2), Run::new(4, 5, 0)]);
+        assert_eq!(other.runs(), &[Run::new(2, 5, 0), Run::new(0, 8, 2), Run::new(3, 3, 4)]);
+        let s = RLE::from_raw_data(3, 2, &[1, 0, 1, 0, 1, 1]);
+        let results = [
+            rle.dilate(&s),
+            rle.erode(&s),
+            rle.open(&s),
+            rle.close(&s),
+            s.reflect(),
+            !&rle,
+            &rle | &other,
+            &rle & &other,
+            &rle - &other,
+            &rle ^ &other,
+            rle.translate(-2, 1, false),
+            rle.flip_horizontal(),
+            rle.transpose(),
+            rle.rotate90(),
+            rle.pad(1, 2, 2, 1, true),
+            rle.skeletonize(),
+            RLE::reconstruct_by_dilation(&other, &rle),
+            RLE::vote(&[&rle, &other, &s.pad(0, 4, 0, 3, false)], 2),
+        ];
+        for res in results.iter() {
+            assert!(Run::is_normalized(res.runs()), "{:?}", res);
+        }
+        for component in rle.components(crate::Connectivity::Four) {
+            assert!(Run::is_normalized(component.runs()));
+        }
+    }
+
+    #[test]
+    fn try_from_runs_test() {
+        let runs = vec![Run::new(3, 4, 1), Run::new(0, 2, 1), Run::new(1, 1, 0)];
+        let rle = RLE::try_from_runs(5, 2, runs.clone()).unwrap();
+        assert_eq!(rle, RLE::from_runs(5, 2, runs));
+        assert_eq!(rle.runs, vec![Run::new(1, 1, 0), Run::new(0, 4, 1)]);
+        assert!(rle.is_canonical());
+        assert_eq!(rle.validate(), Ok(()));
+        for &run in &[Run::new(2, 1, 0), Run::new(-1, 1, 0), Run::new(0, 5, 0), Run::new(0, 1, 2), Run::new(0, 1, -1)] {
+            assert_eq!(RLE::try_from_runs(5, 2, vec![Run::new(0, 0, 0), run]), Err(RleError::InvalidRun(run)));
+        }
+
+        // dilation leaves runs outside of image
+        let dilated = rle.dilate(&RLE::linf_structuring(1));
+        assert!(!dilated.is_canonical());
+        assert_eq!(dilated.validate(), Err(RleError::InvalidRun(dilated.runs[0])));
+        let touching = RLE { width: 5, height: 2, runs: vec![Run::new(0, 1, 0), Run::new(2, 3, 0)] };
+        assert_eq!(touching.validate(), Err(RleError::InvalidRun(Run::new(2, 3, 0))));
+        let unsorted = RLE { width: 5, height: 2, runs: vec![Run::new(0, 1, 1), Run::new(2, 3, 0)] };
+        assert_eq!(unsorted.validate(), Err(RleError::InvalidRun(Run::new(2, 3, 0))));
+        // run at smallest coordinate is reported as outside of image
+        let outside = RLE { width: 5, height: 2, runs: vec![Run::new(i32::MIN, i32::MIN, 0), Run::new(i32::MIN, 0, 0)] };
+        assert_eq!(outside.validate(), Err(RleError::InvalidRun(Run::new(i32::MIN, i32::MIN, 0))));
+    }
+
+    #[test]
+    fn iterator_traits_test() {
+        let rle = RLE::from_raw_data(4, 3, &[
+            1, 1, 0, 1,
+            0, 0, 0, 0,
+            0, 1, 1, 1,
+        ]);
+        let flipped = rle.flip_bits_iter().filter(|run| run.y != 1).collect_into_rle(4, 3);
+        assert_eq!(flipped, RLE::from_raw_data(4, 3, &[
+            0, 0, 1, 0,
+            0, 0, 0, 0,
+            1, 0, 0, 0,
+        ]));
+        let mut extended = flipped.clone();
+        extended.extend(&rle);
+        extended.extend(vec![Run::new(2, 3, 1), Run::new(0, 1, 1)]);
+        assert_eq!(extended, RLE::ones(4, 3));
+        assert_eq!(extended.runs(), &[Run::new(0, 3, 0), Run::new(0, 3, 1), Run::new(0, 3, 2)]);
+        let runs: Vec<Run> = rle.clone().into_iter().collect();
+        assert_eq!(runs, rle.runs());
+        assert_eq!((&rle).into_iter().count(), 3);
+    }
+
+    #[test]
+    fn clip_to_bounds_test() {
+        let rle = RLE::from_raw_data(3, 3, &[
+            1, 0, 0,
+            0, 0, 0,
+            0, 0, 1,
+        ]);
+        let mut dilated = rle.dilate(&RLE::linf_structuring(1));
+        assert!(!dilated.is_canonical());
+        let area = dilated.area();
+        dilated.clip_to_bounds();
+        assert!(dilated.is_canonical());
+        assert_eq!(dilated.area(), area);
+        assert_eq!(dilated.runs(), &[
+            Run::new(0, 1, 0),
+            Run::new(0, 2, 1),
+            Run::new(1, 2, 2),
+        ]);
+        let mut outside = RLE::from_runs(3, 3, vec![Run::new(-5, -1, 0), Run::new(3, 4, 1), Run::new(0, 2, 3)]);
+        outside.clip_to_bounds();
+        assert!(outside.runs().is_empty());
+    }
+
+    #[test]
+    fn hash_test() {
+        use std::collections::HashSet;
+        let a = RLE::from_runs(6, 2, vec![Run::new(0, 5, 0)]);
+        // same pixels split differently and with parts outside of image
+        let b = RLE {
+            width: 6,
+            height: 2,
+            runs: vec![Run::new(-2, 2, 0), Run::new(3, 5, 0), Run::new(0, 3, 5)],
+        };
+        let c = RLE::from_runs(6, 2, vec![Run::new(0, 5, 1)]);
+        let set: HashSet<RLE> = vec![a.clone(), b, c, a, RLE::from_runs(7, 2, vec![Run::new(0, 5, 0)])].into_iter().collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn zero_dimension_test() {
+        for &(w, h) in &[(0, 0), (0, 3), (3, 0)] {
+            let empty = RLE::new(w, h);
+            assert!(RLE::ones(w, h).runs().is_empty());
+            assert!(RLE::from_raw_data(w, h, &[]).runs().is_empty());
+            assert_eq!(empty.to_image(1), Image::new(w, h, vec![]));
+            assert!((!&empty).runs().is_empty());
+            assert_eq!(empty.flip_bits_iter().count(), 0);
+            assert!(empty.dilate(&RLE::linf_structuring(1)).runs().is_empty());
+            assert!(empty.erode(&RLE::linf_structuring(1)).runs().is_empty());
+            assert!((&empty & &RLE::ones(w, h)).runs().is_empty());
+            assert!((&empty | &RLE::ones(w, h)).runs().is_empty());
+            assert!((&RLE::ones(w, h) - &empty).runs().is_empty());
+        }
+        // empty structuring element
+        let rle = RLE::ones(3, 3);
+        assert!(rle.dilate(&RLE::new(0, 0)).runs().is_empty());
+    }
+
+    #[test]
+    fn ones_test() {
+        assert_eq!(RLE::ones(3, 2).to_image(1), Image::new(3, 2, vec![1; 6]));
+        assert_eq!(RLE::ones(3, 2).runs(), &[Run::new(0, 2, 0), Run::new(0, 2, 1)]);
+    }
+
+    #[test]
+    fn to_image_clips_runs_test() {
+        let rle = RLE::from_runs(3, 2, vec![
+            Run::new(-5, -1, 0),
+            Run::new(3, 6, 0),
+            Run::new(-1, 0, 1),
+            Run::new(0, 2, 2),
+        ]);
+        assert_eq!(rle.to_image(1), Image::new(3, 2, vec![
+            0, 0, 0,
+            1, 0, 0,
+        ]));
+    }
+
+    #[test]
+    fn vote_test() {
+        let a = RLE::from(&Image::new(4, 2, vec![
+            1, 1, 0, 0,
+            1, 0, 0, 1,
+        ]));
+        let b = RLE::from(&Image::new(4, 2, vec![
+            0, 1, 1, 0,
+            1, 0, 0, 0,
+        ]));
+        // run split in two parts must count once
+        let c = RLE::from_runs(4, 2, vec![Run::new(0, 1, 0), Run::new(2, 3, 0), Run::new(3, 3, 1)]);
+        let masks = [&a, &b, &c];
+        assert_eq!(RLE::vote(&masks, 1), &(&a | &b) | &c);
+        assert_eq!(RLE::vote(&masks, 2).to_image(1), Image::new(4, 2, vec![
+            1, 1, 1, 0,
+            1, 0, 0, 1,
+        ]));
+        assert_eq!(RLE::vote(&masks, 3).to_image(1), Image::new(4, 2, vec![
+            0, 1, 0, 0,
+            0, 0, 0, 0,
+        ]));
+        assert!(RLE::vote(&masks, 4).runs().is_empty());
+        assert_eq!(RLE::vote(&masks, 0), RLE::ones(4, 2));
+    }
+
     #[test]
     fn dilate_test() {
         let orig = Image::new(6, 6, vec![
@@ -489,6 +2200,51 @@ mod test {
         );
     }
 
+    #[test]
+    fn hit_or_miss_test() {
+        let img = RLE::from(&Image::new(5, 4, vec![
+            1, 1, 0, 0, 0,
+            0, 0, 0, 1, 0,
+            0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        // pixel with pixel on the right and nothing on the left
+        let hit = RLE::from(&Image::new(3, 1, vec![0, 1, 1]));
+        let miss = RLE::from(&Image::new(3, 1, vec![1, 0, 0]));
+        assert_eq!(img.hit_or_miss(&hit, &miss).to_image(1), Image::new(5, 4, vec![
+            1, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 1, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ]));
+        // pixels outside of image are 0 so they are not hits
+        let hit = RLE::from(&Image::new(3, 1, vec![1, 1, 0]));
+        let miss = RLE::new(3, 1);
+        assert_eq!(img.hit_or_miss(&hit, &miss).to_image(1), Image::new(5, 4, vec![
+            0, 1, 0, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 1, 1, 0,
+            0, 0, 0, 0, 0,
+        ]));
+    }
+
+    #[test]
+    fn rect_structuring_test() {
+        let r = RLE::rect_structuring(3, 2);
+        assert_eq!((r.width(), r.height()), (3, 2));
+        assert_eq!(r.runs(), &[Run::new(0, 2, 0), Run::new(0, 2, 1)]);
+        assert_eq!(RLE::from_runs(3, 2, RLE::rect_structuring_runs::<3, 2>().to_vec()), r);
+        assert_eq!(RLE::hline_structuring(4).runs(), &[Run::new(0, 3, 0)]);
+        assert_eq!(RLE::vline_structuring(2).runs(), &[Run::new(0, 0, 0), Run::new(0, 0, 1)]);
+        // separable: dilating with lines is same as dilating with rectangle
+        let mut rle = RLE::new(9, 9);
+        crate::draw::filled_rect(&mut rle, 4, 3, 1, 2);
+        assert_eq!(
+            rle.dilate(&RLE::hline_structuring(5)).dilate(&RLE::vline_structuring(3)),
+            rle.dilate(&RLE::rect_structuring(5, 3))
+        );
+    }
+
     #[test]
     fn l1_structuring_test() {
         // manhattan distance of 0, no dilation
@@ -575,6 +2331,79 @@ mod test {
         assert_eq!(r, RLE::from(&expected));
     }
 
+    #[test]
+    fn ellipse_structuring_test() {
+        // same as cv::getStructuringElement(MORPH_ELLIPSE, (2 * rx + 1, 2 * ry + 1))
+        assert_eq!(RLE::ellipse_structuring(2, 1).to_image(1), Image::new(5, 3, vec![
+            0, 0, 1, 0, 0,
+            1, 1, 1, 1, 1,
+            0, 0, 1, 0, 0,
+        ]));
+        assert_eq!(RLE::ellipse_structuring(3, 3).to_image(1), Image::new(7, 7, vec![
+            0, 0, 0, 1, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 0,
+            1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1,
+            0, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 1, 0, 0, 0,
+        ]));
+        assert_eq!(RLE::ellipse_structuring(1, 3).to_image(1), Image::new(3, 7, vec![
+            0, 1, 0,
+            1, 1, 1,
+            1, 1, 1,
+            1, 1, 1,
+            1, 1, 1,
+            1, 1, 1,
+            0, 1, 0,
+        ]));
+        assert_eq!(RLE::ellipse_structuring(3, 0), RLE::hline_structuring(7));
+        assert_eq!(RLE::ellipse_structuring(0, 0), RLE::ones(1, 1));
+    }
+
+    #[test]
+    fn disk_structuring_test() {
+        assert_eq!(RLE::disk_structuring(0), RLE::from(&Image::new(1, 1, vec![1])));
+        assert_eq!(RLE::disk_structuring(1), RLE::l1_structuring(1));
+        let r = RLE::disk_structuring(2);
+        let expected = Image::new(
+            5, 5,
+            vec![
+                0, 0, 1, 0, 0,
+                0, 1, 1, 1, 0,
+                1, 1, 1, 1, 1,
+                0, 1, 1, 1, 0,
+                0, 0, 1, 0, 0,
+            ]
+        );
+        assert_eq!(r, RLE::from(&expected));
+        let r = RLE::disk_structuring(3);
+        let expected = Image::new(
+            7, 7,
+            vec![
+                0, 0, 0, 1, 0, 0, 0,
+                0, 1, 1, 1, 1, 1, 0,
+                0, 1, 1, 1, 1, 1, 0,
+                1, 1, 1, 1, 1, 1, 1,
+                0, 1, 1, 1, 1, 1, 0,
+                0, 1, 1, 1, 1, 1, 0,
+                0, 0, 0, 1, 0, 0, 0,
+            ]
+        );
+        assert_eq!(r, RLE::from(&expected));
+    }
+
+    #[test]
+    fn const_structuring_runs_test() {
+        const L1_3: [Run; 7] = RLE::l1_structuring_runs::<7>();
+        const LINF_2: [Run; 5] = RLE::linf_structuring_runs::<5>();
+        const RECT: [Run; 2] = RLE::rect_structuring_runs::<3, 2>();
+        assert_eq!(RLE::from_runs(7, 7, L1_3.to_vec()), RLE::l1_structuring(3));
+        assert_eq!(RLE::from_runs(5, 5, LINF_2.to_vec()), RLE::linf_structuring(2));
+        assert_eq!(RLE::from_runs(1, 1, RLE::l1_structuring_runs::<1>().to_vec()), RLE::l1_structuring(0));
+        assert_eq!(RECT, [Run::new(0, 2, 0), Run::new(0, 2, 1)]);
+    }
+
     #[test]
     fn linf_structuring_test() {
         let r = RLE::linf_structuring(1);
@@ -1,7 +1,10 @@
 use std::cmp::Ordering;
 
 /// Sequence of '1' (or '0') pixels horizontally in binary image.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Coordinates are i32, so images can have up to i32::MAX columns and rows,
+/// arithmetic which can exceed that range (areas, moments, pixel indices) is done
+/// in wider types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Run {
     /// coordinate (column) of left-most (starting) pixel
     pub x_start: i32,
@@ -12,11 +15,17 @@ pub struct Run {
 }
 
 impl Run {
+    /// Create new run.
+    #[inline]
+    pub const fn new(x_start: i32, x_end: i32, y: i32) -> Self {
+        Self { x_start, x_end, y }
+    }
+
     /// Check if self overlaps with other.
     /// Two runs overlap if their y value is same and if their (x_start, x_end)
     /// intervals overlap.
     #[inline]
-    fn overlaps(mut self, other: Self) -> bool {
+    fn overlaps(self, other: Self) -> bool {
         match self.cmp(&other) {
             Ordering::Equal => true,
             Ordering::Less => {
@@ -28,14 +37,24 @@ impl Run {
         }
     }
 
+    /// Check if self overlaps with other or if other starts right after self ends
+    /// (or the other way around), so that they can be merged into one run.
+    #[inline]
+    fn touches(self, other: Self) -> bool {
+        self.overlaps(other) || (self.y == other.y && (
+            (self.x_end < other.x_start && other.x_start - 1 == self.x_end) ||
+            (other.x_end < self.x_start && self.x_start - 1 == other.x_end)
+        ))
+    }
+
     /// Merge two runs.
-    /// If self does not overlap with other then self is returned.
+    /// If self does not overlap or touch other then self is returned.
     #[inline]
     fn merge(self, other: Self) -> Self {
-        if !self.overlaps(other) {
+        if !self.touches(other) {
             return self;
         }
-        return Self {
+        Self {
             x_start: std::cmp::min(self.x_start, other.x_start),
             x_end: std::cmp::max(self.x_end, other.x_end),
             y: self.y
@@ -70,7 +89,7 @@ impl Run {
     }
 
     #[inline]
-    pub fn with_x_start(self, x_start: i32) -> Self {
+    pub const fn with_x_start(self, x_start: i32) -> Self {
         Self {
             x_start, 
             ..self
@@ -78,7 +97,7 @@ impl Run {
     }
 
     #[inline]
-    pub fn with_x_end(self, x_end: i32) -> Self {
+    pub const fn with_x_end(self, x_end: i32) -> Self {
         Self {
             x_end, 
             ..self
@@ -86,31 +105,38 @@ impl Run {
     }
 
     #[inline]
-    pub fn with_y(self, y: i32) -> Self {
+    pub const fn with_y(self, y: i32) -> Self {
         Self {
             y, 
             ..self
         }
     }
 
-    /// Merges overlapping runs given vec
+    /// Sort runs and merge overlapping and touching runs in single pass, so that runs
+    /// are sorted, disjoint and not adjacent. Empty runs (x_start > x_end) are removed.
     pub fn merge_overlapping_runs_mut(runs: &mut Vec<Self>) {
+        runs.retain(|run| run.x_start <= run.x_end);
         runs.sort_unstable();
-        if runs.len() < 2 {
-            return;
-        }
-        let mut res = Vec::new();
-        res.push(runs[0]);
-        for &current in &runs[1..] {
-            let top = res.pop().unwrap();
-            if !current.overlaps(top) {
-                res.push(top);
-                res.push(current);
+        let mut len = 0;
+        for i in 0..runs.len() {
+            let current = runs[i];
+            if len > 0 && runs[len - 1].touches(current) {
+                runs[len - 1] = runs[len - 1].merge(current);
             } else {
-                res.push(top.merge(current));
+                runs[len] = current;
+                len += 1;
             }
         }
-        *runs = res;
+        runs.truncate(len);
+    }
+
+    /// Check if runs are sorted, disjoint and not adjacent (see `merge_overlapping_runs_mut`).
+    /// Gap between runs is computed in i64, so runs at any coordinates can be checked.
+    pub(crate) fn is_normalized(runs: &[Self]) -> bool {
+        runs.iter().all(|run| run.x_start <= run.x_end) &&
+            runs.windows(2).all(|w| {
+                w[0].y < w[1].y || (w[0].y == w[1].y && (w[0].x_end as i64) < w[1].x_start as i64 - 1)
+            })
     }
 }
 
@@ -378,7 +404,7 @@ mod tests {
             )
         );
         assert!(
-            Run {
+            !Run {
                 x_start: 0,
                 x_end: 10,
                 y: 0,
@@ -388,11 +414,11 @@ mod tests {
                     x_end: 12,
                     y: 0,
                 }
-            ) == false
+            )
         );
 
         assert!(
-            Run {
+            !Run {
                 x_start: 0,
                 x_end: 10,
                 y: 1,
@@ -402,11 +428,11 @@ mod tests {
                     x_end: 12,
                     y: 0,
                 }
-            ) == false
+            )
         );
 
         assert!(
-            Run {
+            !Run {
                 x_start: 0,
                 x_end: 10,
                 y: 1,
@@ -416,7 +442,7 @@ mod tests {
                     x_end: 10,
                     y: 0,
                 }
-            ) == false
+            )
         );
     }
 
@@ -461,6 +487,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn merge_overlapping_runs_test() {
+        let mut runs = vec![
+            Run::new(5, 6, 0),
+            Run::new(0, 10, 1),
+            Run::new(0, 2, 0),
+            // touches first run
+            Run::new(3, 4, 0),
+            // inside of previous run in row
+            Run::new(2, 3, 1),
+            Run::new(12, 12, 1),
+            // empty run
+            Run::new(3, 2, 2),
+        ];
+        Run::merge_overlapping_runs_mut(&mut runs);
+        assert_eq!(runs, vec![Run::new(0, 6, 0), Run::new(0, 10, 1), Run::new(12, 12, 1)]);
+        assert!(Run::is_normalized(&runs));
+        assert!(!Run::is_normalized(&[Run::new(0, 2, 0), Run::new(3, 4, 0)]));
+        assert!(!Run::is_normalized(&[Run::new(0, 2, 1), Run::new(0, 2, 0)]));
+        // x_start - 1 of second run does not fit in i32
+        assert!(!Run::is_normalized(&[Run::new(i32::MIN, i32::MIN, 0), Run::new(i32::MIN, 0, 0)]));
+        assert!(Run::is_normalized(&[Run::new(i32::MIN, i32::MIN, 0), Run::new(i32::MIN + 2, 0, 0)]));
+    }
+
     #[test]
     fn intersects_test() {
         {
@@ -0,0 +1,195 @@
+use super::RLE;
+
+/// Sequence of masks (for example one per video frame) with same dimensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskSequence {
+    /// width of every frame
+    width: usize,
+    /// height of every frame
+    height: usize,
+    /// masks of frames
+    frames: Vec<RLE>,
+}
+
+/// Difference between mask and mask of previous frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskDelta {
+    /// pixels which are set in current frame but not in previous
+    pub added: RLE,
+    /// pixels which are set in previous frame but not in current
+    pub removed: RLE,
+}
+
+impl MaskSequence {
+    /// Create empty sequence of frames with given dimensions.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, frames: Vec::new() }
+    }
+
+    /// Create sequence from given frames.
+    /// If dimensions of frames are not same as given dimensions this method will panic.
+    pub fn from_frames(width: usize, height: usize, frames: Vec<RLE>) -> Self {
+        for frame in &frames {
+            assert!(frame.width() == width && frame.height() == height);
+        }
+        Self { width, height, frames }
+    }
+
+    /// Add frame at the end of sequence.
+    /// If dimensions of frame are not same as dimensions of sequence this method will panic.
+    pub fn push(&mut self, frame: RLE) {
+        assert!(frame.width() == self.width && frame.height() == self.height);
+        self.frames.push(frame);
+    }
+
+    /// Get all frames.
+    pub fn frames(&self) -> &[RLE] {
+        &self.frames
+    }
+
+    /// Get number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Check if sequence has no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Get frame width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get frame height.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get frames in window [t - radius, t + radius] clipped to sequence.
+    fn window(&self, t: usize, radius: usize) -> Vec<&RLE> {
+        let start = t.saturating_sub(radius);
+        let end = std::cmp::min(self.frames.len(), t + radius + 1);
+        self.frames[start..end].iter().collect()
+    }
+
+    /// Filter each frame so that pixel is set if it is set in at least k frames
+    /// of window [t - radius, t + radius] (window is clipped at sequence ends).
+    pub fn temporal_majority(&self, radius: usize, k: usize) -> Self {
+        let frames = (0..self.frames.len())
+            .map(|t| RLE::vote(&self.window(t, radius), k))
+            .collect();
+        Self { width: self.width, height: self.height, frames }
+    }
+
+    /// Temporal median filter, for binary masks this means pixel is set if it is set in
+    /// more than half of frames of window [t - radius, t + radius]
+    /// (window is clipped at sequence ends).
+    pub fn temporal_median(&self, radius: usize) -> Self {
+        let frames = (0..self.frames.len())
+            .map(|t| {
+                let window = self.window(t, radius);
+                RLE::vote(&window, window.len() / 2 + 1)
+            })
+            .collect();
+        Self { width: self.width, height: self.height, frames }
+    }
+
+    /// Temporal dilation: pixel is set in frame t if it is set in any frame
+    /// of window [t - radius, t + radius].
+    pub fn temporal_dilate(&self, radius: usize) -> Self {
+        self.temporal_majority(radius, 1)
+    }
+
+    /// Encode every frame as difference to previous frame (first frame is compared with
+    /// empty mask). Masks of consecutive frames are usually similar so differences are
+    /// much smaller than frames.
+    pub fn to_deltas(&self) -> Vec<MaskDelta> {
+        let mut previous = RLE::new(self.width, self.height);
+        let mut deltas = Vec::with_capacity(self.frames.len());
+        for frame in &self.frames {
+            deltas.push(MaskDelta {
+                added: frame - &previous,
+                removed: &previous - frame,
+            });
+            previous = frame.clone();
+        }
+        deltas
+    }
+
+    /// Decode sequence from differences created with `to_deltas`.
+    /// If dimensions of deltas are not same as given dimensions this method will panic.
+    pub fn from_deltas(width: usize, height: usize, deltas: &[MaskDelta]) -> Self {
+        let mut frames: Vec<RLE> = Vec::with_capacity(deltas.len());
+        let mut previous = RLE::new(width, height);
+        for delta in deltas {
+            let frame = &(&previous - &delta.removed) | &delta.added;
+            frames.push(frame.clone());
+            previous = frame;
+        }
+        Self::from_frames(width, height, frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    fn frame(data: &[u8]) -> RLE {
+        RLE::from(&Image::new(4, 1, data.to_vec()))
+    }
+
+    fn sequence() -> MaskSequence {
+        MaskSequence::from_frames(4, 1, vec![
+            frame(&[1, 1, 0, 0]),
+            frame(&[1, 0, 0, 1]),
+            frame(&[1, 1, 0, 0]),
+            frame(&[0, 1, 1, 0]),
+        ])
+    }
+
+    #[test]
+    fn temporal_median_test() {
+        let filtered = sequence().temporal_median(1);
+        assert_eq!(filtered.frames(), &[
+            // window of 2 frames, needs both
+            frame(&[1, 0, 0, 0]),
+            frame(&[1, 1, 0, 0]),
+            frame(&[1, 1, 0, 0]),
+            // window of 2 frames, needs both
+            frame(&[0, 1, 0, 0]),
+        ]);
+    }
+
+    #[test]
+    fn temporal_majority_test() {
+        let filtered = sequence().temporal_majority(3, 3);
+        assert_eq!(filtered.frames()[0], frame(&[1, 1, 0, 0]));
+        let filtered = sequence().temporal_majority(1, 0);
+        assert_eq!(filtered.frames()[0], frame(&[1, 1, 1, 1]));
+    }
+
+    #[test]
+    fn temporal_dilate_test() {
+        let dilated = sequence().temporal_dilate(1);
+        assert_eq!(dilated.frames(), &[
+            frame(&[1, 1, 0, 1]),
+            frame(&[1, 1, 0, 1]),
+            frame(&[1, 1, 1, 1]),
+            frame(&[1, 1, 1, 0]),
+        ]);
+    }
+
+    #[test]
+    fn delta_round_trip_test() {
+        let seq = sequence();
+        let deltas = seq.to_deltas();
+        assert_eq!(deltas[1], MaskDelta {
+            added: frame(&[0, 0, 0, 1]),
+            removed: frame(&[0, 1, 0, 0]),
+        });
+        assert_eq!(MaskSequence::from_deltas(4, 1, &deltas), seq);
+    }
+}
@@ -0,0 +1,182 @@
+//! Compact binary serialization of `RLE`.
+//!
+//! Layout (version 1), all integers are unsigned LEB128 varints:
+//!
+//! | field     | size    | description                               |
+//! |-----------|---------|-------------------------------------------|
+//! | magic     | 4 bytes | `b"RLEM"`                                 |
+//! | version   | 1 byte  | format version, currently 1               |
+//! | width     | varint  | width of image                            |
+//! | height    | varint  | height of image                           |
+//! | run count | varint  | number of runs which follow               |
+//! | runs      | varints | 3 varints per run, see below              |
+//!
+//! Runs are written in canonical form (sorted, clipped, merged). Every run is stored as
+//! difference of its row and row of previous run (row 0 for first run), number of
+//! 0 pixels between previous run in same row (or column 0 for first run of row) and
+//! start of run, and length of run minus 1.
+use super::{Run, RleError, RLE};
+
+const MAGIC: &[u8; 4] = b"RLEM";
+const VERSION: u8 = 1;
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Read varint from start of data and advance data past it.
+fn read_varint(data: &mut &[u8]) -> Result<u64, RleError> {
+    let mut value = 0u64;
+    for (i, &byte) in data.iter().enumerate().take(10) {
+        let bits = (byte & 0x7f) as u64;
+        if i == 9 && bits > 1 {
+            return Err(RleError::InvalidEncoding);
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(RleError::InvalidEncoding)
+}
+
+/// Read varint which has to be smaller than limit.
+fn read_bounded(data: &mut &[u8], limit: u64) -> Result<u64, RleError> {
+    let value = read_varint(data)?;
+    if value >= limit {
+        return Err(RleError::InvalidEncoding);
+    }
+    Ok(value)
+}
+
+impl RLE {
+    /// Serialize to compact binary format described in `serialize` module docs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let runs = self.canonical_runs();
+        let mut out = Vec::with_capacity(16 + 3 * runs.len());
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        write_varint(&mut out, self.width as u64);
+        write_varint(&mut out, self.height as u64);
+        write_varint(&mut out, runs.len() as u64);
+        let (mut y, mut x) = (0, 0);
+        for run in runs.iter() {
+            if run.y != y {
+                x = 0;
+            }
+            write_varint(&mut out, (run.y - y) as u64);
+            write_varint(&mut out, (run.x_start - x) as u64);
+            write_varint(&mut out, (run.x_end - run.x_start) as u64);
+            y = run.y;
+            x = run.x_end + 1;
+        }
+        out
+    }
+
+    /// Deserialize from binary format created with `to_bytes`.
+    /// Returns `RleError::UnsupportedVersion` for unknown format version and
+    /// `RleError::InvalidEncoding` if data is truncated, has trailing bytes or
+    /// describes runs outside of image.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, RleError> {
+        if data.len() < MAGIC.len() + 1 || &data[..MAGIC.len()] != MAGIC {
+            return Err(RleError::InvalidEncoding);
+        }
+        let version = data[MAGIC.len()];
+        if version != VERSION {
+            return Err(RleError::UnsupportedVersion(version));
+        }
+        let mut data = &data[MAGIC.len() + 1..];
+        let limit = i32::MAX as u64 + 1;
+        let width = read_bounded(&mut data, limit)?;
+        let height = read_bounded(&mut data, limit)?;
+        let count = read_varint(&mut data)?;
+        // every run needs at least 3 bytes, so allocation is bounded by input size
+        if count > data.len() as u64 / 3 {
+            return Err(RleError::InvalidEncoding);
+        }
+        let mut runs = Vec::with_capacity(count as usize);
+        let (mut y, mut x) = (0u64, 0u64);
+        for i in 0..count {
+            let dy = read_bounded(&mut data, height - y)?;
+            if dy > 0 {
+                x = 0;
+            }
+            y += dy;
+            let gap = read_bounded(&mut data, width.saturating_sub(x))?;
+            if i > 0 && dy == 0 && gap == 0 {
+                // run would touch previous run in same row
+                return Err(RleError::InvalidEncoding);
+            }
+            let x_start = x + gap;
+            let x_end = x_start + read_bounded(&mut data, width - x_start)?;
+            runs.push(Run::new(x_start as _, x_end as _, y as _));
+            x = x_end + 1;
+        }
+        if !data.is_empty() {
+            return Err(RleError::InvalidEncoding);
+        }
+        Ok(Self::from_runs(width as usize, height as usize, runs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn bytes_layout_test() {
+        let rle = RLE::from(&Image::new(5, 3, vec![
+            0, 1, 1, 0, 1,
+            0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1,
+        ]));
+        assert_eq!(rle.to_bytes(), vec![
+            b'R', b'L', b'E', b'M', 1,
+            5, 3, 3,
+            0, 1, 1,
+            0, 1, 0,
+            2, 0, 4,
+        ]);
+        assert_eq!(RLE::from_bytes(&rle.to_bytes()), Ok(rle));
+    }
+
+    #[test]
+    fn bytes_round_trip_test() {
+        let mut rle = RLE::new(1000, 300);
+        crate::draw::filled_rect(&mut rle, 10, 200, 900, 50);
+        crate::draw::filled_rect(&mut rle, 950, 0, 50, 300);
+        let bytes = rle.to_bytes();
+        assert_eq!(RLE::from_bytes(&bytes), Ok(rle));
+        // runs are written in canonical form
+        let overlapping = RLE::from_runs(6, 2, vec![Run::new(3, 5, 1), Run::new(0, 1, 0), Run::new(1, 4, 1)]);
+        let decoded = RLE::from_bytes(&overlapping.to_bytes()).unwrap();
+        assert_eq!(decoded.runs(), &[Run::new(0, 1, 0), Run::new(1, 5, 1)]);
+        assert_eq!(RLE::from_bytes(&RLE::new(0, 0).to_bytes()), Ok(RLE::new(0, 0)));
+    }
+
+    #[test]
+    fn bytes_errors_test() {
+        let rle = RLE::ones(4, 4);
+        let bytes = rle.to_bytes();
+        assert_eq!(RLE::from_bytes(&bytes[..bytes.len() - 1]), Err(RleError::InvalidEncoding));
+        assert_eq!(RLE::from_bytes(&[bytes.as_slice(), &[0]].concat()), Err(RleError::InvalidEncoding));
+        assert_eq!(RLE::from_bytes(b"RLE"), Err(RleError::InvalidEncoding));
+        assert_eq!(RLE::from_bytes(b"RLEM\x02"), Err(RleError::UnsupportedVersion(2)));
+        // run outside of image
+        assert_eq!(RLE::from_bytes(b"RLEM\x01\x04\x04\x01\x00\x02\x02"), Err(RleError::InvalidEncoding));
+        // second run touches first one
+        assert_eq!(RLE::from_bytes(b"RLEM\x01\x04\x04\x02\x00\x00\x00\x00\x00\x00"), Err(RleError::InvalidEncoding));
+        // run count larger than data
+        assert_eq!(RLE::from_bytes(b"RLEM\x01\x04\x04\x7f\x00\x00\x00"), Err(RleError::InvalidEncoding));
+    }
+}
@@ -0,0 +1,283 @@
+//! Lazy set operations on runs, similar to `RLE::flip_bits_iter`.
+//! Inputs are iterators of normalized runs (sorted, disjoint and not adjacent, as
+//! runs of every `RLE`) and outputs are again normalized, so operations can be chained
+//! without allocating intermediate RLEs and result collected with
+//! `RLE::from_sorted_run_iter`.
+//!
+//! ```
+//! use rle_morph::{difference_iter, intersect_iter, union_iter, RLE};
+//!
+//! let a = RLE::from_raw_data(4, 1, &[1, 1, 1, 0]);
+//! let b = RLE::from_raw_data(4, 1, &[0, 1, 1, 1]);
+//! let c = RLE::from_raw_data(4, 1, &[1, 0, 0, 0]);
+//! let runs = union_iter(intersect_iter(a.runs().iter().copied(), b.runs().iter().copied()), c.runs().iter().copied());
+//! assert_eq!(RLE::from_sorted_run_iter(4, 1, runs), &(&a & &b) | &c);
+//! let runs = difference_iter(a.runs().iter().copied(), b.runs().iter().copied());
+//! assert_eq!(RLE::from_sorted_run_iter(4, 1, runs), &a - &b);
+//! ```
+use std::iter::Peekable;
+
+use super::{Run, RLE};
+
+/// Check if run a is before run b and they do not overlap.
+fn before(a: &Run, b: &Run) -> bool {
+    a.y < b.y || (a.y == b.y && a.x_end < b.x_start)
+}
+
+/// Iterator of runs of union of two sets of runs, see `union_iter`.
+pub struct UnionIter<A: Iterator<Item = Run>, B: Iterator<Item = Run>> {
+    a: Peekable<A>,
+    b: Peekable<B>,
+}
+
+/// Get runs of pixels which are in a or in b.
+pub fn union_iter<A, B>(a: A, b: B) -> UnionIter<A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator<Item = Run>,
+    B: IntoIterator<Item = Run>,
+{
+    UnionIter { a: a.into_iter().peekable(), b: b.into_iter().peekable() }
+}
+
+impl<A: Iterator<Item = Run>, B: Iterator<Item = Run>> UnionIter<A, B> {
+    /// Take smaller of next runs of a and b.
+    fn next_smaller(&mut self) -> Option<Run> {
+        match (self.a.peek(), self.b.peek()) {
+            (Some(ra), Some(rb)) if rb < ra => self.b.next(),
+            (Some(_), _) => self.a.next(),
+            (None, _) => self.b.next(),
+        }
+    }
+}
+
+impl<A: Iterator<Item = Run>, B: Iterator<Item = Run>> Iterator for UnionIter<A, B> {
+    type Item = Run;
+    fn next(&mut self) -> Option<Run> {
+        let mut current = self.next_smaller()?;
+        loop {
+            let touches = |run: &Run| run.y == current.y && (run.x_start as i64) <= current.x_end as i64 + 1;
+            let next = match (self.a.peek(), self.b.peek()) {
+                (Some(ra), _) if touches(ra) => self.a.next(),
+                (_, Some(rb)) if touches(rb) => self.b.next(),
+                _ => return Some(current),
+            };
+            if let Some(next) = next {
+                current.x_end = std::cmp::max(current.x_end, next.x_end);
+            }
+        }
+    }
+}
+
+/// Iterator of runs of intersection of two sets of runs, see `intersect_iter`.
+pub struct IntersectIter<A: Iterator<Item = Run>, B: Iterator<Item = Run>> {
+    a: Peekable<A>,
+    b: Peekable<B>,
+}
+
+/// Get runs of pixels which are in both a and b.
+pub fn intersect_iter<A, B>(a: A, b: B) -> IntersectIter<A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator<Item = Run>,
+    B: IntoIterator<Item = Run>,
+{
+    IntersectIter { a: a.into_iter().peekable(), b: b.into_iter().peekable() }
+}
+
+impl<A: Iterator<Item = Run>, B: Iterator<Item = Run>> Iterator for IntersectIter<A, B> {
+    type Item = Run;
+    fn next(&mut self) -> Option<Run> {
+        loop {
+            let (ra, rb) = (*self.a.peek()?, *self.b.peek()?);
+            if before(&ra, &rb) {
+                self.a.next();
+            } else if before(&rb, &ra) {
+                self.b.next();
+            } else {
+                // runs overlap, run which ends first can not overlap anything else
+                if ra.x_end <= rb.x_end {
+                    self.a.next();
+                } else {
+                    self.b.next();
+                }
+                return Some(Run::new(
+                    std::cmp::max(ra.x_start, rb.x_start),
+                    std::cmp::min(ra.x_end, rb.x_end),
+                    ra.y,
+                ));
+            }
+        }
+    }
+}
+
+/// Iterator of runs of difference of two sets of runs, see `difference_iter`.
+pub struct DifferenceIter<A: Iterator<Item = Run>, B: Iterator<Item = Run>> {
+    a: A,
+    b: Peekable<B>,
+    /// part of current run of a which is not yet returned
+    current: Option<Run>,
+}
+
+/// Get runs of pixels which are in a and not in b.
+pub fn difference_iter<A, B>(a: A, b: B) -> DifferenceIter<A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator<Item = Run>,
+    B: IntoIterator<Item = Run>,
+{
+    DifferenceIter { a: a.into_iter(), b: b.into_iter().peekable(), current: None }
+}
+
+impl<A: Iterator<Item = Run>, B: Iterator<Item = Run>> Iterator for DifferenceIter<A, B> {
+    type Item = Run;
+    fn next(&mut self) -> Option<Run> {
+        loop {
+            let current = match self.current {
+                Some(run) => run,
+                None => self.a.next()?,
+            };
+            while let Some(rb) = self.b.peek() {
+                if before(rb, &current) {
+                    self.b.next();
+                } else {
+                    break;
+                }
+            }
+            let rb = match self.b.peek() {
+                Some(rb) if !before(&current, rb) => *rb,
+                _ => {
+                    self.current = None;
+                    return Some(current);
+                }
+            };
+            // rb overlaps current, keep part of current after rb
+            self.current = if rb.x_end < current.x_end {
+                Some(current.with_x_start(rb.x_end + 1))
+            } else {
+                None
+            };
+            if current.x_start < rb.x_start {
+                return Some(current.with_x_end(rb.x_start - 1));
+            }
+        }
+    }
+}
+
+impl RLE {
+    /// Create RLE from iterator of runs which are already sorted, disjoint and not
+    /// adjacent (for example output of `union_iter`). If they are not, runs are
+    /// normalized as in `from_runs`.
+    pub fn from_sorted_run_iter<I: IntoIterator<Item = Run>>(width: usize, height: usize, runs: I) -> Self {
+        let runs: Vec<Run> = runs.into_iter().collect();
+        if Run::is_normalized(&runs) {
+            Self { width, height, runs }
+        } else {
+            Self::from_runs(width, height, runs)
+        }
+    }
+}
+
+/// Overlap predicates, computed with iterators above so no intermediate RLE is created
+/// and `intersects`, `is_disjoint` and `is_subset_of` stop at first decisive run.
+/// Only pixels inside of image are compared.
+/// If dimensions of self and other are not same these methods will panic.
+impl RLE {
+    /// Check if self and other have at least one common pixel.
+    pub fn intersects(&self, other: &Self) -> bool {
+        assert!(self.width == other.width && self.height == other.height);
+        let (a, b) = (self.canonical_runs(), other.canonical_runs());
+        intersect_iter(a.iter().copied(), b.iter().copied()).next().is_some()
+    }
+
+    /// Check if self and other have no common pixel.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        !self.intersects(other)
+    }
+
+    /// Number of pixels set in both self and other.
+    pub fn intersection_area(&self, other: &Self) -> usize {
+        assert!(self.width == other.width && self.height == other.height);
+        let (a, b) = (self.canonical_runs(), other.canonical_runs());
+        intersect_iter(a.iter().copied(), b.iter().copied())
+            .map(|run| (run.x_end - run.x_start + 1) as usize)
+            .sum()
+    }
+
+    /// Check if all pixels of self are set in other.
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        assert!(self.width == other.width && self.height == other.height);
+        let (a, b) = (self.canonical_runs(), other.canonical_runs());
+        difference_iter(a.iter().copied(), b.iter().copied()).next().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util, Image};
+
+    fn noise(w: usize, h: usize, seed: u32) -> RLE {
+        RLE::from(&test_util::noise(w, h, seed | 1, |state| state & 3 != 0))
+    }
+
+    #[test]
+    fn set_iter_test() {
+        for seed in 1..6 {
+            let (a, b) = (noise(23, 11, seed), noise(23, 11, 100 + seed));
+            let (ra, rb) = (a.runs().iter().copied(), b.runs().iter().copied());
+            let union: Vec<Run> = union_iter(ra.clone(), rb.clone()).collect();
+            assert_eq!(union, (&a | &b).runs());
+            let intersection: Vec<Run> = intersect_iter(ra.clone(), rb.clone()).collect();
+            assert_eq!(intersection, (&a & &b).runs());
+            let difference: Vec<Run> = difference_iter(ra.clone(), rb.clone()).collect();
+            assert_eq!(difference, (&a - &b).runs());
+            assert_eq!(difference_iter(rb.clone(), ra.clone()).collect::<Vec<Run>>(), (&b - &a).runs());
+        }
+    }
+
+    #[test]
+    fn overlap_predicates_test() {
+        let a = RLE::from(&Image::new(5, 3, vec![
+            1, 1, 0, 0, 0,
+            0, 1, 1, 0, 0,
+            0, 0, 0, 0, 1,
+        ]));
+        let b = RLE::from(&Image::new(5, 3, vec![
+            0, 0, 1, 1, 0,
+            0, 0, 1, 1, 1,
+            1, 0, 0, 0, 1,
+        ]));
+        assert!(a.intersects(&b) && !a.is_disjoint(&b));
+        assert_eq!(a.intersection_area(&b), 2);
+        assert!(!a.is_subset_of(&b));
+        let c = &a & &b;
+        assert!(c.is_subset_of(&a) && c.is_subset_of(&b));
+        assert!(RLE::new(5, 3).is_subset_of(&c) && RLE::new(5, 3).is_disjoint(&a));
+        assert!((&a - &b).is_disjoint(&b));
+        // parts of runs outside of image are ignored
+        let outside = RLE::from_runs(5, 3, vec![Run::new(5, 9, 0), Run::new(-4, -1, 1)]);
+        assert!(outside.is_disjoint(&RLE::ones(5, 3)));
+        assert!(a.dilate(&RLE::linf_structuring(1)).intersects(&b));
+        assert_eq!(a.dilate(&RLE::linf_structuring(1)).intersection_area(&RLE::ones(5, 3)),
+            a.dilate(&RLE::linf_structuring(1)).area());
+    }
+
+    #[test]
+    fn set_iter_edge_test() {
+        let a = [Run::new(0, 3, 0), Run::new(5, 9, 0), Run::new(2, 2, 2)];
+        let b = [Run::new(4, 4, 0), Run::new(9, 12, 0), Run::new(-3, 1, 1)];
+        assert_eq!(union_iter(a.iter().copied(), b.iter().copied()).collect::<Vec<Run>>(), vec![
+            Run::new(0, 12, 0),
+            Run::new(-3, 1, 1),
+            Run::new(2, 2, 2),
+        ]);
+        assert_eq!(intersect_iter(a.iter().copied(), b.iter().copied()).collect::<Vec<Run>>(), vec![Run::new(9, 9, 0)]);
+        assert_eq!(difference_iter(a.iter().copied(), b.iter().copied()).collect::<Vec<Run>>(), vec![
+            Run::new(0, 3, 0),
+            Run::new(5, 8, 0),
+            Run::new(2, 2, 2),
+        ]);
+        assert_eq!(union_iter(a.iter().copied(), std::iter::empty()).collect::<Vec<Run>>(), a.to_vec());
+        assert_eq!(intersect_iter(a.iter().copied(), std::iter::empty()).count(), 0);
+        let rle = RLE::from_sorted_run_iter(3, 1, vec![Run::new(2, 2, 0), Run::new(0, 1, 0)]);
+        assert_eq!(rle.runs(), &[Run::new(0, 2, 0)]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Skeletonization and analysis of skeletons (1 pixel wide lines) of binary images.
+use super::{Run, RLE};
+
+/// Options of thinning, see `RLE::skeletonize_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThinningOptions {
+    /// maximum number of iterations (each one has two subiterations), None for no limit
+    pub max_iterations: Option<usize>,
+    /// stop when at most this many pixels are removed in one iteration,
+    /// 0 thins until nothing can be removed
+    pub min_removed: usize,
+}
+
+/// Get runs in row y, runs must be sorted.
+fn row_runs(runs: &[Run], y: i32) -> &[Run] {
+    let start = runs.partition_point(|run| run.y < y);
+    let end = start + runs[start..].partition_point(|run| run.y == y);
+    &runs[start..end]
+}
+
+/// Check if pixel x is set in row with given runs.
+fn is_set(row: &[Run], x: i32) -> bool {
+    let i = row.partition_point(|run| run.x_end < x);
+    i < row.len() && row[i].x_start <= x
+}
+
+/// 8 neighbors of pixel in circular (clockwise) order starting from top left,
+/// given runs of rows above, same and below pixel.
+fn neighborhood(above: &[Run], row: &[Run], below: &[Run], x: i32) -> [bool; 8] {
+    [
+        is_set(above, x - 1),
+        is_set(above, x),
+        is_set(above, x + 1),
+        is_set(row, x + 1),
+        is_set(below, x + 1),
+        is_set(below, x),
+        is_set(below, x - 1),
+        is_set(row, x - 1),
+    ]
+}
+
+/// Number of 0 -> 1 transitions when going around neighborhood (crossing number),
+/// which is number of separate branches touching pixel.
+fn crossings(n: &[bool; 8]) -> usize {
+    (0..8).filter(|&i| !n[i] && n[(i + 1) % 8]).count()
+}
+
+/// Get all set pixels of skeleton for which predicate on their neighborhood is true.
+fn filter_pixels<F: Fn(&[bool; 8]) -> bool>(skeleton: &RLE, predicate: F) -> RLE {
+    let runs = skeleton.canonical_runs();
+    let mut res: Vec<Run> = Vec::new();
+    for run in runs.iter() {
+        let above = row_runs(&runs, run.y - 1);
+        let row = row_runs(&runs, run.y);
+        let below = row_runs(&runs, run.y + 1);
+        for x in run.x_start..=run.x_end {
+            if predicate(&neighborhood(above, row, below, x)) {
+                match res.last_mut() {
+                    Some(last) if last.y == run.y && last.x_end + 1 == x => last.x_end = x,
+                    _ => res.push(Run::new(x, x, run.y)),
+                }
+            }
+        }
+    }
+    RLE::from_runs(skeleton.width(), skeleton.height(), res)
+}
+
+/// Get endpoints of skeleton, pixels with exactly one 8-connected neighbor.
+pub fn endpoints(skeleton: &RLE) -> RLE {
+    filter_pixels(skeleton, |n| n.iter().filter(|&&set| set).count() == 1)
+}
+
+/// Get branch points of skeleton, pixels where at least 3 branches meet
+/// (crossing number of 8-neighborhood is at least 3).
+pub fn branch_points(skeleton: &RLE) -> RLE {
+    filter_pixels(skeleton, |n| crossings(n) >= 3)
+}
+
+/// Number of set neighbors.
+fn neighbors(n: &[bool; 8]) -> usize {
+    n.iter().filter(|&&set| set).count()
+}
+
+/// Zhang-Suen condition for removing pixel in given subiteration (0 or 1).
+/// Neighborhood order is NW, N, NE, E, SE, S, SW, W.
+fn removable(n: &[bool; 8], subiteration: usize) -> bool {
+    let count = neighbors(n);
+    if !(2..=6).contains(&count) || crossings(n) != 1 {
+        return false;
+    }
+    let (north, east, south, west) = (n[1], n[3], n[5], n[7]);
+    if subiteration == 0 {
+        !(east && south && (north || west))
+    } else {
+        !(north && west && (east || south))
+    }
+}
+
+impl RLE {
+    /// Thin image to 1 pixel wide 8-connected centerlines using Zhang-Suen thinning
+    /// until convergence. Image is never decoded, neighborhoods are read from runs.
+    pub fn skeletonize(&self) -> RLE {
+        self.skeletonize_with(ThinningOptions::default())
+    }
+
+    /// Thin image like `skeletonize` but stop after options.max_iterations iterations
+    /// or when at most options.min_removed pixels are removed in one iteration.
+    pub fn skeletonize_with(&self, options: ThinningOptions) -> RLE {
+        let mut current = RLE::from_runs(self.width(), self.height(), self.canonical_runs().into_owned());
+        let mut iteration = 0;
+        loop {
+            if let Some(max) = options.max_iterations {
+                if iteration >= max {
+                    break;
+                }
+            }
+            let mut removed = 0;
+            for subiteration in 0..2 {
+                let deleted = filter_pixels(&current, |n| removable(n, subiteration));
+                removed += deleted.area();
+                if !deleted.runs().is_empty() {
+                    current = &current - &deleted;
+                }
+            }
+            iteration += 1;
+            if removed <= options.min_removed {
+                break;
+            }
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connectivity, Image};
+
+    fn component_count(rle: &RLE) -> usize {
+        rle.components(Connectivity::Eight).len()
+    }
+
+    fn points(rle: &RLE) -> Vec<(i32, i32)> {
+        rle.runs().iter().flat_map(|run| (run.x_start..=run.x_end).map(move |x| (x, run.y))).collect()
+    }
+
+    #[test]
+    fn endpoints_test() {
+        let skeleton = RLE::from(&Image::new(6, 4, vec![
+            1, 0, 0, 0, 0, 1,
+            0, 1, 0, 0, 1, 0,
+            0, 0, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(points(&endpoints(&skeleton)), vec![(0, 0), (5, 0)]);
+        assert!(endpoints(&RLE::new(3, 3)).runs().is_empty());
+    }
+
+    #[test]
+    fn branch_points_test() {
+        let skeleton = RLE::from(&Image::new(7, 5, vec![
+            0, 0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 1,
+            0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0,
+        ]));
+        assert_eq!(points(&branch_points(&skeleton)), vec![(3, 2)]);
+        // diagonal Y junction
+        let skeleton = RLE::from(&Image::new(5, 5, vec![
+            1, 0, 0, 0, 1,
+            0, 1, 0, 1, 0,
+            0, 0, 1, 0, 0,
+            0, 0, 1, 0, 0,
+            0, 0, 1, 0, 0,
+        ]));
+        assert_eq!(points(&branch_points(&skeleton)), vec![(2, 2)]);
+        // simple line has no branch points
+        let line = RLE::from(&Image::new(4, 1, vec![1, 1, 1, 1]));
+        assert!(branch_points(&line).runs().is_empty());
+    }
+
+    #[test]
+    fn skeletonize_test() {
+        let rle = RLE::from(&Image::new(9, 5, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+        let skeleton = rle.skeletonize();
+        assert_eq!(points(&skeleton), vec![(2, 2), (3, 2), (4, 2), (5, 2)]);
+        assert_eq!(skeleton.skeletonize(), skeleton);
+
+        let mut rle = RLE::new(9, 7);
+        crate::draw::filled_rect(&mut rle, 1, 1, 7, 5);
+        assert_eq!(points(&rle.skeletonize()), vec![(3, 3), (4, 3)]);
+        let limited = |max_iterations, min_removed| rle.skeletonize_with(ThinningOptions { max_iterations, min_removed }).area();
+        assert_eq!(limited(Some(0), 0), 35);
+        assert_eq!(limited(Some(1), 0), 14);
+        // first iteration removes 21 pixels and second one 12
+        assert_eq!(limited(None, 21), 14);
+        assert_eq!(limited(None, 20), 2);
+    }
+
+    #[test]
+    fn skeletonize_keeps_topology_test() {
+        let rle = RLE::from(&Image::new(8, 7, vec![
+            1, 1, 1, 1, 1, 1, 1, 0,
+            1, 1, 1, 1, 1, 1, 1, 0,
+            1, 1, 0, 0, 0, 1, 1, 0,
+            1, 1, 0, 0, 0, 1, 1, 0,
+            1, 1, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0, 0, 1,
+            0, 0, 0, 0, 0, 0, 0, 1,
+        ]));
+        let skeleton = rle.skeletonize();
+        assert_eq!(skeleton.to_image(1), Image::new(8, 7, vec![
+            0, 1, 1, 1, 1, 1, 0, 0,
+            1, 1, 0, 0, 0, 1, 0, 0,
+            1, 0, 0, 0, 0, 1, 0, 0,
+            1, 0, 0, 0, 0, 1, 0, 0,
+            0, 1, 1, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0, 0, 1,
+            0, 0, 0, 0, 0, 0, 0, 1,
+        ]));
+        assert_eq!(component_count(&skeleton), component_count(&rle));
+        assert_eq!(component_count(&!&skeleton), component_count(&!&rle));
+    }
+}
@@ -0,0 +1,92 @@
+//! Morphological smoothing: opening followed by closing, and alternating sequential
+//! filters which repeat it with growing structuring elements.
+use super::{Kernel, RLE};
+
+/// Shape of symmetric structuring elements of growing radius used by `RLE::asf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuringShape {
+    /// square of size 2r + 1, see `RLE::linf_structuring`
+    Square,
+    /// diamond of size 2r + 1, see `RLE::l1_structuring`
+    Diamond,
+    /// disk of size 2r + 1, see `RLE::disk_structuring`
+    Disk,
+}
+
+impl StructuringShape {
+    /// Structuring element of this shape with given radius.
+    pub fn element(self, radius: usize) -> RLE {
+        match self {
+            StructuringShape::Square => RLE::linf_structuring(radius),
+            StructuringShape::Diamond => RLE::l1_structuring(radius),
+            StructuringShape::Disk => RLE::disk_structuring(radius),
+        }
+    }
+}
+
+impl RLE {
+    /// Open and then close with structuring element s, which removes small objects
+    /// and then fills small holes and gaps.
+    pub fn smooth<K: Kernel + ?Sized>(&self, s: &K) -> Self {
+        self.open(s).close(s)
+    }
+
+    /// Alternating sequential filter: smooth (open then close) with structuring
+    /// elements of given shape and radius 1, 2, ..., max_radius. Noise is removed
+    /// gradually, so shapes are distorted less than with one smoothing of largest size.
+    /// Elements are symmetric so they are not reflected, each step is four erosions
+    /// and dilations of one working image.
+    pub fn asf(&self, max_radius: usize, shape: StructuringShape) -> Self {
+        let mut res = self.clone();
+        for radius in 1..=max_radius {
+            let s = shape.element(radius);
+            res = res.erode(&s).dilate(&s).dilate(&s).erode(&s);
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn smooth_test() {
+        let rle = RLE::from(&Image::new(12, 11, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 0, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]));
+        let square = RLE::linf_structuring(1);
+        // isolated pixel is removed and hole is filled, closing near image border
+        // would keep border pixels because erosion treats outside pixels as 1
+        assert_eq!(rle.smooth(&square), RLE::from(&Image::new(12, 11, vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ])));
+        assert_eq!(rle.asf(0, StructuringShape::Square), rle);
+        assert_eq!(rle.asf(1, StructuringShape::Square), rle.smooth(&square));
+        for &shape in &[StructuringShape::Square, StructuringShape::Diamond, StructuringShape::Disk] {
+            let expected = rle.smooth(&shape.element(1)).smooth(&shape.element(2));
+            assert_eq!(rle.asf(2, shape), expected);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use super::{Run, RLE};
+
+/// Shape used for dilation and erosion: set pixels and anchor (origin) point.
+/// Pixel (x, y) of dilation is 1 if any pixel (x + sx - ax, y + sy - ay) of image is 1
+/// where (sx, sy) is set pixel and (ax, ay) is anchor.
+/// Reflection negates offsets (sx - ax, sy - ay) of all pixels and is defined once by
+/// `StructuringElement::reflect`, `reflected` and `RLE::reflect` only differ in
+/// where reflected anchor is stored (see `RLE::reflect`).
+pub trait Kernel {
+    /// runs of set pixels, sorted
+    fn kernel_runs(&self) -> &[Run];
+    /// anchor point (ax, ay)
+    fn anchor(&self) -> (i32, i32);
+
+    /// Reflect through anchor, pixel at offset (dx, dy) from anchor moves to offset
+    /// (-dx, -dy). Works for any size and anchor, used by opening and closing.
+    /// Set pixels are moved to their bounding box which is reflected with
+    /// `StructuringElement::reflect`.
+    fn reflected(&self) -> StructuringElement {
+        let runs = self.kernel_runs();
+        let (ax, ay) = self.anchor();
+        let (min_x, max_x) = runs.iter().fold((i32::MAX, i32::MIN), |(lo, hi), run| (lo.min(run.x_start), hi.max(run.x_end)));
+        let (min_y, max_y) = match (runs.first(), runs.last()) {
+            (Some(first), Some(last)) => (first.y, last.y),
+            _ => return StructuringElement::new(RLE::new(0, 0), 0, 0),
+        };
+        let moved = runs.iter()
+            .map(|run| Run::new(run.x_start - min_x, run.x_end - min_x, run.y - min_y))
+            .collect();
+        let shape = RLE::from_runs((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize, moved);
+        StructuringElement::new(shape, ax - min_x, ay - min_y).reflect()
+    }
+}
+
+/// When `RLE` is used as structuring element its anchor is (width / 2, height / 2).
+impl Kernel for RLE {
+    fn kernel_runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    fn anchor(&self) -> (i32, i32) {
+        ((self.width / 2) as i32, (self.height / 2) as i32)
+    }
+}
+
+/// Structuring element with explicit anchor, so even sized and off-center kernels
+/// give predictable offsets instead of using center of `RLE`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuringElement {
+    /// set pixels
+    shape: RLE,
+    /// anchor point, can also be outside of shape
+    anchor: (i32, i32),
+}
+
+impl StructuringElement {
+    /// Create structuring element from shape with anchor at (ax, ay).
+    pub fn new(shape: RLE, ax: i32, ay: i32) -> Self {
+        let runs = shape.canonical_runs().into_owned();
+        Self { shape: RLE::from_runs(shape.width(), shape.height(), runs), anchor: (ax, ay) }
+    }
+
+    /// Create structuring element from shape with anchor at (width / 2, height / 2),
+    /// which behaves same as shape itself.
+    pub fn centered(shape: RLE) -> Self {
+        let anchor = shape.anchor();
+        Self::new(shape, anchor.0, anchor.1)
+    }
+
+    /// Create structuring element from offsets (dx, dy) of its pixels from anchor, so
+    /// dilation of pixel (x, y) is 1 if any pixel (x + dx, y + dy) of image is 1.
+    /// Duplicate offsets are allowed, element without offsets has no pixels.
+    pub fn from_points(points: &[(i32, i32)]) -> Self {
+        let min_x = points.iter().map(|&(dx, _)| dx).min().unwrap_or(0);
+        let max_x = points.iter().map(|&(dx, _)| dx).max().unwrap_or(-1);
+        let min_y = points.iter().map(|&(_, dy)| dy).min().unwrap_or(0);
+        let max_y = points.iter().map(|&(_, dy)| dy).max().unwrap_or(-1);
+        let runs = points.iter()
+            .map(|&(dx, dy)| Run::new(dx - min_x, dx - min_x, dy - min_y))
+            .collect();
+        let shape = RLE::from_runs((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize, runs);
+        Self { shape, anchor: (-min_x, -min_y) }
+    }
+
+    /// Get set pixels.
+    pub fn shape(&self) -> &RLE {
+        &self.shape
+    }
+
+    /// Get width of shape.
+    pub fn width(&self) -> usize {
+        self.shape.width()
+    }
+
+    /// Get height of shape.
+    pub fn height(&self) -> usize {
+        self.shape.height()
+    }
+
+    /// Reflect through anchor, pixel at offset (dx, dy) from anchor moves to offset
+    /// (-dx, -dy). Shape is mirrored inside of its dimensions, which do not change,
+    /// and anchor (ax, ay) moves to (width - 1 - ax, height - 1 - ay).
+    pub fn reflect(&self) -> Self {
+        let (w, h) = (self.width() as i32, self.height() as i32);
+        let mut runs: Vec<Run> = self.shape.runs().iter()
+            .map(|run| Run::new(w - 1 - run.x_end, w - 1 - run.x_start, h - 1 - run.y))
+            .collect();
+        runs.sort_unstable();
+        Self {
+            shape: RLE::from_runs(self.width(), self.height(), runs),
+            anchor: (w - 1 - self.anchor.0, h - 1 - self.anchor.1),
+        }
+    }
+}
+
+impl StructuringElement {
+    /// Get `RLE` which has same offsets from its anchor (width / 2, height / 2) as
+    /// this element, shape is padded so that anchor is at its center.
+    pub(crate) fn to_centered_rle(&self) -> RLE {
+        let (w, h) = (self.width() as i32, self.height() as i32);
+        let (ax, ay) = self.anchor;
+        let (half_w, half_h) = (ax.max(w - 1 - ax), ay.max(h - 1 - ay));
+        let (dx, dy) = (half_w - ax, half_h - ay);
+        let runs = self.shape.runs().iter()
+            .map(|run| Run::new(run.x_start + dx, run.x_end + dx, run.y + dy))
+            .collect();
+        RLE { width: (2 * half_w + 1) as usize, height: (2 * half_h + 1) as usize, runs }
+    }
+}
+
+impl Kernel for StructuringElement {
+    fn kernel_runs(&self) -> &[Run] {
+        self.shape.runs()
+    }
+
+    fn anchor(&self) -> (i32, i32) {
+        self.anchor
+    }
+}
+
+impl From<RLE> for StructuringElement {
+    fn from(shape: RLE) -> Self {
+        Self::centered(shape)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    fn sample() -> RLE {
+        RLE::from(&Image::new(6, 4, vec![
+            0, 0, 0, 0, 0, 0,
+            0, 0, 1, 1, 0, 0,
+            0, 0, 0, 1, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]))
+    }
+
+    #[test]
+    fn anchor_test() {
+        let rle = sample();
+        let s = RLE::rect_structuring(2, 2);
+        assert_eq!(rle.dilate(&StructuringElement::centered(s.clone())), rle.dilate(&s));
+        // anchor at top left, pixels grow to left and up
+        let se = StructuringElement::new(s.clone(), 0, 0);
+        assert_eq!(rle.dilate(&se).to_image(1), Image::new(6, 4, vec![
+            0, 1, 1, 1, 0, 0,
+            0, 1, 1, 1, 0, 0,
+            0, 0, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]));
+        // anchor at bottom right, pixels grow to right and down
+        let se = StructuringElement::new(s, 1, 1);
+        assert_eq!(rle.dilate(&se).to_image(1), Image::new(6, 4, vec![
+            0, 0, 0, 0, 0, 0,
+            0, 0, 1, 1, 1, 0,
+            0, 0, 1, 1, 1, 0,
+            0, 0, 0, 1, 1, 0,
+        ]));
+        let closed = rle.dilate(&se).erode(&se.reflect());
+        assert_eq!(&rle - &closed, RLE::new(6, 4));
+        // single pixel with anchor outside of shape only translates image
+        let se = StructuringElement::new(RLE::ones(1, 1), -2, 1);
+        assert_eq!(rle.dilate(&se), rle.translate(-2, 1, false));
+        // pixels outside of image are 1 so they are moved in by erosion
+        assert_eq!(rle.erode(&se), !&(!&rle).translate(-2, 1, true));
+    }
+
+    #[test]
+    fn reflect_test() {
+        let se = StructuringElement::new(RLE::from(&Image::new(3, 2, vec![
+            1, 1, 0,
+            0, 0, 1,
+        ])), 0, 0);
+        let reflected = se.reflect();
+        assert_eq!(reflected.anchor(), (2, 1));
+        assert_eq!(reflected.shape().to_image(1), Image::new(3, 2, vec![
+            1, 0, 0,
+            0, 1, 1,
+        ]));
+        assert_eq!(reflected.reflect(), se);
+        // opening with exact reflection is subset of image
+        let rle = sample();
+        let opened = rle.erode(&se).dilate(&se.reflect());
+        assert_eq!(&opened - &rle, RLE::new(6, 4));
+        // all three reflections give same offsets, also for even sized shapes
+        let shape = se.shape().clone();
+        let centered = StructuringElement::centered(shape.clone()).reflect();
+        assert_eq!(rle.dilate(&shape.reflect()), rle.dilate(&centered));
+        assert_eq!(rle.dilate(&shape.reflected()), rle.dilate(&centered));
+        assert_eq!(shape.reflect(), RLE::from(&Image::new(3, 3, vec![
+            0, 0, 0,
+            1, 0, 0,
+            0, 1, 1,
+        ])));
+    }
+
+    /// Dense dilation (or erosion) with anchor, pixels outside of image are 0 for
+    /// dilation and 1 for erosion.
+    fn naive(img: &Image, s: &Image, (ax, ay): (i32, i32), dilate: bool) -> Image {
+        let (w, h) = (img.w() as i32, img.h() as i32);
+        let mut out = Image::empty(img.w(), img.h());
+        for y in 0..h {
+            for x in 0..w {
+                let mut values = (0..s.h() as i32)
+                    .flat_map(|sy| (0..s.w() as i32).map(move |sx| (sx, sy)))
+                    .filter(|&(sx, sy)| s[sy as usize][sx as usize] > 0)
+                    .map(|(sx, sy)| {
+                        let (px, py) = (x + sx - ax, y + sy - ay);
+                        if px < 0 || py < 0 || px >= w || py >= h {
+                            !dilate
+                        } else {
+                            img[py as usize][px as usize] > 0
+                        }
+                    });
+                let set = if dilate { values.any(|v| v) } else { values.all(|v| v) };
+                out[y as usize][x as usize] = set as u8;
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn even_and_off_center_test() {
+        let img = Image::new(7, 6, vec![
+            0, 1, 1, 0, 0, 0, 1,
+            0, 1, 1, 1, 0, 0, 0,
+            0, 0, 0, 1, 1, 0, 0,
+            1, 1, 0, 1, 1, 1, 0,
+            0, 1, 0, 0, 1, 1, 1,
+            0, 0, 0, 0, 0, 1, 1,
+        ]);
+        let rle = RLE::from(&img);
+        let shapes = vec![
+            RLE::rect_structuring(2, 2),
+            RLE::from(&Image::new(4, 1, vec![1, 0, 1, 1])),
+            RLE::from(&Image::new(2, 3, vec![
+                1, 0,
+                1, 1,
+                0, 1,
+            ])),
+        ];
+        for shape in &shapes {
+            let s = shape.to_image(1);
+            for &anchor in &[(0, 0), (1, 0), (1, 1), (shape.width() as i32 - 1, shape.height() as i32 - 1), (-1, 2)] {
+                let se = StructuringElement::new(shape.clone(), anchor.0, anchor.1);
+                let reflected = se.reflected();
+                assert_eq!(rle.dilate(&reflected.reflected()), rle.dilate(&se));
+                assert_eq!(rle.dilate(&se).to_image(1), naive(&img, &s, anchor, true));
+                assert_eq!(rle.erode(&se).to_image(1), naive(&img, &s, anchor, false));
+                let (rs, ranchor) = (reflected.shape().to_image(1), reflected.anchor());
+                let opened = naive(&naive(&img, &s, anchor, false), &rs, ranchor, true);
+                let closed = naive(&naive(&img, &s, anchor, true), &rs, ranchor, false);
+                assert_eq!(rle.open(&se).to_image(1), opened);
+                assert_eq!(rle.close(&se).to_image(1), closed);
+                assert_eq!(&rle.open(&se) - &rle, RLE::new(7, 6));
+                assert_eq!(&rle - &rle.close(&se), RLE::new(7, 6));
+            }
+            // RLE uses (width / 2, height / 2) as anchor
+            let centered = StructuringElement::centered(shape.clone());
+            assert_eq!(rle.open(shape), rle.open(&centered));
+            assert_eq!(rle.close(shape), rle.close(&centered));
+        }
+    }
+
+    #[test]
+    fn from_points_test() {
+        // ring of 8 neighbors without center
+        let ring = StructuringElement::from_points(&[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)]);
+        assert_eq!(ring.anchor(), (1, 1));
+        assert_eq!(ring.shape().to_image(1), Image::new(3, 3, vec![
+            1, 1, 1,
+            1, 0, 1,
+            1, 1, 1,
+        ]));
+        let rle = sample();
+        // every pixel of sample has neighbor which is set
+        assert_eq!(rle.dilate(&ring), rle.dilate(&RLE::linf_structuring(1)));
+        assert_eq!(RLE::ones(1, 1).dilate(&ring), RLE::new(1, 1));
+        // directional probe which does not contain anchor
+        let probe = StructuringElement::from_points(&[(2, 0), (3, 0), (2, 0)]);
+        assert_eq!(probe.anchor(), (-2, 0));
+        assert_eq!(probe.shape(), &RLE::hline_structuring(2));
+        assert_eq!(rle.dilate(&probe), rle.translate(-3, 0, false) | rle.translate(-2, 0, false));
+        let empty = StructuringElement::from_points(&[]);
+        assert_eq!(rle.dilate(&empty), RLE::new(6, 4));
+    }
+}
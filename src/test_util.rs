@@ -0,0 +1,31 @@
+//! Helpers shared by unit tests.
+use super::Image;
+
+/// Xorshift32 generator, so tests get reproducible pseudo random data without `rand`.
+pub(crate) struct XorShift(u32);
+
+impl XorShift {
+    /// Create generator from seed, seed 0 is replaced with 1 because zero state never
+    /// changes.
+    pub(crate) fn new(seed: u32) -> Self {
+        XorShift(if seed == 0 { 1 } else { seed })
+    }
+}
+
+impl Iterator for XorShift {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        Some(self.0)
+    }
+}
+
+/// Binary image of w x h pseudo random pixels, pixel is 1 if is_set returns true for
+/// next number of generator with given seed.
+pub(crate) fn noise<F: Fn(u32) -> bool>(w: usize, h: usize, seed: u32, is_set: F) -> Image {
+    let data = XorShift::new(seed).take(w * h).map(|state| is_set(state) as u8).collect();
+    Image::new(w, h, data)
+}
@@ -0,0 +1,134 @@
+//! Helpers for testing code built on top of this crate.
+//! Enabled with `testing` feature.
+//!
+//! Contains proptest strategies generating valid runs, RLEs (single or pairs with same
+//! dimensions) and structuring elements and
+//! naive dense implementations of morphological operations which can be used as
+//! reference (oracle) in differential tests:
+//! ```
+//! use proptest::prelude::*;
+//! use rle_morph::testing::{dense_dilate, rle_strategy, structuring_strategy};
+//!
+//! proptest!(|(rle in rle_strategy(16, 16), s in structuring_strategy(2))| {
+//!     prop_assert_eq!(
+//!         rle.dilate(&s).to_image(1),
+//!         dense_dilate(&rle.to_image(1), &s.to_image(1))
+//!     );
+//! });
+//! ```
+use super::{Image, Morphology, Run, RLE};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Strategy generating RLE with width in 0..=max_width and height in 0..=max_height.
+/// Runs are generated from random pixels so they are always canonical and
+/// shrinking removes pixels (or shrinks dimensions) without breaking invariants.
+pub fn rle_strategy(max_width: usize, max_height: usize) -> impl Strategy<Value = RLE> {
+    (0..=max_width, 0..=max_height).prop_flat_map(|(w, h)| sized_rle_strategy(w, h))
+}
+
+/// Strategy generating RLE with exactly given dimensions, shrinking removes pixels.
+pub fn sized_rle_strategy(width: usize, height: usize) -> impl Strategy<Value = RLE> {
+    vec(any::<bool>(), width * height).prop_map(move |pixels| {
+        let data: Vec<u8> = pixels.into_iter().map(|p| p as u8).collect();
+        RLE::from_raw_data(width, height, &data)
+    })
+}
+
+/// Strategy generating two RLEs with same dimensions (up to max_width x max_height),
+/// for testing binary operations.
+pub fn rle_pair_strategy(max_width: usize, max_height: usize) -> impl Strategy<Value = (RLE, RLE)> {
+    (0..=max_width, 0..=max_height).prop_flat_map(|(w, h)| (sized_rle_strategy(w, h), sized_rle_strategy(w, h)))
+}
+
+/// Strategy generating valid run (x_start <= x_end) inside of width x height image.
+/// If width or height is 0 there is no such run and this function will panic.
+pub fn run_strategy(width: usize, height: usize) -> impl Strategy<Value = Run> {
+    assert!(width > 0 && height > 0);
+    (0..width as i32, 0..height as i32).prop_flat_map(move |(x_start, y)| {
+        (x_start..width as i32).prop_map(move |x_end| Run::new(x_start, x_end, y))
+    })
+}
+
+/// Strategy generating structuring elements with odd dimension 2 * k + 1
+/// for k in 0..=max_k and at least one pixel set (center).
+pub fn structuring_strategy(max_k: usize) -> impl Strategy<Value = RLE> {
+    (0..=max_k).prop_flat_map(|k| {
+        let dim = 2 * k + 1;
+        vec(any::<bool>(), dim * dim).prop_map(move |pixels| {
+            let mut data: Vec<u8> = pixels.into_iter().map(|p| p as u8).collect();
+            // center is always set so structuring element is not empty
+            data[dim * dim / 2] = 1;
+            RLE::from_raw_data(dim, dim, &data)
+        })
+    })
+}
+
+/// Naive dilation of binary image img with structuring element s.
+/// Pixel (x, y) of result is 1 if any pixel (x + sx - cx, y + sy - cy) of img is nonzero
+/// where (sx, sy) is nonzero pixel of s and (cx, cy) = (s.w() / 2, s.h() / 2).
+/// Same semantics as `RLE::dilate`.
+pub fn dense_dilate(img: &Image, s: &Image) -> Image {
+    Morphology::dilate(img, s)
+}
+
+/// Naive erosion of binary image img with structuring element s.
+/// Pixel (x, y) of result is 1 if all pixels (x + sx - cx, y + sy - cy) of img are nonzero
+/// where (sx, sy) is nonzero pixel of s and (cx, cy) = (s.w() / 2, s.h() / 2).
+/// Pixels outside of image are treated as 1.
+/// Same semantics as `RLE::erode`.
+pub fn dense_erode(img: &Image, s: &Image) -> Image {
+    Morphology::erode(img, s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn rle_strategy_is_canonical(rle in rle_strategy(16, 16)) {
+            prop_assert!(rle.is_canonical());
+        }
+
+        #[test]
+        fn run_strategy_is_valid(run in run_strategy(8, 4)) {
+            prop_assert!(run.x_start <= run.x_end && run.x_end < 8 && run.y < 4);
+        }
+
+        #[test]
+        fn rle_pair_strategy_same_size((a, b) in rle_pair_strategy(16, 16)) {
+            prop_assert_eq!((a.width(), a.height()), (b.width(), b.height()));
+            prop_assert!(a.is_canonical() && b.is_canonical());
+            // closing is superset of opening
+            let s = RLE::linf_structuring(1);
+            prop_assert_eq!(&a.open(&s) & &a.close(&s), a.open(&s));
+        }
+
+        #[test]
+        fn dilate_matches_dense(rle in rle_strategy(16, 16), s in structuring_strategy(2)) {
+            prop_assert_eq!(
+                rle.dilate(&s).to_image(1),
+                dense_dilate(&rle.to_image(1), &s.to_image(1))
+            );
+        }
+
+        #[test]
+        fn erode_matches_dense(rle in rle_strategy(16, 16), s in structuring_strategy(2)) {
+            prop_assert_eq!(
+                rle.erode(&s).to_image(1),
+                dense_erode(&rle.to_image(1), &s.to_image(1))
+            );
+        }
+    }
+
+    #[test]
+    fn dense_dilate_asymmetric_test() {
+        // structuring element with only pixel right of center
+        let s = Image::new(3, 1, vec![0, 0, 1]);
+        let img = Image::new(4, 1, vec![0, 0, 1, 0]);
+        // result at x is pixel x + 1 of input
+        assert_eq!(dense_dilate(&img, &s), Image::new(4, 1, vec![0, 1, 0, 0]));
+        assert_eq!(RLE::from(&img).dilate(&RLE::from(&s)).to_image(1), Image::new(4, 1, vec![0, 1, 0, 0]));
+    }
+}
@@ -0,0 +1,97 @@
+//! Binarization of grayscale images directly to runs, without binary image in between.
+use super::rle::encode_row;
+use super::{Image, RLE};
+
+impl RLE {
+    /// Threshold of Otsu's method for 8-bit image: value t maximizing variance between
+    /// pixels <= t and pixels > t. If more values give same variance smallest one is
+    /// used, so image with only one value gets 0.
+    pub fn otsu_threshold(img: &Image) -> u8 {
+        let mut histogram = [0u64; 256];
+        for &pixel in img.data() {
+            histogram[pixel as usize] += 1;
+        }
+        let total: u64 = histogram.iter().sum();
+        let total_sum: f64 = histogram.iter().enumerate().map(|(v, &n)| v as f64 * n as f64).sum();
+        let (mut count, mut sum) = (0u64, 0.0);
+        let (mut best, mut best_variance) = (0, 0.0);
+        for (t, &n) in histogram.iter().enumerate() {
+            count += n;
+            sum += t as f64 * n as f64;
+            if count == 0 || count == total {
+                continue;
+            }
+            let (w0, w1) = (count as f64, (total - count) as f64);
+            let diff = sum / w0 - (total_sum - sum) / w1;
+            // between class variance without constant factor 1 / total^2
+            let variance = w0 * w1 * diff * diff;
+            if variance > best_variance {
+                best = t;
+                best_variance = variance;
+            }
+        }
+        best as u8
+    }
+
+    /// Binarize image with threshold of Otsu's method (see `otsu_threshold`), pixels
+    /// greater than threshold are 1. Image is read once for histogram and once while
+    /// runs are encoded.
+    pub fn from_gray_otsu(img: &Image) -> RLE {
+        let threshold = Self::otsu_threshold(img);
+        Self::from_image_with(img, |&pixel| pixel > threshold)
+    }
+
+    /// Hysteresis thresholding: pixels >= high are 1 together with pixels >= low which
+    /// are 8-connected to them through pixels >= low. Runs of both thresholds are
+    /// encoded in same pass over rows and components of low mask are kept if they
+    /// contain high run (see `reconstruct_by_dilation`).
+    pub fn from_gray_hysteresis(img: &Image, low: u8, high: u8) -> RLE {
+        let (w, h) = (img.w(), img.h());
+        let (mut low_runs, mut high_runs) = (Vec::new(), Vec::new());
+        for y in 0..h {
+            let row = &img.data()[y * w..(y + 1) * w];
+            encode_row(row, y as i32, &mut low_runs, &|&pixel| pixel >= low);
+            encode_row(row, y as i32, &mut high_runs, &|&pixel| pixel >= high);
+        }
+        let low = RLE { width: w, height: h, runs: low_runs };
+        let high = RLE { width: w, height: h, runs: high_runs };
+        RLE::reconstruct_by_dilation(&high, &low)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn otsu_test() {
+        let img = Image::new(4, 2, vec![
+            10, 12, 200, 11,
+            198, 10, 202, 13,
+        ]);
+        let threshold = RLE::otsu_threshold(&img);
+        assert!((13..198).contains(&threshold));
+        assert_eq!(RLE::from_gray_otsu(&img), RLE::from(&Image::new(4, 2, vec![
+            0, 0, 1, 0,
+            1, 0, 1, 0,
+        ])));
+        assert_eq!(RLE::otsu_threshold(&Image::new(2, 1, vec![7, 7])), 0);
+        assert_eq!(RLE::otsu_threshold(&Image::new(0, 0, vec![])), 0);
+    }
+
+    #[test]
+    fn hysteresis_test() {
+        let img = Image::new(6, 3, vec![
+            50, 60, 0, 60, 60, 0,
+            0, 200, 0, 0, 0, 0,
+            0, 0, 80, 0, 90, 90,
+        ]);
+        // weak pixels connected to strong pixel are kept, other weak pixels are removed
+        assert_eq!(RLE::from_gray_hysteresis(&img, 50, 150), RLE::from(&Image::new(6, 3, vec![
+            1, 1, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0,
+        ])));
+        assert_eq!(RLE::from_gray_hysteresis(&img, 150, 150), RLE::from_image_with(&img, |&p| p >= 150));
+    }
+}
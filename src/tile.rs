@@ -0,0 +1,185 @@
+//! Splitting mask into rectangular tiles and assembling mask back from tiles, for
+//! tile-wise processing of masks which are too large to process at once.
+use super::{Context, Run, RleError, RLE};
+
+/// Tile with position (x, y) of its top left pixel.
+type Tile = ((usize, usize), RLE);
+
+impl RLE {
+    /// Split image into tiles of tile_w columns and tile_h rows, tiles in last column
+    /// and last row are smaller if dimensions are not multiples of tile size.
+    /// Tiles are yielded row after row together with position (x, y) of their top
+    /// left pixel, coordinates of tile runs are relative to that position.
+    /// Every run is visited once, so all tiles take time proportional to number of
+    /// runs and tiles. If tile_w or tile_h is 0 this function will panic.
+    pub fn tiles(&self, tile_w: usize, tile_h: usize) -> impl Iterator<Item = ((usize, usize), RLE)> + '_ {
+        assert!(tile_w > 0 && tile_h > 0);
+        let runs = self.canonical_runs();
+        let (width, height) = (self.width, self.height);
+        (0..height).step_by(tile_h).flat_map(move |y0| row_tiles(&runs, width, height, tile_w, tile_h, y0))
+    }
+
+    /// Same as `tiles` but all tiles are collected, progress (rows of tiles done) is
+    /// reported to ctx and `RleError::Cancelled` is returned if ctx is cancelled.
+    /// If tile_w or tile_h is 0 this function will panic.
+    pub fn tiles_with_progress(
+        &self,
+        tile_w: usize,
+        tile_h: usize,
+        ctx: &mut Context,
+    ) -> Result<Vec<Tile>, RleError> {
+        assert!(tile_w > 0 && tile_h > 0);
+        let runs = self.canonical_runs();
+        let rows = self.height.div_ceil(tile_h);
+        let mut tiles = Vec::with_capacity(rows * self.width.div_ceil(tile_w));
+        for row in 0..rows {
+            ctx.step(row, rows)?;
+            tiles.extend(row_tiles(&runs, self.width, self.height, tile_w, tile_h, row * tile_h));
+        }
+        ctx.step(rows, rows)?;
+        Ok(tiles)
+    }
+
+    /// Assemble image with given dimensions from tiles with positions (x, y) of their
+    /// top left pixels, for example tiles produced by `tiles`. Tiles can have any size
+    /// and can overlap (pixels are ORed), parts outside of image are clipped and
+    /// pixels not covered by any tile are 0.
+    pub fn from_tiles<I>(width: usize, height: usize, tiles: I) -> RLE
+    where
+        I: IntoIterator<Item = ((usize, usize), RLE)>,
+    {
+        let mut runs = Vec::new();
+        for ((x, y), tile) in tiles {
+            push_tile_runs(width, height, (x, y), &tile, &mut runs);
+        }
+        assemble(width, height, runs)
+    }
+
+    /// Same as `from_tiles` but progress (tiles done) is reported to ctx and
+    /// `RleError::Cancelled` is returned if ctx is cancelled.
+    pub fn from_tiles_with_progress<I>(width: usize, height: usize, tiles: I, ctx: &mut Context) -> Result<RLE, RleError>
+    where
+        I: IntoIterator<Item = ((usize, usize), RLE)>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let tiles = tiles.into_iter();
+        let total = tiles.len();
+        let mut runs = Vec::new();
+        for (i, ((x, y), tile)) in tiles.enumerate() {
+            ctx.step(i, total)?;
+            push_tile_runs(width, height, (x, y), &tile, &mut runs);
+        }
+        ctx.step(total, total)?;
+        Ok(assemble(width, height, runs))
+    }
+}
+
+/// Tiles of one row of tiles with top row y0, see `RLE::tiles`.
+fn row_tiles(
+    runs: &[Run],
+    width: usize,
+    height: usize,
+    tile_w: usize,
+    tile_h: usize,
+    y0: usize,
+) -> impl Iterator<Item = Tile> {
+    let y1 = (y0 + tile_h).min(height);
+    let start = runs.partition_point(|run| (run.y as usize) < y0);
+    let end = runs.partition_point(|run| (run.y as usize) < y1);
+    // runs are sorted so pieces of them are pushed to tiles in sorted order
+    let mut tile_runs = vec![Vec::new(); width.div_ceil(tile_w)];
+    for run in &runs[start..end] {
+        let (x_start, x_end) = (run.x_start as usize, run.x_end as usize);
+        let first = x_start / tile_w;
+        for (col, pieces) in tile_runs[first..=x_end / tile_w].iter_mut().enumerate() {
+            let x0 = (first + col) * tile_w;
+            let piece_start = x_start.max(x0) - x0;
+            let piece_end = x_end.min(x0 + tile_w - 1) - x0;
+            pieces.push(Run::new(piece_start as i32, piece_end as i32, (run.y as usize - y0) as i32));
+        }
+    }
+    tile_runs.into_iter().enumerate().map(move |(col, runs)| {
+        let x0 = col * tile_w;
+        let tile_width = tile_w.min(width - x0);
+        // clipped canonical runs are canonical
+        ((x0, y0), RLE { width: tile_width, height: y1 - y0, runs })
+    })
+}
+
+/// Push runs of tile with top left pixel (x, y) moved to image coordinates, tiles
+/// starting outside of image are skipped.
+fn push_tile_runs(width: usize, height: usize, (x, y): (usize, usize), tile: &RLE, runs: &mut Vec<Run>) {
+    if x >= width || y >= height {
+        return;
+    }
+    let (x, y) = (x as i32, y as i32);
+    runs.extend(tile.canonical_runs().iter().map(|run| Run::new(run.x_start + x, run.x_end + x, run.y + y)));
+}
+
+/// Image from runs of all tiles, see `RLE::from_tiles`.
+fn assemble(width: usize, height: usize, runs: Vec<Run>) -> RLE {
+    // touching runs of neighbouring tiles are merged
+    let mut res = RLE::from_runs(width, height, runs);
+    res.clip_to_bounds();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CancellationToken, Image};
+
+    #[test]
+    fn tiles_test() {
+        let rle = RLE::from(&Image::new(5, 3, vec![
+            1, 1, 1, 1, 1,
+            0, 1, 0, 0, 1,
+            1, 0, 0, 1, 0,
+        ]));
+        let tiles: Vec<_> = rle.tiles(2, 2).collect();
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(tiles[0], ((0, 0), RLE::from(&Image::new(2, 2, vec![
+            1, 1,
+            0, 1,
+        ]))));
+        assert_eq!(tiles[2], ((4, 0), RLE::ones(1, 2)));
+        assert_eq!(tiles[4], ((2, 2), RLE::from(&Image::new(2, 1, vec![0, 1]))));
+        assert_eq!(tiles[5], ((4, 2), RLE::new(1, 1)));
+        assert!(tiles.iter().all(|(_, tile)| tile.is_canonical()));
+        assert_eq!(RLE::from_tiles(5, 3, tiles), rle);
+        for &(tile_w, tile_h) in &[(1, 1), (3, 2), (5, 3), (7, 9)] {
+            assert_eq!(RLE::from_tiles(5, 3, rle.tiles(tile_w, tile_h)), rle);
+        }
+        // tiles sticking out of image are clipped
+        assert_eq!(RLE::from_tiles(3, 2, rle.tiles(4, 4)), RLE::from(&Image::new(3, 2, vec![
+            1, 1, 1,
+            0, 1, 0,
+        ])));
+        assert_eq!(RLE::new(0, 0).tiles(2, 2).count(), 0);
+    }
+
+    #[test]
+    fn tiles_with_progress_test() {
+        let rle = RLE::from(&Image::new(5, 3, vec![
+            1, 1, 1, 1, 1,
+            0, 1, 0, 0, 1,
+            1, 0, 0, 1, 0,
+        ]));
+        let mut reported = Vec::new();
+        let tiles = rle.tiles_with_progress(2, 2, &mut Context::new().with_progress(|p| reported.push(p)));
+        assert_eq!(tiles, Ok(rle.tiles(2, 2).collect()));
+        assert_eq!(reported, vec![0.0, 0.5, 1.0]);
+
+        let mut reported = Vec::new();
+        let res = RLE::from_tiles_with_progress(5, 3, tiles.unwrap(), &mut Context::new().with_progress(|p| reported.push(p)));
+        assert_eq!(res, Ok(rle.clone()));
+        assert_eq!(reported.len(), 7);
+        assert_eq!(reported.last(), Some(&1.0));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut ctx = Context::new().with_cancellation(token);
+        assert_eq!(rle.tiles_with_progress(2, 2, &mut ctx), Err(RleError::Cancelled));
+        assert_eq!(RLE::from_tiles_with_progress(5, 3, rle.tiles(2, 2).collect::<Vec<_>>(), &mut ctx), Err(RleError::Cancelled));
+    }
+}
@@ -0,0 +1,116 @@
+//! Instrumentation of operations enabled with `tracing` feature.
+use super::{LabelRle, RLE};
+
+/// Result of operation which can report number of runs.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) trait RunCount {
+    fn run_count(&self) -> usize;
+}
+
+impl RunCount for RLE {
+    fn run_count(&self) -> usize {
+        self.runs().len()
+    }
+}
+
+impl RunCount for Vec<RLE> {
+    fn run_count(&self) -> usize {
+        self.iter().map(|rle| rle.runs().len()).sum()
+    }
+}
+
+impl RunCount for LabelRle {
+    fn run_count(&self) -> usize {
+        self.runs().len()
+    }
+}
+
+impl<T: RunCount> RunCount for Option<T> {
+    fn run_count(&self) -> usize {
+        self.as_ref().map_or(0, |res| res.run_count())
+    }
+}
+
+impl<T: RunCount, E> RunCount for Result<T, E> {
+    fn run_count(&self) -> usize {
+        self.as_ref().map_or(0, |res| res.run_count())
+    }
+}
+
+/// Run f inside of debug span `rle_morph` with fields `op` (name of operation),
+/// `input_runs`, `output_runs` and `elapsed_us`.
+/// Without `tracing` feature this just calls f.
+#[cfg(feature = "tracing")]
+#[inline]
+pub(crate) fn instrument<T: RunCount, F: FnOnce() -> T>(op: &'static str, input_runs: usize, f: F) -> T {
+    let span = tracing::debug_span!(
+        "rle_morph",
+        op,
+        input_runs,
+        output_runs = tracing::field::Empty,
+        elapsed_us = tracing::field::Empty
+    );
+    let _enter = span.enter();
+    let start = std::time::Instant::now();
+    let res = f();
+    span.record("output_runs", res.run_count() as u64);
+    span.record("elapsed_us", start.elapsed().as_micros() as u64);
+    res
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn instrument<T: RunCount, F: FnOnce() -> T>(_op: &'static str, _input_runs: usize, f: F) -> T {
+    f()
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+
+    /// Subscriber collecting all recorded fields as (name, value) pairs.
+    #[derive(Clone, Default)]
+    struct Collect(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl Visit for Collect {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.lock().unwrap().push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    impl tracing::Subscriber for Collect {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut self.clone());
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.clone());
+        }
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn instrument_records_fields_test() {
+        let collect = Collect::default();
+        tracing::subscriber::with_default(collect.clone(), || {
+            RLE::linf_structuring(1).dilate(&RLE::linf_structuring(1));
+        });
+        let fields = collect.0.lock().unwrap().clone();
+        let value = |name: &str| fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+        assert_eq!(value("op"), Some("\"dilate\"".to_string()));
+        assert_eq!(value("input_runs"), Some("3".to_string()));
+        // dilation keeps runs which grow outside of image
+        assert_eq!(value("output_runs"), Some("5".to_string()));
+        assert!(value("elapsed_us").is_some());
+    }
+}
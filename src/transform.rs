@@ -0,0 +1,540 @@
+//! Geometric transformations of masks.
+use super::{coord, Run, RleError, RLE};
+
+/// Runs of every column as (y_start, y_end) pairs, found by sweeping rows top to bottom
+/// and opening or closing column intervals only where row differs from previous one.
+fn column_runs(rle: &RLE) -> Vec<Vec<(i32, i32)>> {
+    let runs = rle.canonical_runs();
+    let mut columns = vec![Vec::new(); rle.width()];
+    // row where currently open interval of column started
+    let mut open = vec![-1i32; rle.width()];
+    let mut prev: &[Run] = &[];
+    let mut start = 0;
+    for y in 0..=rle.height() as i32 {
+        let end = start + runs[start..].iter().take_while(|run| run.y == y).count();
+        let row = &runs[start..end];
+        // walk over boundaries of both rows, x is toggled for every boundary
+        let mut boundaries: Vec<i32> = prev.iter().chain(row)
+            .flat_map(|run| [run.x_start, run.x_end + 1])
+            .collect();
+        boundaries.sort_unstable();
+        let mut toggled = Vec::new();
+        for pair in boundaries.chunks(2) {
+            // boundaries present in both rows cancel out
+            match toggled.last() {
+                Some(&(_, last_end)) if last_end == pair[0] => toggled.last_mut().unwrap().1 = pair[1],
+                _ => toggled.push((pair[0], pair[1])),
+            }
+        }
+        for (x_start, x_end) in toggled {
+            for x in x_start..x_end {
+                let column = x as usize;
+                if open[column] < 0 {
+                    open[column] = y;
+                } else {
+                    columns[column].push((open[column], y - 1));
+                    open[column] = -1;
+                }
+            }
+        }
+        prev = row;
+        start = end;
+    }
+    columns
+}
+
+impl RLE {
+    /// Shift all pixels by dx columns and dy rows.
+    /// If clip is true pixels moved outside of image are removed, otherwise runs are
+    /// kept as they are (same as after `dilate`) and can be moved back later.
+    /// With `strict` feature this panics if some coordinate overflows.
+    pub fn translate(&self, dx: i32, dy: i32, clip: bool) -> Self {
+        let mut res = self.clone();
+        res.translate_mut(dx, dy, clip);
+        res
+    }
+
+    /// Shift all pixels by dx columns and dy rows in place, see `translate`.
+    pub fn translate_mut(&mut self, dx: i32, dy: i32, clip: bool) {
+        if let Err(err) = self.try_translate_mut(dx, dy) {
+            panic!("{}", err);
+        }
+        if clip && !self.is_canonical() {
+            self.runs = self.canonical_runs().into_owned();
+        }
+    }
+
+    /// Enlarge image by adding left and right columns and top and bottom rows,
+    /// new pixels are set to value. Original pixels are shifted by (left, top).
+    /// With `strict` feature this panics if new dimensions do not fit in i32.
+    pub fn pad(&self, left: usize, right: usize, top: usize, bottom: usize, value: bool) -> Self {
+        let width = self.width + left + right;
+        let height = self.height + top + bottom;
+        if let Err(err) = coord::from_usize(width).and(coord::from_usize(height)) {
+            panic!("{}", err);
+        }
+        let full_rows = |rows: std::ops::Range<usize>| rows.map(|y| Run::new(0, width as i32 - 1, y as _));
+        if !value || width == 0 {
+            let mut res = Self::from_runs(width, height, self.canonical_runs().into_owned());
+            res.translate_mut(left as _, top as _, false);
+            return res;
+        }
+        let runs = self.canonical_runs();
+        let mut res = Vec::with_capacity(runs.len() + 2 * self.height + top + bottom);
+        res.extend(full_rows(0..top));
+        let (left, right_start) = (left as i32, (left + self.width) as i32);
+        let mut start = 0;
+        for y in 0..self.height as i32 {
+            let end = start + runs[start..].iter().take_while(|run| run.y == y).count();
+            if left > 0 {
+                res.push(Run::new(0, left - 1, y + top as i32));
+            }
+            for run in &runs[start..end] {
+                let run = Run::new(run.x_start + left, run.x_end + left, y + top as i32);
+                match res.last_mut() {
+                    Some(last) if last.y == run.y && last.x_end + 1 == run.x_start => last.x_end = run.x_end,
+                    _ => res.push(run),
+                }
+            }
+            if right_start < width as i32 {
+                match res.last_mut() {
+                    Some(last) if last.y == y + top as i32 && last.x_end + 1 == right_start => last.x_end = width as i32 - 1,
+                    _ => res.push(Run::new(right_start, width as i32 - 1, y + top as i32)),
+                }
+            }
+            start = end;
+        }
+        res.extend(full_rows(top + self.height..height));
+        Self::from_runs(width, height, res)
+    }
+
+    /// Mirror image over vertical axis, pixel (x, y) goes to (width - 1 - x, y).
+    pub fn flip_horizontal(&self) -> Self {
+        let w = self.width as i32;
+        let mut runs = self.canonical_runs().into_owned();
+        let mut start = 0;
+        while start < runs.len() {
+            let y = runs[start].y;
+            let end = start + runs[start..].iter().take_while(|run| run.y == y).count();
+            runs[start..end].reverse();
+            for run in &mut runs[start..end] {
+                *run = Run::new(w - 1 - run.x_end, w - 1 - run.x_start, y);
+            }
+            start = end;
+        }
+        Self::from_runs(self.width, self.height, runs)
+    }
+
+    /// Mirror image over horizontal axis, pixel (x, y) goes to (x, height - 1 - y).
+    pub fn flip_vertical(&self) -> Self {
+        let h = self.height as i32;
+        let runs = self.canonical_runs();
+        let mut res = Vec::with_capacity(runs.len());
+        // rows are taken from last to first, runs inside of row keep their order
+        let mut end = runs.len();
+        while end > 0 {
+            let y = runs[end - 1].y;
+            let start = end - runs[..end].iter().rev().take_while(|run| run.y == y).count();
+            res.extend(runs[start..end].iter().map(|run| Run::new(run.x_start, run.x_end, h - 1 - y)));
+            end = start;
+        }
+        Self::from_runs(self.width, self.height, res)
+    }
+
+    /// Swap rows and columns, pixel (x, y) goes to (y, x) and result has width
+    /// equal to height of self and vice versa.
+    pub fn transpose(&self) -> Self {
+        let runs = column_runs(self).into_iter().enumerate()
+            .flat_map(|(x, column)| column.into_iter().map(move |(y_start, y_end)| Run::new(y_start, y_end, x as _)))
+            .collect();
+        Self::from_runs(self.height, self.width, runs)
+    }
+
+    /// Rotate image by 90 degrees clockwise, pixel (x, y) goes to (height - 1 - y, x).
+    pub fn rotate90(&self) -> Self {
+        self.transpose().flip_horizontal()
+    }
+
+    /// Rotate image by 180 degrees, pixel (x, y) goes to (width - 1 - x, height - 1 - y).
+    pub fn rotate180(&self) -> Self {
+        self.flip_horizontal().flip_vertical()
+    }
+
+    /// Rotate image by 270 degrees clockwise (90 counterclockwise),
+    /// pixel (x, y) goes to (y, width - 1 - x).
+    pub fn rotate270(&self) -> Self {
+        self.transpose().flip_vertical()
+    }
+
+    /// Resize image to new_width x new_height with nearest neighbor sampling, pixel
+    /// (x, y) of result is pixel (x * width / new_width, y * height / new_height) of
+    /// self (rounded down, same as OpenCV `INTER_NEAREST`). Runs are scaled directly and
+    /// rows are repeated (or skipped), so image is never decoded.
+    pub fn resize(&self, new_width: usize, new_height: usize) -> Self {
+        if self.width == 0 || self.height == 0 {
+            return Self::new(new_width, new_height);
+        }
+        // first pixel of result which samples pixel a of self (in one dimension)
+        let first = |a: i32, old: usize, new: usize| (a as u64 * new as u64).div_ceil(old as u64) as i32;
+        let runs = self.canonical_runs();
+        let mut res = Vec::new();
+        let mut start = 0;
+        while start < runs.len() {
+            let y = runs[start].y;
+            let end = start + runs[start..].iter().take_while(|run| run.y == y).count();
+            let row: Vec<(i32, i32)> = runs[start..end].iter()
+                .map(|run| (first(run.x_start, self.width, new_width), first(run.x_end + 1, self.width, new_width) - 1))
+                .filter(|&(x_start, x_end)| x_start <= x_end)
+                .collect();
+            for new_y in first(y, self.height, new_height)..first(y + 1, self.height, new_height) {
+                res.extend(row.iter().map(|&(x_start, x_end)| Run::new(x_start, x_end, new_y)));
+            }
+            start = end;
+        }
+        // runs can touch after gaps between them are skipped
+        Self::from_runs(new_width, new_height, res)
+    }
+
+    /// Scale image by factors fx and fy with nearest neighbor sampling (see `resize`),
+    /// new dimensions are rounded to nearest integer.
+    /// Panics if factor is negative or not finite.
+    pub fn scale(&self, fx: f64, fy: f64) -> Self {
+        assert!(fx.is_finite() && fy.is_finite() && fx >= 0.0 && fy >= 0.0, "scale factors must be finite and not negative");
+        let new_width = (self.width as f64 * fx).round() as usize;
+        let new_height = (self.height as f64 * fy).round() as usize;
+        self.resize(new_width, new_height)
+    }
+
+    /// Rotate image by angle (in radians) around center (in pixel coordinates, center
+    /// of image is (width / 2, height / 2)). Y axis points down, so positive angle is
+    /// clockwise on screen (same as `Moments::orientation`, rotating by minus
+    /// orientation aligns major axis with x axis).
+    /// Every pixel of result gets value of pixel of self which contains inverse image
+    /// of its center (nearest neighbor). If expand is true result is enlarged so that
+    /// whole rotated image fits, otherwise it has same dimensions as self.
+    /// Runs of result are found row by row from runs of self, image is never decoded.
+    pub fn rotate(&self, angle: f64, center: (f64, f64), expand: bool) -> Self {
+        let map = InverseRotation::new(angle, center, self.width, self.height, expand);
+        let (width, height) = (map.width, map.height);
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return Self::new(width, height);
+        }
+        let indexed = self.indexed();
+        let last = width as i64 - 1;
+        let mut res = Vec::new();
+        for y in 0..height as i64 {
+            let (ox, oy) = map.source(0, y);
+            let ey = map.source(last, y).1;
+            // rows of self crossed by inverse image of row y
+            let ys_start = std::cmp::max(0, oy.min(ey).floor() as i64 - 1);
+            let ys_end = std::cmp::min(self.height as i64 - 1, oy.max(ey).floor() as i64 + 1);
+            for ys in ys_start..=ys_end {
+                let row = indexed.row(ys as i32);
+                if row.is_empty() {
+                    continue;
+                }
+                let (ta, tb) = match t_range(oy, map.dx.1, ys as f64, ys as f64 + 1.0, last) {
+                    Some(range) => range,
+                    None => continue,
+                };
+                // only runs around columns crossed in row ys can contain pixels
+                let (sa, sb) = (map.source(ta, y).0, map.source(tb, y).0);
+                let (x_min, x_max) = (sa.min(sb).floor() as i64 - 1, sa.max(sb).floor() as i64 + 1);
+                let first = row.partition_point(|run| (run.x_end as i64) < x_min);
+                for run in row[first..].iter().take_while(|run| run.x_start as i64 <= x_max) {
+                    let inside = |t: i64| {
+                        let (sx, sy) = map.source(t, y);
+                        let (px, py) = (sx.floor(), sy.floor());
+                        py == ys as f64 && px >= run.x_start as f64 && px <= run.x_end as f64
+                    };
+                    let (mut t0, mut t1) = match t_range(ox, map.dx.0, run.x_start as f64, run.x_end as f64 + 1.0, last) {
+                        Some((t0, t1)) => (t0.max(ta), t1.min(tb)),
+                        None => continue,
+                    };
+                    // bounds are exact up to rounding, fix them with exact test of pixels
+                    while t0 <= t1 && !inside(t0) {
+                        t0 += 1;
+                    }
+                    while t1 >= t0 && !inside(t1) {
+                        t1 -= 1;
+                    }
+                    if t0 > t1 {
+                        continue;
+                    }
+                    while t0 > 0 && inside(t0 - 1) {
+                        t0 -= 1;
+                    }
+                    while t1 < last && inside(t1 + 1) {
+                        t1 += 1;
+                    }
+                    res.push(Run::new(t0 as i32, t1 as i32, y as i32));
+                }
+            }
+        }
+        Self::from_runs(width, height, res)
+    }
+
+    fn try_translate_mut(&mut self, dx: i32, dy: i32) -> Result<(), RleError> {
+        for run in &mut self.runs {
+            *run = Run::new(coord::add(run.x_start, dx)?, coord::add(run.x_end, dx)?, coord::add(run.y, dy)?);
+        }
+        Ok(())
+    }
+}
+
+/// Inverse of rotation, point of self which is mapped to center of pixel (x, y) of
+/// result is origin + x * dx + y * dy.
+struct InverseRotation {
+    origin: (f64, f64),
+    dx: (f64, f64),
+    dy: (f64, f64),
+    width: usize,
+    height: usize,
+}
+
+impl InverseRotation {
+    fn new(angle: f64, (cx, cy): (f64, f64), width: usize, height: usize, expand: bool) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        // center of rotation in result
+        let (mut rx, mut ry) = (cx, cy);
+        let (mut new_width, mut new_height) = (width, height);
+        if expand {
+            let corners = [(0.0, 0.0), (width as f64, 0.0), (0.0, height as f64), (width as f64, height as f64)];
+            let rotated: Vec<(f64, f64)> = corners.iter()
+                .map(|&(x, y)| (cx + cos * (x - cx) - sin * (y - cy), cy + sin * (x - cx) + cos * (y - cy)))
+                .collect();
+            let min_x = rotated.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+            let max_x = rotated.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+            let min_y = rotated.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+            let max_y = rotated.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+            // small tolerance so that rounding errors do not add row or column
+            new_width = (max_x - min_x - 1e-9).ceil().max(0.0) as usize;
+            new_height = (max_y - min_y - 1e-9).ceil().max(0.0) as usize;
+            rx -= min_x;
+            ry -= min_y;
+        }
+        let (px, py) = (0.5 - rx, 0.5 - ry);
+        Self {
+            origin: (cx + cos * px + sin * py, cy - sin * px + cos * py),
+            dx: (cos, -sin),
+            dy: (sin, cos),
+            width: new_width,
+            height: new_height,
+        }
+    }
+
+    /// Point of self mapped to center of pixel (x, y) of result.
+    fn source(&self, x: i64, y: i64) -> (f64, f64) {
+        let (x, y) = (x as f64, y as f64);
+        (
+            self.origin.0 + x * self.dx.0 + y * self.dy.0,
+            self.origin.1 + x * self.dx.1 + y * self.dy.1,
+        )
+    }
+}
+
+/// Integers t in 0..=last for which a <= p + t * q < b (up to rounding), None if there
+/// are none.
+fn t_range(p: f64, q: f64, a: f64, b: f64, last: i64) -> Option<(i64, i64)> {
+    let (t0, t1) = if q == 0.0 {
+        if a <= p && p < b { (0, last) } else { return None }
+    } else {
+        let (lo, hi) = ((a - p) / q, (b - p) / q);
+        let (lo, hi) = if q > 0.0 { (lo, hi) } else { (hi, lo) };
+        // one more on both sides, bounds are checked exactly by caller
+        ((lo.floor() as i64 - 1).max(0), (hi.ceil() as i64 + 1).min(last))
+    };
+    if t0 <= t1 { Some((t0, t1)) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Image;
+
+    #[test]
+    fn translate_test() {
+        let rle = RLE::from(&Image::new(5, 4, vec![
+            1, 1, 0, 0, 0,
+            0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0,
+            1, 0, 0, 0, 1,
+        ]));
+        assert_eq!(rle.translate(2, 1, true).to_image(1), Image::new(5, 4, vec![
+            0, 0, 0, 0, 0,
+            0, 0, 1, 1, 0,
+            0, 0, 0, 1, 1,
+            0, 0, 0, 0, 0,
+        ]));
+        let shifted = rle.translate(-3, 2, false);
+        assert_eq!(shifted.runs().len(), rle.runs().len());
+        assert_eq!(shifted.translate(3, -2, true), rle);
+        let mut clipped = rle.clone();
+        clipped.translate_mut(-3, 2, true);
+        assert!(clipped.is_canonical());
+        assert_eq!(clipped, shifted);
+        // only pixels which stayed inside of image are moved back
+        assert_eq!(clipped.translate(3, -2, true).to_image(1), Image::new(5, 4, vec![
+            0, 0, 0, 0, 0,
+            0, 0, 0, 1, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ]));
+    }
+
+    #[test]
+    fn pad_test() {
+        let rle = RLE::from(&Image::new(3, 2, vec![
+            1, 0, 0,
+            0, 1, 1,
+        ]));
+        assert_eq!(rle.pad(1, 2, 1, 0, false).to_image(1), Image::new(6, 3, vec![
+            0, 0, 0, 0, 0, 0,
+            0, 1, 0, 0, 0, 0,
+            0, 0, 1, 1, 0, 0,
+        ]));
+        let padded = rle.pad(1, 2, 1, 1, true);
+        assert_eq!(padded.to_image(1), Image::new(6, 4, vec![
+            1, 1, 1, 1, 1, 1,
+            1, 1, 0, 0, 1, 1,
+            1, 0, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1,
+        ]));
+        assert!(padded.is_canonical());
+        assert_eq!(rle.pad(0, 0, 0, 0, true), rle);
+        assert_eq!(RLE::new(0, 0).pad(0, 0, 1, 1, true), RLE::new(0, 2));
+        assert_eq!(RLE::new(1, 1).pad(1, 0, 0, 0, true).to_image(1), Image::new(2, 1, vec![1, 0]));
+    }
+
+    /// Dense equivalent of transformation, f maps (x, y) to new coordinates.
+    fn dense_transform(img: &Image, w: usize, h: usize, f: &dyn Fn(usize, usize) -> (usize, usize)) -> Image {
+        let mut res = Image::empty(w, h);
+        for y in 0..img.h() {
+            for x in 0..img.w() {
+                let (nx, ny) = f(x, y);
+                res[ny][nx] = img[y][x];
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn flip_rotate_test() {
+        let img = Image::new(5, 3, vec![
+            1, 1, 0, 1, 0,
+            0, 0, 0, 0, 0,
+            0, 1, 1, 1, 1,
+        ]);
+        let rle = RLE::from(&img);
+        let (w, h) = (img.w(), img.h());
+        assert_eq!(rle.flip_horizontal().to_image(1), Image::new(5, 3, vec![
+            0, 1, 0, 1, 1,
+            0, 0, 0, 0, 0,
+            1, 1, 1, 1, 0,
+        ]));
+        assert_eq!(rle.transpose().to_image(1), Image::new(3, 5, vec![
+            1, 0, 0,
+            1, 0, 1,
+            0, 0, 1,
+            1, 0, 1,
+            0, 0, 1,
+        ]));
+        let check = |transformed: RLE, tw, th, f: &dyn Fn(usize, usize) -> (usize, usize)| {
+            assert!(transformed.is_canonical());
+            assert_eq!(transformed.to_image(1), dense_transform(&img, tw, th, f));
+        };
+        check(rle.flip_horizontal(), w, h, &|x, y| (w - 1 - x, y));
+        check(rle.flip_vertical(), w, h, &|x, y| (x, h - 1 - y));
+        check(rle.transpose(), h, w, &|x, y| (y, x));
+        check(rle.rotate90(), h, w, &|x, y| (h - 1 - y, x));
+        check(rle.rotate180(), w, h, &|x, y| (w - 1 - x, h - 1 - y));
+        check(rle.rotate270(), h, w, &|x, y| (y, w - 1 - x));
+        assert_eq!(rle.rotate90().rotate270(), rle);
+        assert_eq!(rle.transpose().transpose(), rle);
+        assert_eq!(RLE::new(0, 3).transpose(), RLE::new(3, 0));
+    }
+
+    #[cfg(feature = "strict")]
+    #[test]
+    #[should_panic(expected = "coordinate overflow")]
+    fn strict_translate_overflow_test() {
+        RLE::ones(2, 2).translate(i32::MAX, 0, false);
+    }
+
+    #[test]
+    fn resize_test() {
+        let rle = RLE::from(&Image::new(3, 2, vec![
+            1, 0, 1,
+            0, 1, 1,
+        ]));
+        assert_eq!(rle.scale(2.0, 2.0).to_image(1), Image::new(6, 4, vec![
+            1, 1, 0, 0, 1, 1,
+            1, 1, 0, 0, 1, 1,
+            0, 0, 1, 1, 1, 1,
+            0, 0, 1, 1, 1, 1,
+        ]));
+        // nearest neighbor sampling, pixel x of result samples pixel x * 3 / 2
+        assert_eq!(rle.resize(2, 1).to_image(1), Image::new(2, 1, vec![1, 0]));
+        assert_eq!(rle.resize(0, 5), RLE::new(0, 5));
+        assert_eq!(RLE::new(0, 0).resize(3, 3), RLE::new(3, 3));
+
+        // same as sampling dense image
+        let img = Image::new(7, 5, vec![
+            1, 1, 0, 1, 0, 0, 1,
+            0, 1, 1, 1, 0, 1, 0,
+            0, 0, 0, 0, 0, 0, 0,
+            1, 0, 1, 0, 1, 0, 1,
+            1, 1, 1, 0, 0, 1, 1,
+        ]);
+        let rle = RLE::from(&img);
+        for &(w, h) in &[(7, 5), (14, 10), (3, 2), (10, 3), (4, 9), (1, 1), (20, 20)] {
+            let mut expected = Image::empty(w, h);
+            for y in 0..h {
+                for x in 0..w {
+                    expected[y][x] = img[y * 5 / h][x * 7 / w];
+                }
+            }
+            let resized = rle.resize(w, h);
+            assert!(resized.is_canonical());
+            assert_eq!(resized.to_image(1), expected);
+        }
+    }
+
+    #[test]
+    fn rotate_test() {
+        let rle = RLE::from(&Image::new(7, 5, vec![
+            1, 1, 0, 0, 0, 1, 1,
+            0, 1, 1, 1, 0, 0, 1,
+            0, 0, 0, 1, 1, 0, 0,
+            1, 0, 0, 1, 1, 1, 0,
+            1, 1, 0, 0, 0, 1, 1,
+        ]));
+        let center = (3.5, 2.5);
+        assert_eq!(rle.rotate(0.0, center, false), rle);
+        assert_eq!(rle.rotate(std::f64::consts::FRAC_PI_2, center, true), rle.rotate90());
+        assert_eq!(rle.rotate(std::f64::consts::PI, center, false), rle.rotate180());
+        assert_eq!(rle.rotate(-std::f64::consts::FRAC_PI_2, center, true), rle.rotate270());
+        // compare with sampling of every pixel
+        for &angle in &[0.3, -0.7, 1.2, 2.5, 4.0] {
+            for &expand in &[false, true] {
+                for &center in &[(3.5, 2.5), (0.0, 0.0), (5.2, 1.7)] {
+                    let map = InverseRotation::new(angle, center, rle.width(), rle.height(), expand);
+                    let rotated = rle.rotate(angle, center, expand);
+                    assert_eq!((rotated.width(), rotated.height()), (map.width, map.height));
+                    for y in 0..map.height as i64 {
+                        for x in 0..map.width as i64 {
+                            let (sx, sy) = map.source(x, y);
+                            let expected = sx >= 0.0 && sy >= 0.0 && rle.contains(sx.floor() as i32, sy.floor() as i32);
+                            assert_eq!(rotated.contains(x as i32, y as i32), expected, "{} {} {} {}", angle, expand, x, y);
+                        }
+                    }
+                }
+            }
+        }
+        // expanded image of 45 degrees rotation keeps all pixels
+        let ones = RLE::ones(10, 10);
+        let rotated = ones.rotate(std::f64::consts::FRAC_PI_4, (5.0, 5.0), true);
+        assert_eq!((rotated.width(), rotated.height()), (15, 15));
+        assert!((rotated.area() as f64 - 100.0).abs() < 10.0);
+    }
+}
@@ -0,0 +1,152 @@
+//! Borrowed read-only view of runs stored outside of `RLE`.
+use super::{intersect_iter, Image, RleError, Run, RLE};
+
+/// Read-only binary image over borrowed runs, for runs kept in other buffers (for
+/// example memory mapped file) which should not be copied to `Vec` just to query them.
+/// Runs are checked once when view is created, so they are always canonical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RleView<'a> {
+    width: usize,
+    height: usize,
+    /// canonical runs
+    runs: &'a [Run],
+}
+
+/// Get runs intersecting rectangle of w columns and h rows with top left pixel (x, y),
+/// clipped to rectangle. Runs must be sorted.
+fn clipped_runs(runs: &[Run], x: i32, y: i32, w: usize, h: usize) -> Vec<Run> {
+    let (x_end, y_end) = (x as i64 + w as i64 - 1, y as i64 + h as i64 - 1);
+    let start = runs.partition_point(|run| run.y < y);
+    runs[start..].iter()
+        .take_while(|run| run.y as i64 <= y_end)
+        .filter(|run| run.x_end >= x && run.x_start as i64 <= x_end)
+        .map(|run| Run::new(run.x_start.max(x), (run.x_end as i64).min(x_end) as i32, run.y))
+        .collect()
+}
+
+impl<'a> RleView<'a> {
+    /// Create view of runs of width x height image. Returns `RleError::InvalidRun`
+    /// with first run which is empty, outside of image, out of order or overlapping or
+    /// touching previous run.
+    pub fn try_new(width: usize, height: usize, runs: &'a [Run]) -> Result<Self, RleError> {
+        for (i, run) in runs.iter().enumerate() {
+            let ordered = i == 0 || Run::is_normalized(&runs[i - 1..=i]);
+            if !ordered || !RLE::run_in_bounds(width, height, run) {
+                return Err(RleError::InvalidRun(*run));
+            }
+        }
+        Ok(RleView { width, height, runs })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn runs(&self) -> &'a [Run] {
+        self.runs
+    }
+
+    /// Number of pixels set to 1.
+    pub fn area(&self) -> usize {
+        self.runs.iter().map(|run| (run.x_end - run.x_start + 1) as usize).sum()
+    }
+
+    /// Check if no pixel is set to 1.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Get runs of row y, found with binary search.
+    pub fn row_runs(&self, y: i32) -> &'a [Run] {
+        let start = self.runs.partition_point(|run| run.y < y);
+        let row = &self.runs[start..];
+        &row[..row.partition_point(|run| run.y == y)]
+    }
+
+    /// Check if pixel (x, y) is set to 1, false for pixels outside of image.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let row = self.row_runs(y);
+        let i = row.partition_point(|run| run.x_start <= x);
+        i > 0 && row[i - 1].x_end >= x
+    }
+
+    /// Get runs intersecting rectangle of w columns and h rows with top left pixel
+    /// (x, y), clipped to rectangle. Coordinates are not changed (not relative to
+    /// rectangle), rows outside of rectangle are found with binary search.
+    pub fn runs_in_rect(&self, x: i32, y: i32, w: usize, h: usize) -> Vec<Run> {
+        clipped_runs(self.runs, x, y, w, h)
+    }
+
+    /// Number of pixels set in both self and other.
+    /// If dimensions of self and other are not same this method will panic.
+    pub fn intersection_area(&self, other: &RleView<'_>) -> usize {
+        assert!(self.width == other.width && self.height == other.height);
+        intersect_iter(self.runs.iter().copied(), other.runs.iter().copied())
+            .map(|run| (run.x_end - run.x_start + 1) as usize)
+            .sum()
+    }
+
+    /// Intersection over union of self and other, 0 if both are empty.
+    /// If dimensions of self and other are not same this method will panic.
+    pub fn iou(&self, other: &RleView<'_>) -> f64 {
+        let intersection = self.intersection_area(other);
+        let union = self.area() + other.area() - intersection;
+        if union == 0 {
+            0.0
+        } else {
+            intersection as f64 / union as f64
+        }
+    }
+
+    /// Decode to binary image, pixels set to 1 get pixel_val (see `RLE::to_image`).
+    pub fn to_image(&self, pixel_val: u8) -> Image {
+        self.to_rle().to_image(pixel_val)
+    }
+
+    /// Copy runs to owned `RLE`.
+    pub fn to_rle(&self) -> RLE {
+        RLE { width: self.width, height: self.height, runs: self.runs.to_vec() }
+    }
+}
+
+impl RLE {
+    /// Get runs intersecting rectangle of w columns and h rows with top left pixel
+    /// (x, y), clipped to rectangle and image (see `RleView::runs_in_rect`).
+    pub fn runs_in_rect(&self, x: i32, y: i32, w: usize, h: usize) -> Vec<Run> {
+        clipped_runs(&self.canonical_runs(), x, y, w, h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_test() {
+        let runs = [Run::new(1, 3, 0), Run::new(0, 0, 1), Run::new(2, 4, 1), Run::new(4, 4, 2)];
+        let view = RleView::try_new(5, 3, &runs).unwrap();
+        let rle = RLE::from_runs(5, 3, runs.to_vec());
+        assert_eq!(view.area(), rle.area());
+        assert!(view.contains(2, 1) && !view.contains(1, 1) && !view.contains(-1, 1));
+        assert_eq!(view.row_runs(1), &runs[1..3]);
+        assert_eq!(view.to_image(1), rle.to_image(1));
+        assert_eq!(view.to_rle(), rle);
+        assert_eq!(view.runs_in_rect(1, 1, 3, 5), vec![Run::new(2, 3, 1)]);
+        assert_eq!(view.runs_in_rect(-2, 0, 3, 2), vec![Run::new(0, 0, 1)]);
+        assert_eq!(rle.runs_in_rect(2, 0, 9, 9), vec![Run::new(2, 3, 0), Run::new(2, 4, 1), Run::new(4, 4, 2)]);
+
+        let other_runs = [Run::new(0, 4, 0)];
+        let other = RleView::try_new(5, 3, &other_runs).unwrap();
+        assert_eq!(view.intersection_area(&other), 3);
+        assert_eq!(view.iou(&other), 3.0 / 10.0);
+
+        let touching = [Run::new(0, 1, 0), Run::new(2, 3, 0)];
+        assert_eq!(RleView::try_new(5, 3, &touching), Err(RleError::InvalidRun(touching[1])));
+        let outside = [Run::new(0, 5, 0)];
+        assert_eq!(RleView::try_new(5, 3, &outside), Err(RleError::InvalidRun(outside[0])));
+    }
+}